@@ -0,0 +1,52 @@
+//! Measures encoding and verifying HS256 tokens, where the cost of the HMAC
+//! itself is small enough that allocations and base64/JSON work dominate.
+//!
+//! Run with `cargo bench --bench hmac`. To compare a change against a
+//! baseline, run `cargo bench --bench hmac -- --save-baseline before` first
+//! and then `cargo bench --bench hmac -- --baseline before` with the change.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_json::json;
+
+use jsonwebtokens as jwt;
+use jwt::{Algorithm, AlgorithmID, PreparedVerifier, Verifier};
+
+fn hs256(c: &mut Criterion) {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": alg.name(), "typ": "JWT" });
+    let claims = json!({
+        "iss": "http://some-auth-service.com",
+        "sub": "user-123",
+        "aud": "application_id",
+        "exp": 4102444800u64,
+    });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create()
+        .issuer("http://some-auth-service.com")
+        .audience("application_id")
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("HS256");
+    group.bench_function("encode", |b| {
+        b.iter(|| black_box(jwt::encode(&header, &claims, &alg).unwrap()))
+    });
+    group.bench_function("verify", |b| {
+        b.iter(|| black_box(verifier.verify_for_time(&token, &alg, 0).unwrap()))
+    });
+
+    let prepared_alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let mut prepared = PreparedVerifier::new(verifier.clone(), prepared_alg);
+    group.bench_function("verify (PreparedVerifier)", |b| {
+        b.iter(|| black_box(prepared.verify_for_time(&token, 0).unwrap()))
+    });
+
+    let message = token.rsplit_once('.').unwrap().0;
+    group.bench_function("sign (Algorithm::sign)", |b| {
+        b.iter(|| black_box(alg.sign(message).unwrap()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, hs256);
+criterion_main!(benches);
@@ -0,0 +1,59 @@
+//! Compares verifying tokens with a reused `Algorithm` against constructing a
+//! new `Algorithm` (and so re-parsing the PEM key or recomputing the HMAC key
+//! state) for each token.
+//!
+//! Run with `cargo bench --bench verify`. To compare a change against a
+//! baseline, run `cargo bench --bench verify -- --save-baseline before` first
+//! and then `cargo bench --bench verify -- --baseline before` with the change.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_json::json;
+
+use jsonwebtokens as jwt;
+use jwt::{Algorithm, AlgorithmID, Verifier};
+
+fn rsa_verify(c: &mut Criterion) {
+    let privkey_pem = include_bytes!("../tests/rsa/private_rsa_key_pkcs1.pem");
+    let pubkey_pem = include_bytes!("../tests/rsa/public_rsa_key_pkcs1.pem");
+
+    let signer = Algorithm::new_rsa_pem_signer(AlgorithmID::RS256, privkey_pem).unwrap();
+    let header = json!({ "alg": signer.name() });
+    let claims = json!({ "sub": "user-123", "exp": 4102444800u64 });
+    let token = jwt::encode(&header, &claims, &signer).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+
+    let mut group = c.benchmark_group("RS256 verify");
+    group.bench_function("Algorithm parsed per token", |b| {
+        b.iter(|| {
+            let alg = Algorithm::new_rsa_pem_verifier(AlgorithmID::RS256, pubkey_pem).unwrap();
+            black_box(verifier.verify_for_time(&token, &alg, 0).unwrap())
+        })
+    });
+    let alg = Algorithm::new_rsa_pem_verifier(AlgorithmID::RS256, pubkey_pem).unwrap();
+    group.bench_function("Algorithm reused", |b| {
+        b.iter(|| black_box(verifier.verify_for_time(&token, &alg, 0).unwrap()))
+    });
+    group.finish();
+}
+
+fn hmac_verify(c: &mut Criterion) {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let claims = json!({ "sub": "user-123", "exp": 4102444800u64 });
+    let token = jwt::encode(&json!({ "alg": alg.name() }), &claims, &alg).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+
+    let mut group = c.benchmark_group("HS256 verify");
+    group.bench_function("Algorithm constructed per token", |b| {
+        b.iter(|| {
+            let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+            black_box(verifier.verify_for_time(&token, &alg, 0).unwrap())
+        })
+    });
+    group.bench_function("Algorithm reused", |b| {
+        b.iter(|| black_box(verifier.verify_for_time(&token, &alg, 0).unwrap()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, rsa_verify, hmac_verify);
+criterion_main!(benches);
@@ -0,0 +1,39 @@
+//! Feeds arbitrary bytes into the decoding and verification paths, which
+//! must return an `Error` for any malformed input instead of panicking
+//!
+//! Run with `cargo +nightly fuzz run decode` from the repository root.
+
+#![no_main]
+
+use jsonwebtokens as jwt;
+use jwt::{Algorithm, AlgorithmID, Verifier};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "fuzzing secret, 32 bytes or more").unwrap();
+    let strict = Verifier::create()
+        .strip_bearer_prefix()
+        .require_typ("JWT")
+        .audience("fuzz")
+        .claim_equals("sub", "fuzz")
+        .build()
+        .unwrap();
+    let lenient = Verifier::create().leeway(u32::MAX).build().unwrap();
+
+    let _ = strict.verify_for_time_bytes(data, &alg, 0);
+    let _ = lenient.verify_for_time_bytes(data, &alg, u64::MAX);
+
+    if let Ok(token) = std::str::from_utf8(data) {
+        let _ = jwt::decode_unverified(token);
+        let _ = strict.verify_for_time(token, &alg, 1_600_000_000);
+
+        // Also reach the claim checks, with a valid signature over the fuzzed segments
+        if let Some((message, _)) = token.rsplit_once('.') {
+            if let Ok(signature) = alg.sign(message) {
+                let signed = format!("{message}.{signature}");
+                let _ = strict.verify_for_time(&signed, &alg, 1_600_000_000);
+                let _ = lenient.verify_for_time(&signed, &alg, 0);
+            }
+        }
+    }
+});
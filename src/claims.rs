@@ -0,0 +1,153 @@
+use serde_json::map::Map;
+use serde_json::value::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+
+/// Builds the claims for a token to be encoded, with a builder-pattern API
+///
+/// For example:
+/// ```rust
+/// # use serde_json::json;
+/// # use std::time::Duration;
+/// # use jsonwebtokens as jwt;
+/// # use jwt::{Algorithm, AlgorithmID, ClaimsBuilder};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret")?;
+/// let header = json!({ "alg": alg.name() });
+/// let claims = ClaimsBuilder::new()
+///     .issuer("http://some-auth-service.com")
+///     .subject("user-123")
+///     .audience("application_id")
+///     .issued_now()
+///     .expires_in(Duration::from_secs(3600))
+///     .claim("scope", "read write")
+///     .build();
+/// let token = jwt::encode(&header, &claims, &alg)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Times are encoded as integer unix epoch timestamps (in seconds), relative
+/// to the time that `build()` is called.
+pub struct ClaimsBuilder {
+    claims: Map<String, Value>,
+    expires_in: Option<Duration>,
+    not_before_in: Option<Duration>,
+    issued_now: bool,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for ClaimsBuilder {
+    fn default() -> Self {
+        ClaimsBuilder::new()
+    }
+}
+
+impl ClaimsBuilder {
+    pub fn new() -> ClaimsBuilder {
+        ClaimsBuilder {
+            claims: Map::new(),
+            expires_in: None,
+            not_before_in: None,
+            issued_now: false,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sets the 'iss' claim
+    pub fn issuer(&mut self, issuer: impl Into<String>) -> &mut Self {
+        self.claim("iss", issuer.into())
+    }
+
+    /// Sets the 'sub' claim
+    pub fn subject(&mut self, sub: impl Into<String>) -> &mut Self {
+        self.claim("sub", sub.into())
+    }
+
+    /// Sets the 'aud' claim to a single audience
+    pub fn audience(&mut self, aud: impl Into<String>) -> &mut Self {
+        self.claim("aud", aud.into())
+    }
+
+    /// Sets the 'aud' claim to an array of audiences
+    pub fn audiences(&mut self, auds: &[&str]) -> &mut Self {
+        self.claim("aud", auds.to_vec())
+    }
+
+    /// Sets the 'exp' claim to expire this long after the claims are built
+    ///
+    /// The time saturates at `u64::MAX`, so `Duration::MAX` never expires
+    pub fn expires_in(&mut self, duration: Duration) -> &mut Self {
+        self.expires_in = Some(duration);
+        self
+    }
+
+    /// Sets the 'exp' claim to a specific unix epoch timestamp
+    pub fn expires_at(&mut self, exp: u64) -> &mut Self {
+        self.expires_in = None;
+        self.claim("exp", exp)
+    }
+
+    /// Sets the 'nbf' claim to a specific unix epoch timestamp
+    pub fn not_before(&mut self, nbf: u64) -> &mut Self {
+        self.not_before_in = None;
+        self.claim("nbf", nbf)
+    }
+
+    /// Sets the 'nbf' claim so the token isn't valid until this long after the
+    /// claims are built
+    pub fn not_before_in(&mut self, duration: Duration) -> &mut Self {
+        self.not_before_in = Some(duration);
+        self
+    }
+
+    /// Sets the 'iat' claim to the time the claims are built
+    pub fn issued_now(&mut self) -> &mut Self {
+        self.issued_now = true;
+        self
+    }
+
+    /// Sets the 'jti' claim
+    pub fn jwt_id(&mut self, jti: impl Into<String>) -> &mut Self {
+        self.claim("jti", jti.into())
+    }
+
+    /// Sets any (custom) claim to the given JSON value
+    pub fn claim(&mut self, claim: impl Into<String>, value: impl Into<Value>) -> &mut Self {
+        self.claims.insert(claim.into(), value.into());
+        self
+    }
+
+    /// Sets the `Clock` used to get the current time (Defaults to `SystemClock`)
+    pub fn clock(&mut self, clock: impl Clock + 'static) -> &mut Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Build the final claims JSON object
+    pub fn build(&self) -> Value {
+        let mut claims = self.claims.clone();
+        let now = self.clock.now();
+
+        if self.issued_now {
+            claims.insert("iat".to_owned(), now.into());
+        }
+        // A duration like `Duration::MAX` (meaning "effectively never") saturates
+        if let Some(duration) = self.not_before_in {
+            claims.insert(
+                "nbf".to_owned(),
+                now.saturating_add(duration.as_secs()).into(),
+            );
+        }
+        if let Some(duration) = self.expires_in {
+            claims.insert(
+                "exp".to_owned(),
+                now.saturating_add(duration.as_secs()).into(),
+            );
+        }
+
+        Value::Object(claims)
+    }
+}
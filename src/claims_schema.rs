@@ -0,0 +1,70 @@
+//! Validates claims against a JSON Schema, for `VerifierBuilder::claims_schema()`
+//!
+//! This is a thin wrapper around the `jsonschema` crate, which supports the
+//! complete specification (defaulting to draft 2020-12 unless the schema
+//! declares its `$schema`). Formats are validated as assertions, so that a
+//! `format` constraint is never silently ignored, and remote `$ref`s can't be
+//! resolved.
+
+use alloc::sync::Arc;
+use core::fmt;
+
+use jsonschema::{ValidationError, Validator};
+use serde_json::value::Value;
+
+use crate::error::{Error, ErrorDetails};
+use crate::prelude::*;
+
+/// A compiled JSON Schema
+#[derive(Clone)]
+pub(crate) struct ClaimsSchema {
+    source: Value,
+    validator: Arc<Validator>,
+}
+impl Eq for ClaimsSchema {}
+impl PartialEq for ClaimsSchema {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+impl fmt::Debug for ClaimsSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ClaimsSchema({})", self.source)
+    }
+}
+
+impl ClaimsSchema {
+    /// Compiles a schema, returning `Error::InvalidInput` if it's invalid
+    pub(crate) fn compile(schema: &Value) -> Result<ClaimsSchema, Error> {
+        let validator = jsonschema::options()
+            .should_validate_formats(true)
+            .build(schema)
+            .map_err(|e| {
+                Error::InvalidInput(ErrorDetails::new(format!("Invalid claims schema: {e}")))
+            })?;
+        Ok(ClaimsSchema {
+            source: schema.clone(),
+            validator: Arc::new(validator),
+        })
+    }
+
+    /// Validates the claims, returning `Error::SchemaValidation` with every failure
+    pub(crate) fn validate(&self, claims: &Value) -> Result<(), Error> {
+        let failures: Vec<String> = self.validator.iter_errors(claims).map(describe).collect();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::SchemaValidation(failures))
+        }
+    }
+}
+
+/// Describes a failure as "{path}: {message}", where the path is the failing
+/// value's (RFC 6901 escaped) JSON Pointer, or only the message for the claims
+/// object itself
+fn describe(error: ValidationError<'_>) -> String {
+    match error.instance_path().as_str() {
+        "" => error.to_string(),
+        path => format!("{path}: {error}"),
+    }
+}
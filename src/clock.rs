@@ -0,0 +1,30 @@
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
+/// A source of the current time, for checking time-based claims
+///
+/// A `Verifier` uses a `SystemClock` by default but a custom `Clock` can be
+/// given via `VerifierBuilder::clock()`, such as to test with a fixed time.
+/// Without the `std` feature there's no `SystemClock` and a `Clock` must be given.
+pub trait Clock: Send + Sync {
+    /// Returns the current time as a unix epoch timestamp (in seconds)
+    fn now(&self) -> u64;
+}
+
+/// A `Clock` that reads the current system time
+///
+/// This requires the `std` feature
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        // A system clock set before the epoch is treated as being at the epoch
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
@@ -1,37 +1,25 @@
-use ring::signature;
+use core::fmt;
+use core::str::FromStr;
 use serde::{Deserialize, Serialize};
-use simple_asn1::BigUint;
-use std::fmt;
-use std::str::FromStr;
+use zeroize::Zeroizing;
 
+use crate::crypto::backend::{Backend, HmacKey, Selected};
 use crate::crypto::*;
-use crate::error::{Error, ErrorDetails};
+use crate::error::{Error, ErrorDetails, TokenSegment};
+#[cfg(feature = "std")]
 use crate::pem::decoder::PemEncodedKey;
 use crate::raw::*;
 
-impl From<AlgorithmID> for &dyn signature::VerificationAlgorithm {
-    fn from(alg: AlgorithmID) -> Self {
-        match alg {
-            AlgorithmID::ES256 => &signature::ECDSA_P256_SHA256_FIXED,
-            AlgorithmID::ES384 => &signature::ECDSA_P384_SHA384_FIXED,
-
-            AlgorithmID::RS256 => &signature::RSA_PKCS1_2048_8192_SHA256,
-            AlgorithmID::RS384 => &signature::RSA_PKCS1_2048_8192_SHA384,
-            AlgorithmID::RS512 => &signature::RSA_PKCS1_2048_8192_SHA512,
-            AlgorithmID::PS256 => &signature::RSA_PSS_2048_8192_SHA256,
-            AlgorithmID::PS384 => &signature::RSA_PSS_2048_8192_SHA384,
-            AlgorithmID::PS512 => &signature::RSA_PSS_2048_8192_SHA512,
-
-            _ => unreachable!("algorithm doesn't map to a ring signature verification algorithm"),
-        }
-    }
-}
-
 /// Uniquely identifies a specific cryptographic algorithm for signing or verifying tokens
+///
+/// Its `Display` and `FromStr` implementations use the JOSE name (such as `"RS256"`),
+/// and parsing an unknown name returns `Error::UnsupportedAlgorithm`. It's also
+/// (de)serialized as its JOSE name, such as for configuration files.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum AlgorithmID {
     /// Unsecured JWT
+    #[serde(rename = "none", alias = "NONE")]
     NONE,
 
     /// HMAC using SHA-256
@@ -45,6 +33,16 @@ pub enum AlgorithmID {
     ES256,
     /// ECDSA using SHA-384
     ES384,
+    /// ECDSA using P-521 and SHA-512 (only with the `es512` feature, otherwise
+    /// constructing an `Algorithm` returns `Error::UnsupportedAlgorithm`)
+    ES512,
+    /// ECDSA using secp256k1 and SHA-256, as defined by RFC 8812 (only with the
+    /// `es256k` feature, otherwise constructing an `Algorithm` returns
+    /// `Error::UnsupportedAlgorithm`)
+    ES256K,
+
+    /// EdDSA using Ed25519
+    EdDSA,
 
     /// RSASSA-PKCS1-v1_5 using SHA-256
     RS256,
@@ -53,11 +51,11 @@ pub enum AlgorithmID {
     /// RSASSA-PKCS1-v1_5 using SHA-512
     RS512,
 
-    /// RSASSA-PSS using SHA-256
+    /// RSASSA-PSS using SHA-256 (and MGF1 with SHA-256, with a 32 byte salt)
     PS256,
-    /// RSASSA-PSS using SHA-384
+    /// RSASSA-PSS using SHA-384 (and MGF1 with SHA-384, with a 48 byte salt)
     PS384,
-    /// RSASSA-PSS using SHA-512
+    /// RSASSA-PSS using SHA-512 (and MGF1 with SHA-512, with a 64 byte salt)
     PS512,
 }
 
@@ -72,6 +70,10 @@ impl From<AlgorithmID> for &'static str {
 
             AlgorithmID::ES256 => "ES256",
             AlgorithmID::ES384 => "ES384",
+            AlgorithmID::ES512 => "ES512",
+            AlgorithmID::ES256K => "ES256K",
+
+            AlgorithmID::EdDSA => "EdDSA",
 
             AlgorithmID::RS256 => "RS256",
             AlgorithmID::RS384 => "RS384",
@@ -84,7 +86,7 @@ impl From<AlgorithmID> for &'static str {
     }
 }
 
-impl std::fmt::Display for AlgorithmID {
+impl fmt::Display for AlgorithmID {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let alg = *self;
         let s: &'static str = alg.into();
@@ -104,6 +106,10 @@ impl FromStr for AlgorithmID {
 
             "ES256" => Ok(AlgorithmID::ES256),
             "ES384" => Ok(AlgorithmID::ES384),
+            "ES512" => Ok(AlgorithmID::ES512),
+            "ES256K" => Ok(AlgorithmID::ES256K),
+
+            "EdDSA" => Ok(AlgorithmID::EdDSA),
 
             "RS256" => Ok(AlgorithmID::RS256),
             "RS384" => Ok(AlgorithmID::RS384),
@@ -113,9 +119,7 @@ impl FromStr for AlgorithmID {
             "PS384" => Ok(AlgorithmID::PS384),
             "PS512" => Ok(AlgorithmID::PS512),
 
-            _ => Err(Error::InvalidInput(ErrorDetails::new(format!(
-                "Unknown algorithm name {s}"
-            )))),
+            _ => Err(Error::UnsupportedAlgorithm(s.to_owned())),
         }
     }
 }
@@ -140,6 +144,10 @@ mod tests {
 
         assert!(AlgorithmID::from_str("ES256").is_ok());
         assert!(AlgorithmID::from_str("ES384").is_ok());
+        assert!(AlgorithmID::from_str("ES512").is_ok());
+        assert!(AlgorithmID::from_str("ES256K").is_ok());
+
+        assert!(AlgorithmID::from_str("EdDSA").is_ok());
 
         assert!(AlgorithmID::from_str("RS256").is_ok());
         assert!(AlgorithmID::from_str("RS384").is_ok());
@@ -151,6 +159,42 @@ mod tests {
 
         assert!(AlgorithmID::from_str("").is_err());
     }
+
+    #[test]
+    fn algorithm_enum_display_round_trip() {
+        for id in [
+            AlgorithmID::NONE,
+            AlgorithmID::HS256,
+            AlgorithmID::HS384,
+            AlgorithmID::HS512,
+            AlgorithmID::ES256,
+            AlgorithmID::ES384,
+            AlgorithmID::ES512,
+            AlgorithmID::ES256K,
+            AlgorithmID::EdDSA,
+            AlgorithmID::RS256,
+            AlgorithmID::RS384,
+            AlgorithmID::RS512,
+            AlgorithmID::PS256,
+            AlgorithmID::PS384,
+            AlgorithmID::PS512,
+        ] {
+            assert_eq!(AlgorithmID::from_str(&id.to_string()).unwrap(), id);
+        }
+        assert_eq!(AlgorithmID::RS256.to_string(), "RS256");
+
+        for id in [AlgorithmID::NONE, AlgorithmID::HS256, AlgorithmID::EdDSA] {
+            let json = serde_json::to_value(id).unwrap();
+            assert_eq!(json, serde_json::Value::from(id.to_string()));
+            assert_eq!(serde_json::from_value::<AlgorithmID>(json).unwrap(), id);
+        }
+        assert!(serde_json::from_str::<AlgorithmID>(r#""XS256""#).is_err());
+
+        match AlgorithmID::from_str("hs256") {
+            Err(Error::UnsupportedAlgorithm(name)) => assert_eq!(name, "hs256"),
+            other => panic!("Expected UnsupportedAlgorithm, got {other:?}"),
+        }
+    }
 }
 
 fn ensure_hmac_id(id: AlgorithmID) -> Result<(), Error> {
@@ -166,7 +210,19 @@ fn ensure_ecdsa_id(id: AlgorithmID) -> Result<(), Error> {
     match id {
         AlgorithmID::ES256 => Ok(()),
         AlgorithmID::ES384 => Ok(()),
+        AlgorithmID::ES512 => Ok(()),
+        AlgorithmID::ES256K => Ok(()),
         _ => Err(Error::AlgorithmMismatch()),
+    }?;
+    ensure_supported(id)
+}
+
+/// Checks the selected cryptography backend implements an algorithm
+fn ensure_supported(id: AlgorithmID) -> Result<(), Error> {
+    if Selected::supports(id) {
+        Ok(())
+    } else {
+        Err(Error::UnsupportedAlgorithm(id.to_string()))
     }
 }
 
@@ -183,6 +239,35 @@ fn ensure_rsa_id(id: AlgorithmID) -> Result<(), Error> {
     }
 }
 
+/// Parses an ECDSA public key, given as an uncompressed point
+fn ecdsa_public_key(id: AlgorithmID, point: Vec<u8>) -> SecretOrKey {
+    let public_key = Selected::ecdsa_parse_public_key(id, &point);
+    SecretOrKey::EcdsaPublicKey(point, public_key)
+}
+
+/// Parses an RSA public key, given as big-endian (n, e) components
+fn rsa_public_key(n: Vec<u8>, e: Vec<u8>) -> SecretOrKey {
+    let public_key = Selected::rsa_parse_public_key(&n, &e);
+    SecretOrKey::RsaPublicKey(n, e, public_key)
+}
+
+/// The canonical JWK (as used for thumbprints) for an Ed25519 public key
+fn ed25519_canonical_jwk(public_key: &[u8]) -> String {
+    format!(
+        r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#,
+        b64_encode(public_key)
+    )
+}
+
+/// The canonical JWK (as used for thumbprints) for an RSA public key
+fn rsa_canonical_jwk(n: &[u8], e: &[u8]) -> String {
+    format!(
+        r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+        b64_encode(rsa::trim_leading_zeros(e)),
+        b64_encode(rsa::trim_leading_zeros(n))
+    )
+}
+
 /// A cryptographic function for signing or verifying a token signature
 ///
 /// An Algorithm encapsulates one function for signing or verifying tokens. A key
@@ -191,6 +276,12 @@ fn ensure_rsa_id(id: AlgorithmID) -> Result<(), Error> {
 /// immutable after construction to avoid the chance of being coerced into using
 /// the wrong algorithm to sign or verify a token at runtime.
 ///
+/// All PEM, DER and base64 decoding of a key happens when the `Algorithm` is
+/// constructed, as does computing the key state for an HMAC secret and parsing
+/// a private or public key, with whichever cryptography backend is selected (see
+/// the README). An `Algorithm` is `Send` and `Sync` so a single instance can be
+/// shared across threads, such as in an `Arc`, to verify many tokens.
+///
 /// Optionally a `kid` Key ID can be assigned to an `Algorithm` to add a strict
 /// check that a token's header must include the same `kid` value. This is useful
 /// when using an `Algorithm` to represent a single key within a JWKS key set,
@@ -221,6 +312,24 @@ impl Algorithm {
         self.kid = Some(kid.into());
     }
 
+    /// Returns whether this algorithm can sign tokens, and not only verify them
+    ///
+    /// Algorithms constructed from a public key can only be used for verifying,
+    /// while those with a secret or private key can be used for both.
+    pub fn can_sign(&self) -> bool {
+        match self.secret_or_key {
+            SecretOrKey::None
+            | SecretOrKey::Secret(..)
+            | SecretOrKey::HmacKey(_)
+            | SecretOrKey::EcdsaKeyPair(..)
+            | SecretOrKey::Ed25519KeyPair(..)
+            | SecretOrKey::RsaKeyPair(..) => true,
+            SecretOrKey::EcdsaPublicKey(..)
+            | SecretOrKey::Ed25519PublicKey(..)
+            | SecretOrKey::RsaPublicKey(..) => false,
+        }
+    }
+
     /// Returns a reference to any associated `kid` set via `set_kid()`
     pub fn kid(&self) -> Option<&str> {
         match &self.kid {
@@ -229,6 +338,73 @@ impl Algorithm {
         }
     }
 
+    /// Computes the [RFC 7638](https://tools.ietf.org/html/rfc7638) JWK thumbprint
+    /// of this algorithm's key, as base64url encoded SHA-256
+    ///
+    /// The thumbprint is a stable identifier for the key (such as for deriving a
+    /// `kid`), and for asymmetric algorithms is the same whether the `Algorithm`
+    /// was constructed from the private or the public key. Note that for HMAC
+    /// the thumbprint is a digest of the secret itself.
+    ///
+    /// Returns `Error::InvalidInput` for unsecured algorithms and for keys whose
+    /// components aren't available, such as HMAC keys from `new_hmac_from_slice()`
+    pub fn jwk_thumbprint(&self) -> Result<String, Error> {
+        // The required members of each key type, in lexicographic order and
+        // without whitespace, as canonicalized by RFC 7638. (For HMAC this
+        // contains the secret, so it's wiped afterwards)
+        let canonical_jwk = Zeroizing::new(match &self.secret_or_key {
+            SecretOrKey::Secret(secret, _) => {
+                let k = Zeroizing::new(b64_encode(secret));
+                [r#"{"k":""#, k.as_str(), r#"","kty":"oct"}"#].concat()
+            }
+            SecretOrKey::EcdsaPublicKey(point, _) => self.ec_canonical_jwk(point)?,
+            SecretOrKey::EcdsaKeyPair(key_pair, _) => {
+                self.ec_canonical_jwk(Selected::ecdsa_public_key(key_pair))?
+            }
+            SecretOrKey::Ed25519PublicKey(public_key, _) => ed25519_canonical_jwk(public_key),
+            SecretOrKey::Ed25519KeyPair(key_pair, _) => {
+                ed25519_canonical_jwk(Selected::ed25519_public_key(key_pair))
+            }
+            SecretOrKey::RsaPublicKey(n, e, _) => rsa_canonical_jwk(n, e),
+            SecretOrKey::RsaKeyPair(key_pair, _) => {
+                let (n, e) = Selected::rsa_public_components(key_pair);
+                rsa_canonical_jwk(&n, &e)
+            }
+            _ => {
+                return Err(Error::InvalidInput(ErrorDetails::new(format!(
+                    "Can't compute a JWK thumbprint for this {} key",
+                    self.id
+                ))))
+            }
+        });
+        Ok(b64_encode(&Selected::sha256(canonical_jwk.as_bytes())))
+    }
+
+    /// The canonical JWK for an EC public key, given as an uncompressed point
+    fn ec_canonical_jwk(&self, point: &[u8]) -> Result<String, Error> {
+        let crv = match self.id {
+            AlgorithmID::ES256 => "P-256",
+            AlgorithmID::ES384 => "P-384",
+            AlgorithmID::ES512 => "P-521",
+            AlgorithmID::ES256K => "secp256k1",
+            _ => unreachable!("Tried to get EC curve for a non-EC algorithm"),
+        };
+        let coordinate_len = ecdsa::coordinate_len(self.id);
+        match point.split_first() {
+            Some((0x04, coordinates)) if coordinates.len() == 2 * coordinate_len => {
+                let (x, y) = coordinates.split_at(coordinate_len);
+                Ok(format!(
+                    r#"{{"crv":"{crv}","kty":"EC","x":"{}","y":"{}"}}"#,
+                    b64_encode(x),
+                    b64_encode(y)
+                ))
+            }
+            _ => Err(Error::InvalidInput(ErrorDetails::new(
+                "Expected an uncompressed ECDSA public key",
+            ))),
+        }
+    }
+
     /// Constructs a NOP algorithm for use with unsecured (unsigned) tokens
     pub fn new_unsecured() -> Result<Self, Error> {
         Ok(Algorithm {
@@ -247,13 +423,57 @@ impl Algorithm {
         Ok(Algorithm {
             id,
             kid: None,
-            secret_or_key: SecretOrKey::Secret(secret.into()),
+            secret_or_key: hmac::secret_key(id, Zeroizing::new(secret.into())),
+        })
+    }
+
+    /// Constructs a symmetric HMAC algorithm based on a borrowed secret, without
+    /// copying it
+    ///
+    /// Instead of also keeping its own copy of the secret (like `new_hmac()`), only
+    /// the HMAC key state is computed, which avoids an allocation for services that
+    /// keep their secrets in a long-lived buffer. The caller remains responsible for
+    /// wiping their copy of the secret.
+    ///
+    /// This algorithm may be used for signing and/or verifying signatures
+    pub fn new_hmac_from_slice(id: AlgorithmID, secret: &[u8]) -> Result<Self, Error> {
+        ensure_hmac_id(id)?;
+
+        Ok(Algorithm {
+            id,
+            kid: None,
+            secret_or_key: SecretOrKey::HmacKey(Selected::hmac_key(id, secret)),
+        })
+    }
+
+    /// Constructs a symmetric HMAC algorithm based on a given secret, which must be
+    /// at least as long as the algorithm's hash output
+    ///
+    /// As required by [RFC 7518](https://tools.ietf.org/html/rfc7518#section-3.2) this
+    /// rejects secrets shorter than 32 bytes for HS256, 48 bytes for HS384 or 64 bytes
+    /// for HS512 with `Error::WeakKey`. (`new_hmac()` doesn't check the secret's length,
+    /// for interoperability with legacy systems)
+    ///
+    /// This algorithm may be used for signing and/or verifying signatures
+    pub fn new_hmac_strict(id: AlgorithmID, secret: impl Into<Vec<u8>>) -> Result<Self, Error> {
+        ensure_hmac_id(id)?;
+
+        let secret = Zeroizing::new(secret.into());
+        hmac::ensure_secret_len(id, &secret)?;
+
+        Ok(Algorithm {
+            id,
+            kid: None,
+            secret_or_key: hmac::secret_key(id, secret),
         })
     }
 
     /// Constructs a symmetric HMAC algorithm based on a given base64 secret
     ///
-    /// This is a convenience api in case the secret you're using is base64 encoded
+    /// This is a convenience api in case the secret you're using is base64 encoded,
+    /// such as when read from an environment variable or config file. The secret
+    /// must use the URL-safe base64 alphabet without padding (like JWK 'k' values),
+    /// otherwise `Error::InvalidInput` is returned, with the base64 error as its source.
     ///
     /// This algorithm may be used for signing and/or verifying signatures
     pub fn new_hmac_b64(id: AlgorithmID, secret: impl AsRef<str>) -> Result<Self, Error> {
@@ -262,38 +482,33 @@ impl Algorithm {
         Ok(Algorithm {
             id,
             kid: None,
-            secret_or_key: SecretOrKey::Secret(b64_decode(secret.as_ref())?),
+            secret_or_key: hmac::secret_key(id, Zeroizing::new(b64_decode(secret.as_ref())?)),
         })
     }
 
     /// Constructs an ECDSA algorithm based on a PEM format private key
     ///
     /// This algorithm may only be used for signing tokens
+    #[cfg(feature = "std")]
     pub fn new_ecdsa_pem_signer(id: AlgorithmID, key: &[u8]) -> Result<Self, Error> {
         ensure_ecdsa_id(id)?;
 
-        let ring_alg = id.into();
         let pem_key = PemEncodedKey::new(key)?;
-        let signing_key =
-            signature::EcdsaKeyPair::from_pkcs8(ring_alg, pem_key.as_ec_private_key()?).map_err(
-                |e| {
-                    Error::InvalidInput(ErrorDetails::map(
-                        "Failed to create ECDSA key pair for signing",
-                        Box::new(e),
-                    ))
-                },
-            )?;
+        let signing_key = Selected::ecdsa_key_pair(id, pem_key.as_ec_private_key()?)?;
+        let public_key =
+            Selected::ecdsa_parse_public_key(id, Selected::ecdsa_public_key(&signing_key));
 
         Ok(Algorithm {
             id,
             kid: None,
-            secret_or_key: SecretOrKey::EcdsaKeyPair(Box::from(signing_key)),
+            secret_or_key: SecretOrKey::EcdsaKeyPair(signing_key, public_key),
         })
     }
 
     /// Constructs an ECDSA algorithm based on a PEM format public key
     ///
     /// This algorithm may only be used for verifying tokens
+    #[cfg(feature = "std")]
     pub fn new_ecdsa_pem_verifier(id: AlgorithmID, key: &[u8]) -> Result<Self, Error> {
         ensure_ecdsa_id(id)?;
 
@@ -303,45 +518,150 @@ impl Algorithm {
         Ok(Algorithm {
             id,
             kid: None,
-            secret_or_key: SecretOrKey::EcdsaUnparsedKey(ec_pub_key.to_vec()),
+            secret_or_key: ecdsa_public_key(id, ec_pub_key.to_vec()),
+        })
+    }
+
+    /// Constructs an ECDSA algorithm based on (base64 encoded) x and y coordinates
+    ///
+    /// In some situations (such as JWKS key sets), a public EC key may be
+    /// described in terms of the coordinates of a point on the curve.
+    ///
+    /// This algorithm may only be used for verifying tokens
+    pub fn new_ecdsa_x_y_b64_verifier(
+        id: AlgorithmID,
+        x_b64: &str,
+        y_b64: &str,
+    ) -> Result<Self, Error> {
+        ensure_ecdsa_id(id)?;
+
+        let x = b64_decode(x_b64)?;
+        let y = b64_decode(y_b64)?;
+        let coordinate_len = ecdsa::coordinate_len(id);
+        if x.len() != coordinate_len || y.len() != coordinate_len {
+            return Err(Error::InvalidInput(ErrorDetails::new(format!(
+                "Expected {coordinate_len} byte ECDSA coordinates for {id}"
+            ))));
+        }
+
+        // Uncompressed point format
+        let mut point = Vec::with_capacity(1 + 2 * coordinate_len);
+        point.push(0x04);
+        point.extend_from_slice(&x);
+        point.extend_from_slice(&y);
+
+        Ok(Algorithm {
+            id,
+            kid: None,
+            secret_or_key: ecdsa_public_key(id, point),
+        })
+    }
+
+    /// Constructs an EdDSA algorithm based on a 32 byte Ed25519 private key seed
+    ///
+    /// This algorithm may only be used for signing tokens
+    pub fn new_ed25519_signer(seed: &[u8]) -> Result<Self, Error> {
+        let key_pair = Selected::ed25519_key_pair(seed)?;
+        let public_key =
+            Selected::ed25519_parse_public_key(Selected::ed25519_public_key(&key_pair));
+
+        Ok(Algorithm {
+            id: AlgorithmID::EdDSA,
+            kid: None,
+            secret_or_key: SecretOrKey::Ed25519KeyPair(key_pair, public_key),
+        })
+    }
+
+    /// Constructs an EdDSA algorithm based on a 32 byte Ed25519 public key
+    ///
+    /// This algorithm may only be used for verifying tokens
+    pub fn new_ed25519_verifier(key: &[u8]) -> Result<Self, Error> {
+        if key.len() != eddsa::KEY_LEN {
+            return Err(Error::InvalidInput(ErrorDetails::new(format!(
+                "Expected a {} byte Ed25519 public key, not {} bytes",
+                eddsa::KEY_LEN,
+                key.len()
+            ))));
+        }
+
+        Ok(Algorithm {
+            id: AlgorithmID::EdDSA,
+            kid: None,
+            secret_or_key: SecretOrKey::Ed25519PublicKey(
+                key.to_vec(),
+                Selected::ed25519_parse_public_key(key),
+            ),
         })
     }
 
     /// Constructs an RSA algorithm based on a PEM format private key
     ///
+    /// Keys with a modulus smaller than 2048 bits are rejected
+    ///
     /// This algorithm may only be used for signing tokens
+    #[cfg(feature = "std")]
     pub fn new_rsa_pem_signer(id: AlgorithmID, key: &[u8]) -> Result<Self, Error> {
         ensure_rsa_id(id)?;
+        Self::new_rsa_signer(id, &PemEncodedKey::new(key)?)
+    }
 
-        let pem_key = PemEncodedKey::new(key)?;
-        let key_pair =
-            signature::RsaKeyPair::from_der(pem_key.as_rsa_private_key()?).map_err(|e| {
-                Error::InvalidInput(ErrorDetails::map(
-                    "Failed to create RSA key for signing",
-                    Box::new(e),
-                ))
-            })?;
+    /// Constructs an RSA algorithm based on a DER format (PKCS#1 or PKCS#8) private key
+    ///
+    /// Keys with a modulus smaller than 2048 bits are rejected
+    ///
+    /// This algorithm may only be used for signing tokens
+    #[cfg(feature = "std")]
+    pub fn new_rsa_der_signer(id: AlgorithmID, key: &[u8]) -> Result<Self, Error> {
+        ensure_rsa_id(id)?;
+        Self::new_rsa_signer(id, &PemEncodedKey::from_rsa_der(key, true)?)
+    }
+
+    #[cfg(feature = "std")]
+    fn new_rsa_signer(id: AlgorithmID, key: &PemEncodedKey) -> Result<Self, Error> {
+        let der = key.as_rsa_private_key()?;
+        rsa::ensure_modulus_size(rsa::der_private_modulus(der)?)?;
+        let key_pair = Selected::rsa_key_pair(der)?;
+        let (n, e) = Selected::rsa_public_components(&key_pair);
+        let public_key = Selected::rsa_parse_public_key(&n, &e);
 
         Ok(Algorithm {
             id,
             kid: None,
-            secret_or_key: SecretOrKey::RsaKeyPair(Box::from(key_pair)),
+            secret_or_key: SecretOrKey::RsaKeyPair(key_pair, public_key),
         })
     }
 
     /// Constructs an RSA algorithm based on a PEM format public key
     ///
+    /// Keys with a modulus smaller than 2048 bits are rejected
+    ///
     /// This algorithm may only be used for verifying tokens
+    #[cfg(feature = "std")]
     pub fn new_rsa_pem_verifier(id: AlgorithmID, key: &[u8]) -> Result<Self, Error> {
         ensure_rsa_id(id)?;
+        Self::new_rsa_verifier(id, &PemEncodedKey::new(key)?)
+    }
 
-        let pem_key = PemEncodedKey::new(key)?;
-        let rsa_pub_key = pem_key.as_rsa_public_key()?;
+    /// Constructs an RSA algorithm based on a DER format (PKCS#1 or SPKI) public key
+    ///
+    /// Keys with a modulus smaller than 2048 bits are rejected
+    ///
+    /// This algorithm may only be used for verifying tokens
+    #[cfg(feature = "std")]
+    pub fn new_rsa_der_verifier(id: AlgorithmID, key: &[u8]) -> Result<Self, Error> {
+        ensure_rsa_id(id)?;
+        Self::new_rsa_verifier(id, &PemEncodedKey::from_rsa_der(key, false)?)
+    }
+
+    #[cfg(feature = "std")]
+    fn new_rsa_verifier(id: AlgorithmID, key: &PemEncodedKey) -> Result<Self, Error> {
+        let (n, e) = rsa::der_public_components(key.as_rsa_public_key()?)?;
+        rsa::ensure_modulus_size(&n)?;
 
         Ok(Algorithm {
             id,
             kid: None,
-            secret_or_key: SecretOrKey::RsaUnparsedKey(rsa_pub_key.to_vec()),
+            secret_or_key: rsa_public_key(n, e),
         })
     }
 
@@ -358,23 +678,33 @@ impl Algorithm {
     ) -> Result<Self, Error> {
         ensure_rsa_id(id)?;
 
-        let n = BigUint::from_bytes_be(&b64_decode(n_b64)?).to_bytes_be();
-        let e = BigUint::from_bytes_be(&b64_decode(e_b64)?).to_bytes_be();
+        let n = rsa::trim_leading_zeros(&b64_decode(n_b64)?).to_vec();
+        rsa::ensure_modulus_size(&n)?;
+        let e = rsa::trim_leading_zeros(&b64_decode(e_b64)?).to_vec();
 
         Ok(Algorithm {
             id,
             kid: None,
-            secret_or_key: SecretOrKey::RsaParameters(n, e),
+            secret_or_key: rsa_public_key(n, e),
         })
     }
 
     /// Lower-level api that can be used to verify a signature for a given message
+    ///
+    /// The signature is expected to be base64url encoded, as found in a token.
     pub fn verify(
         &self,
         kid: Option<&str>,
         message: impl AsRef<str>,
         signature: impl AsRef<str>,
     ) -> Result<(), Error> {
+        self.verify_kid(kid)?;
+        let signature = b64_decode_segment(signature.as_ref(), TokenSegment::Signature)?;
+        self.verify_bytes(message.as_ref().as_bytes(), &signature)
+    }
+
+    /// Checks a token's 'kid' matches the ID associated with this Algorithm, if any
+    pub(crate) fn verify_kid(&self, kid: Option<&str>) -> Result<(), Error> {
         // We need an Option(&str) instead of Option(String)
         let kid_matches = match &self.kid {
             Some(string) => kid == Some(string.as_ref()),
@@ -386,57 +716,115 @@ impl Algorithm {
                 kid, self.kid
             ))));
         }
+        Ok(())
+    }
+
+    /// Lower-level api that can be used to calculate a signature for a message
+    ///
+    /// The signature is returned base64url encoded, as used in a token.
+    pub fn sign(&self, message: &str) -> Result<String, Error> {
+        Ok(b64_encode(&self.sign_bytes(message.as_bytes())?))
+    }
+
+    /// Returns whether this algorithm verifies with the given (certificate's) public key
+    #[cfg(feature = "std")]
+    pub(crate) fn has_public_key(&self, key: &crate::x509::PublicKey) -> bool {
+        use crate::x509::PublicKey;
+
+        match (&self.secret_or_key, key) {
+            (SecretOrKey::EcdsaPublicKey(point, _), PublicKey::Ec(bits)) => point == bits,
+            (SecretOrKey::EcdsaKeyPair(key_pair, _), PublicKey::Ec(bits)) => {
+                Selected::ecdsa_public_key(key_pair) == bits.as_slice()
+            }
+            (SecretOrKey::Ed25519PublicKey(public_key, _), PublicKey::Ed25519(bits)) => {
+                public_key == bits
+            }
+            (SecretOrKey::Ed25519KeyPair(key_pair, _), PublicKey::Ed25519(bits)) => {
+                Selected::ed25519_public_key(key_pair) == bits.as_slice()
+            }
+            (SecretOrKey::RsaPublicKey(n, e, _), PublicKey::Rsa(cert_n, cert_e)) => {
+                rsa::trim_leading_zeros(n) == cert_n.as_slice()
+                    && rsa::trim_leading_zeros(e) == cert_e.as_slice()
+            }
+            (SecretOrKey::RsaKeyPair(key_pair, _), PublicKey::Rsa(n, e)) => {
+                Selected::rsa_public_components(key_pair) == (n.clone(), e.clone())
+            }
+            _ => false,
+        }
+    }
 
+    /// Returns the precomputed key for an HMAC algorithm, see `crypto::hmac::prepare_key()`
+    pub(crate) fn prepare_hmac_key(&self) -> Option<HmacKey> {
+        match self.id {
+            AlgorithmID::HS256 | AlgorithmID::HS384 | AlgorithmID::HS512 => {
+                hmac::prepare_key(&self.secret_or_key)
+            }
+            _ => None,
+        }
+    }
+
+    /// Lowest-level api that verifies a raw signature over arbitrary bytes
+    ///
+    /// Unlike `verify()` this doesn't deal with base64 or check a 'kid', which
+    /// can be useful for building other JWS formats (such as detached payloads)
+    /// on top of this crate. Tokens should normally be verified with a `Verifier`.
+    pub fn verify_bytes(&self, signing_input: &[u8], signature: &[u8]) -> Result<(), Error> {
         match self.id {
             AlgorithmID::NONE => {
-                if signature.as_ref() == "" {
+                if signature.is_empty() {
                     Ok(())
                 } else {
                     Err(Error::InvalidSignature())
                 }
             }
-            AlgorithmID::HS256 | AlgorithmID::HS384 | AlgorithmID::HS512 => hmac::verify(
-                self.id,
-                &self.secret_or_key,
-                message.as_ref(),
-                signature.as_ref(),
-            ),
-            AlgorithmID::ES256 | AlgorithmID::ES384 => ecdsa::verify(
-                self.id,
-                &self.secret_or_key,
-                message.as_ref(),
-                signature.as_ref(),
-            ),
+            // A degenerate, empty signature is never valid for a signing algorithm,
+            // which we check explicitly instead of relying on each backend
+            _ if signature.is_empty() => Err(Error::InvalidSignature()),
+            AlgorithmID::HS256 | AlgorithmID::HS384 | AlgorithmID::HS512 => {
+                hmac::verify(self.id, &self.secret_or_key, signing_input, signature)
+            }
+            AlgorithmID::ES256 | AlgorithmID::ES384 | AlgorithmID::ES512 | AlgorithmID::ES256K => {
+                ecdsa::verify(self.id, &self.secret_or_key, signing_input, signature)
+            }
+            AlgorithmID::EdDSA => {
+                eddsa::verify(self.id, &self.secret_or_key, signing_input, signature)
+            }
             AlgorithmID::RS256
             | AlgorithmID::RS384
             | AlgorithmID::RS512
             | AlgorithmID::PS256
             | AlgorithmID::PS384
-            | AlgorithmID::PS512 => rsa::verify(
-                self.id,
-                &self.secret_or_key,
-                message.as_ref(),
-                signature.as_ref(),
-            ),
+            | AlgorithmID::PS512 => {
+                rsa::verify(self.id, &self.secret_or_key, signing_input, signature)
+            }
         }
     }
 
-    /// Lower-level api that can be used to calculate a signature for a message
-    pub fn sign(&self, message: &str) -> Result<String, Error> {
+    /// Lowest-level api that calculates a raw signature over arbitrary bytes
+    ///
+    /// This is the counterpart to `verify_bytes()` and the signature isn't
+    /// base64 encoded. Tokens should normally be created with `encode()`.
+    ///
+    /// Returns `Error::CannotSign` if this algorithm only has a public key.
+    pub fn sign_bytes(&self, signing_input: &[u8]) -> Result<Vec<u8>, Error> {
+        if !self.can_sign() {
+            return Err(Error::CannotSign());
+        }
         match self.id {
-            AlgorithmID::NONE => Ok("".to_owned()),
+            AlgorithmID::NONE => Ok(Vec::new()),
             AlgorithmID::HS256 | AlgorithmID::HS384 | AlgorithmID::HS512 => {
-                hmac::sign(self.id, &self.secret_or_key, message)
+                hmac::sign(self.id, &self.secret_or_key, signing_input)
             }
-            AlgorithmID::ES256 | AlgorithmID::ES384 => {
-                ecdsa::sign(self.id, &self.secret_or_key, message)
+            AlgorithmID::ES256 | AlgorithmID::ES384 | AlgorithmID::ES512 | AlgorithmID::ES256K => {
+                ecdsa::sign(self.id, &self.secret_or_key, signing_input)
             }
+            AlgorithmID::EdDSA => eddsa::sign(self.id, &self.secret_or_key, signing_input),
             AlgorithmID::RS256
             | AlgorithmID::RS384
             | AlgorithmID::RS512
             | AlgorithmID::PS256
             | AlgorithmID::PS384
-            | AlgorithmID::PS512 => rsa::sign(self.id, &self.secret_or_key, message),
+            | AlgorithmID::PS512 => rsa::sign(self.id, &self.secret_or_key, signing_input),
         }
     }
 }
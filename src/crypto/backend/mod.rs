@@ -0,0 +1,220 @@
+//! The cryptography backends, selected at compile time with Cargo features
+//!
+//! `Algorithm` and the `crypto` modules only deal with key formats and JOSE
+//! details, dispatching the primitive operations to the `Selected` backend.
+//! With the `rust-crypto` feature that's `RustCrypto`, otherwise it's `Ring`
+//! (with the default `ring` feature).
+
+use core::fmt;
+
+use crate::crypto::algorithm::AlgorithmID;
+use crate::error::Error;
+use crate::prelude::*;
+
+#[cfg(feature = "ring")]
+mod ring;
+#[cfg(feature = "rust-crypto")]
+mod rust_crypto;
+
+#[cfg(not(any(feature = "ring", feature = "rust-crypto")))]
+compile_error!(
+    "jsonwebtokens needs a cryptography backend: enable the `ring` or `rust-crypto` feature"
+);
+
+#[cfg(feature = "rust-crypto")]
+pub(crate) type Selected = rust_crypto::RustCrypto;
+#[cfg(all(feature = "ring", not(feature = "rust-crypto")))]
+pub(crate) type Selected = self::ring::Ring;
+
+pub(crate) type HmacKey = <Selected as Backend>::HmacKey;
+pub(crate) type EcdsaKeyPair = <Selected as Backend>::EcdsaKeyPair;
+pub(crate) type Ed25519KeyPair = <Selected as Backend>::Ed25519KeyPair;
+pub(crate) type RsaKeyPair = <Selected as Backend>::RsaKeyPair;
+pub(crate) type EcdsaPublicKey = <Selected as Backend>::EcdsaPublicKey;
+pub(crate) type Ed25519PublicKey = <Selected as Backend>::Ed25519PublicKey;
+pub(crate) type RsaPublicKey = <Selected as Backend>::RsaPublicKey;
+
+/// The primitive operations that each backend implements
+///
+/// Keys are passed in the formats that the `crypto` modules have already
+/// decoded: PKCS#8 DER for ECDSA private keys, PKCS#1 DER for RSA private keys,
+/// uncompressed points for ECDSA public keys and big-endian (n, e) components
+/// for RSA public keys. ECDSA signatures are the fixed size r||s encoding.
+///
+/// Public keys are parsed once, when an `Algorithm` is constructed, and then
+/// reused for each signature that's verified. Parsing doesn't fail: a key that
+/// isn't valid (such as a point on another curve) is kept, but never verifies a
+/// signature, the same as with `ring`, which only checks a key while verifying.
+///
+/// Verification failures are always reported as `Error::InvalidSignature`.
+// Without `std` there's no PEM/DER parsing to construct some of the keys
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) trait Backend {
+    /// The key state for an HMAC secret, computed once
+    type HmacKey: Clone + Send + Sync + fmt::Debug;
+    /// A parsed ECDSA private key, along with its public key
+    type EcdsaKeyPair: Send + Sync + fmt::Debug;
+    /// A parsed Ed25519 private key, along with its public key
+    type Ed25519KeyPair: Send + Sync + fmt::Debug;
+    /// A parsed RSA private key, along with its public key
+    type RsaKeyPair: Send + Sync + fmt::Debug;
+    /// A parsed ECDSA public key, for the curve of the algorithm it was parsed for
+    type EcdsaPublicKey: Send + Sync + fmt::Debug;
+    /// A parsed Ed25519 public key
+    type Ed25519PublicKey: Send + Sync + fmt::Debug;
+    /// A parsed RSA public key
+    type RsaPublicKey: Send + Sync + fmt::Debug;
+
+    /// Whether the backend implements an algorithm, which is checked before
+    /// constructing an `Algorithm`, so no other function is given an unsupported one
+    fn supports(id: AlgorithmID) -> bool;
+
+    fn sha256(data: &[u8]) -> [u8; 32];
+
+    fn hmac_key(id: AlgorithmID, secret: &[u8]) -> Self::HmacKey;
+    fn hmac_sign(key: &Self::HmacKey, message: &[u8]) -> Vec<u8>;
+    /// Compares the MAC in constant time
+    fn hmac_verify(key: &Self::HmacKey, message: &[u8], mac: &[u8]) -> Result<(), Error>;
+
+    fn ecdsa_key_pair(id: AlgorithmID, pkcs8: &[u8]) -> Result<Self::EcdsaKeyPair, Error>;
+    /// The public key as an uncompressed point
+    fn ecdsa_public_key(key_pair: &Self::EcdsaKeyPair) -> &[u8];
+    fn ecdsa_sign(key_pair: &Self::EcdsaKeyPair, message: &[u8]) -> Result<Vec<u8>, Error>;
+    /// Parses a public key given as an uncompressed point
+    fn ecdsa_parse_public_key(id: AlgorithmID, point: &[u8]) -> Self::EcdsaPublicKey;
+    fn ecdsa_verify(
+        public_key: &Self::EcdsaPublicKey,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error>;
+
+    fn ed25519_key_pair(seed: &[u8]) -> Result<Self::Ed25519KeyPair, Error>;
+    fn ed25519_public_key(key_pair: &Self::Ed25519KeyPair) -> &[u8];
+    fn ed25519_sign(key_pair: &Self::Ed25519KeyPair, message: &[u8]) -> Vec<u8>;
+    fn ed25519_parse_public_key(public_key: &[u8]) -> Self::Ed25519PublicKey;
+    fn ed25519_verify(
+        public_key: &Self::Ed25519PublicKey,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error>;
+
+    fn rsa_key_pair(pkcs1: &[u8]) -> Result<Self::RsaKeyPair, Error>;
+    /// The (n, e) components of the public key, as big-endian bytes without leading zeros
+    fn rsa_public_components(key_pair: &Self::RsaKeyPair) -> (Vec<u8>, Vec<u8>);
+    fn rsa_sign(
+        id: AlgorithmID,
+        key_pair: &Self::RsaKeyPair,
+        message: &[u8],
+    ) -> Result<Vec<u8>, Error>;
+    /// Parses a public key given as big-endian (n, e) components
+    fn rsa_parse_public_key(n: &[u8], e: &[u8]) -> Self::RsaPublicKey;
+    fn rsa_verify(
+        id: AlgorithmID,
+        public_key: &Self::RsaPublicKey,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error>;
+}
+
+/// Checks that each backend verifies the other's signatures
+#[cfg(all(test, feature = "std", feature = "ring", feature = "rust-crypto"))]
+mod tests {
+    use super::ring::Ring;
+    use super::rust_crypto::RustCrypto;
+    use super::*;
+    use crate::pem::decoder::PemEncodedKey;
+
+    const MESSAGE: &[u8] = b"header.claims";
+
+    fn pem_der(pem: &[u8], f: fn(&PemEncodedKey) -> Result<&[u8], Error>) -> Vec<u8> {
+        f(&PemEncodedKey::new(pem).unwrap()).unwrap().to_vec()
+    }
+
+    #[test]
+    fn digests_and_macs_match() {
+        assert_eq!(Ring::sha256(MESSAGE), RustCrypto::sha256(MESSAGE));
+        for id in [AlgorithmID::HS256, AlgorithmID::HS384, AlgorithmID::HS512] {
+            let ring_key = Ring::hmac_key(id, b"secret");
+            let rust_crypto_key = RustCrypto::hmac_key(id, b"secret");
+            let mac = Ring::hmac_sign(&ring_key, MESSAGE);
+            assert_eq!(mac, RustCrypto::hmac_sign(&rust_crypto_key, MESSAGE));
+            RustCrypto::hmac_verify(&rust_crypto_key, MESSAGE, &mac).unwrap();
+        }
+    }
+
+    #[test]
+    fn ecdsa_interop() {
+        for (id, pem) in [
+            (
+                AlgorithmID::ES256,
+                &include_bytes!("../../../tests/ecdsa/private_ecdsa_key_jwtio_p256_pkcs8.pem")[..],
+            ),
+            (
+                AlgorithmID::ES384,
+                &include_bytes!("../../../tests/ecdsa/private_ecdsa_key_jwtio_p384_pkcs8.pem")[..],
+            ),
+        ] {
+            let pkcs8 = pem_der(pem, PemEncodedKey::as_ec_private_key);
+            let ring_pair = Ring::ecdsa_key_pair(id, &pkcs8).unwrap();
+            let rust_crypto_pair = RustCrypto::ecdsa_key_pair(id, &pkcs8).unwrap();
+            let point = Ring::ecdsa_public_key(&ring_pair);
+            assert_eq!(point, RustCrypto::ecdsa_public_key(&rust_crypto_pair));
+
+            let signature = Ring::ecdsa_sign(&ring_pair, MESSAGE).unwrap();
+            let public_key = RustCrypto::ecdsa_parse_public_key(id, point);
+            RustCrypto::ecdsa_verify(&public_key, MESSAGE, &signature).unwrap();
+            let signature = RustCrypto::ecdsa_sign(&rust_crypto_pair, MESSAGE).unwrap();
+            let public_key = Ring::ecdsa_parse_public_key(id, point);
+            Ring::ecdsa_verify(&public_key, MESSAGE, &signature).unwrap();
+        }
+    }
+
+    #[test]
+    fn ed25519_interop() {
+        let ring_pair = Ring::ed25519_key_pair(&[7; 32]).unwrap();
+        let rust_crypto_pair = RustCrypto::ed25519_key_pair(&[7; 32]).unwrap();
+        let public_key = Ring::ed25519_public_key(&ring_pair);
+        assert_eq!(
+            public_key,
+            RustCrypto::ed25519_public_key(&rust_crypto_pair)
+        );
+
+        // Ed25519 signatures are deterministic
+        let signature = Ring::ed25519_sign(&ring_pair, MESSAGE);
+        assert_eq!(
+            signature,
+            RustCrypto::ed25519_sign(&rust_crypto_pair, MESSAGE)
+        );
+        let public_key = RustCrypto::ed25519_parse_public_key(public_key);
+        RustCrypto::ed25519_verify(&public_key, MESSAGE, &signature).unwrap();
+    }
+
+    #[test]
+    fn rsa_interop() {
+        let pem = include_bytes!("../../../tests/rsa/private_rsa_key_pkcs1.pem");
+        let pkcs1 = pem_der(pem, PemEncodedKey::as_rsa_private_key);
+        let ring_pair = Ring::rsa_key_pair(&pkcs1).unwrap();
+        let rust_crypto_pair = RustCrypto::rsa_key_pair(&pkcs1).unwrap();
+        let (n, e) = Ring::rsa_public_components(&ring_pair);
+        assert_eq!(
+            (n.clone(), e.clone()),
+            RustCrypto::rsa_public_components(&rust_crypto_pair)
+        );
+
+        let ring_key = Ring::rsa_parse_public_key(&n, &e);
+        let rust_crypto_key = RustCrypto::rsa_parse_public_key(&n, &e);
+        for id in [
+            AlgorithmID::RS256,
+            AlgorithmID::RS384,
+            AlgorithmID::RS512,
+            AlgorithmID::PS256,
+            AlgorithmID::PS384,
+            AlgorithmID::PS512,
+        ] {
+            let signature = Ring::rsa_sign(id, &ring_pair, MESSAGE).unwrap();
+            RustCrypto::rsa_verify(id, &rust_crypto_key, MESSAGE, &signature).unwrap();
+            let signature = RustCrypto::rsa_sign(id, &rust_crypto_pair, MESSAGE).unwrap();
+            Ring::rsa_verify(id, &ring_key, MESSAGE, &signature).unwrap();
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! The `ring` backend
+
+use ::ring::signature::{self, KeyPair};
+use ::ring::{digest, hmac, rand};
+use core::fmt;
+
+use super::Backend;
+use crate::crypto::algorithm::AlgorithmID;
+use crate::error::{Error, ErrorDetails};
+use crate::prelude::*;
+
+/// Uses `ring`, which supports all of the algorithms in `AlgorithmID` except
+/// ES512 and ES256K
+#[derive(Debug)]
+#[cfg_attr(feature = "rust-crypto", allow(dead_code))]
+pub(crate) struct Ring;
+
+/// An ECDSA or Ed25519 public key, bound to its verification algorithm
+///
+/// ring has no type for a parsed public key, so it's checked while verifying
+pub(crate) struct PublicKey(signature::UnparsedPublicKey<Vec<u8>>);
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PublicKey").finish_non_exhaustive()
+    }
+}
+
+fn hmac_algorithm(id: AlgorithmID) -> hmac::Algorithm {
+    match id {
+        AlgorithmID::HS256 => hmac::HMAC_SHA256,
+        AlgorithmID::HS384 => hmac::HMAC_SHA384,
+        AlgorithmID::HS512 => hmac::HMAC_SHA512,
+        _ => unreachable!("Tried to map HMAC type for a non-HMAC algorithm"),
+    }
+}
+
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+fn ecdsa_signing_algorithm(id: AlgorithmID) -> &'static signature::EcdsaSigningAlgorithm {
+    match id {
+        AlgorithmID::ES256 => &signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+        AlgorithmID::ES384 => &signature::ECDSA_P384_SHA384_FIXED_SIGNING,
+        _ => unreachable!("Tried to get EC alg for a non-EC algorithm"),
+    }
+}
+
+fn ecdsa_verification_algorithm(id: AlgorithmID) -> &'static signature::EcdsaVerificationAlgorithm {
+    match id {
+        AlgorithmID::ES256 => &signature::ECDSA_P256_SHA256_FIXED,
+        AlgorithmID::ES384 => &signature::ECDSA_P384_SHA384_FIXED,
+        _ => unreachable!("Tried to get EC alg for a non-EC algorithm"),
+    }
+}
+
+fn rsa_encoding(id: AlgorithmID) -> &'static dyn signature::RsaEncoding {
+    match id {
+        AlgorithmID::RS256 => &signature::RSA_PKCS1_SHA256,
+        AlgorithmID::RS384 => &signature::RSA_PKCS1_SHA384,
+        AlgorithmID::RS512 => &signature::RSA_PKCS1_SHA512,
+        AlgorithmID::PS256 => &signature::RSA_PSS_SHA256,
+        AlgorithmID::PS384 => &signature::RSA_PSS_SHA384,
+        AlgorithmID::PS512 => &signature::RSA_PSS_SHA512,
+        _ => unreachable!("Tried to get RSA signature for a non-rsa algorithm"),
+    }
+}
+
+fn rsa_parameters(id: AlgorithmID) -> &'static signature::RsaParameters {
+    match id {
+        AlgorithmID::RS256 => &signature::RSA_PKCS1_2048_8192_SHA256,
+        AlgorithmID::RS384 => &signature::RSA_PKCS1_2048_8192_SHA384,
+        AlgorithmID::RS512 => &signature::RSA_PKCS1_2048_8192_SHA512,
+        AlgorithmID::PS256 => &signature::RSA_PSS_2048_8192_SHA256,
+        AlgorithmID::PS384 => &signature::RSA_PSS_2048_8192_SHA384,
+        AlgorithmID::PS512 => &signature::RSA_PSS_2048_8192_SHA512,
+        _ => unreachable!("Tried to get RSA parameters for a non-rsa algorithm"),
+    }
+}
+
+impl Backend for Ring {
+    type HmacKey = hmac::Key;
+    type EcdsaKeyPair = Box<signature::EcdsaKeyPair>;
+    type Ed25519KeyPair = Box<signature::Ed25519KeyPair>;
+    type RsaKeyPair = Box<signature::RsaKeyPair>;
+    type EcdsaPublicKey = PublicKey;
+    type Ed25519PublicKey = PublicKey;
+    type RsaPublicKey = signature::RsaPublicKeyComponents<Vec<u8>>;
+
+    fn supports(id: AlgorithmID) -> bool {
+        // ring doesn't implement P-521 or secp256k1
+        !matches!(id, AlgorithmID::ES512 | AlgorithmID::ES256K)
+    }
+
+    fn sha256(data: &[u8]) -> [u8; 32] {
+        let mut out = [0; 32];
+        out.copy_from_slice(digest::digest(&digest::SHA256, data).as_ref());
+        out
+    }
+
+    fn hmac_key(id: AlgorithmID, secret: &[u8]) -> Self::HmacKey {
+        hmac::Key::new(hmac_algorithm(id), secret)
+    }
+
+    fn hmac_sign(key: &Self::HmacKey, message: &[u8]) -> Vec<u8> {
+        hmac::sign(key, message).as_ref().to_vec()
+    }
+
+    fn hmac_verify(key: &Self::HmacKey, message: &[u8], mac: &[u8]) -> Result<(), Error> {
+        hmac::verify(key, message, mac).map_err(|_| Error::InvalidSignature())
+    }
+
+    fn ecdsa_key_pair(id: AlgorithmID, pkcs8: &[u8]) -> Result<Self::EcdsaKeyPair, Error> {
+        let key_pair = signature::EcdsaKeyPair::from_pkcs8(ecdsa_signing_algorithm(id), pkcs8)
+            .map_err(|e| {
+                Error::InvalidInput(ErrorDetails::map_dep(
+                    "Failed to create ECDSA key pair for signing",
+                    e,
+                ))
+            })?;
+        Ok(Box::new(key_pair))
+    }
+
+    fn ecdsa_public_key(key_pair: &Self::EcdsaKeyPair) -> &[u8] {
+        key_pair.public_key().as_ref()
+    }
+
+    fn ecdsa_sign(key_pair: &Self::EcdsaKeyPair, message: &[u8]) -> Result<Vec<u8>, Error> {
+        let rng = rand::SystemRandom::new();
+        let out = key_pair.sign(&rng, message).map_err(|e| {
+            Error::InvalidInput(ErrorDetails::map_dep("Failed to sign JWT with ECDSA", e))
+        })?;
+        Ok(out.as_ref().to_vec())
+    }
+
+    fn ecdsa_parse_public_key(id: AlgorithmID, point: &[u8]) -> Self::EcdsaPublicKey {
+        PublicKey(signature::UnparsedPublicKey::new(
+            ecdsa_verification_algorithm(id),
+            point.to_vec(),
+        ))
+    }
+
+    fn ecdsa_verify(
+        public_key: &Self::EcdsaPublicKey,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        public_key
+            .0
+            .verify(message, signature)
+            .map_err(|_| Error::InvalidSignature())
+    }
+
+    fn ed25519_key_pair(seed: &[u8]) -> Result<Self::Ed25519KeyPair, Error> {
+        let key_pair = signature::Ed25519KeyPair::from_seed_unchecked(seed).map_err(|e| {
+            Error::InvalidInput(ErrorDetails::map_dep(
+                "Failed to create Ed25519 key pair for signing",
+                e,
+            ))
+        })?;
+        Ok(Box::new(key_pair))
+    }
+
+    fn ed25519_public_key(key_pair: &Self::Ed25519KeyPair) -> &[u8] {
+        key_pair.public_key().as_ref()
+    }
+
+    fn ed25519_sign(key_pair: &Self::Ed25519KeyPair, message: &[u8]) -> Vec<u8> {
+        key_pair.sign(message).as_ref().to_vec()
+    }
+
+    fn ed25519_parse_public_key(public_key: &[u8]) -> Self::Ed25519PublicKey {
+        PublicKey(signature::UnparsedPublicKey::new(
+            &signature::ED25519,
+            public_key.to_vec(),
+        ))
+    }
+
+    fn ed25519_verify(
+        public_key: &Self::Ed25519PublicKey,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        public_key
+            .0
+            .verify(message, signature)
+            .map_err(|_| Error::InvalidSignature())
+    }
+
+    fn rsa_key_pair(pkcs1: &[u8]) -> Result<Self::RsaKeyPair, Error> {
+        let key_pair = signature::RsaKeyPair::from_der(pkcs1).map_err(|e| {
+            Error::InvalidInput(ErrorDetails::map_dep(
+                "Failed to create RSA key for signing",
+                e,
+            ))
+        })?;
+        Ok(Box::new(key_pair))
+    }
+
+    fn rsa_public_components(key_pair: &Self::RsaKeyPair) -> (Vec<u8>, Vec<u8>) {
+        let public_key = key_pair.public_key();
+        (
+            public_key
+                .modulus()
+                .big_endian_without_leading_zero()
+                .to_vec(),
+            public_key
+                .exponent()
+                .big_endian_without_leading_zero()
+                .to_vec(),
+        )
+    }
+
+    fn rsa_sign(
+        id: AlgorithmID,
+        key_pair: &Self::RsaKeyPair,
+        message: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let mut signature = vec![0; key_pair.public_modulus_len()];
+        let rng = rand::SystemRandom::new();
+        key_pair
+            .sign(rsa_encoding(id), &rng, message, &mut signature)
+            .map_err(|e| {
+                Error::InvalidInput(ErrorDetails::map_dep("Failed to sign JWT with RSA", e))
+            })?;
+        Ok(signature)
+    }
+
+    fn rsa_parse_public_key(n: &[u8], e: &[u8]) -> Self::RsaPublicKey {
+        signature::RsaPublicKeyComponents {
+            n: n.to_vec(),
+            e: e.to_vec(),
+        }
+    }
+
+    fn rsa_verify(
+        id: AlgorithmID,
+        public_key: &Self::RsaPublicKey,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        public_key
+            .verify(rsa_parameters(id), message, signature)
+            .map_err(|_| Error::InvalidSignature())
+    }
+}
@@ -0,0 +1,448 @@
+//! The RustCrypto backend
+
+use core::fmt;
+
+use hmac::{Hmac, Mac};
+#[cfg(feature = "es512")]
+use p256::ecdsa::signature::RandomizedSigner;
+use p256::ecdsa::signature::{Signer, Verifier};
+use p256::pkcs8::DecodePrivateKey;
+use rand_core::OsRng;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, Pkcs1v15Sign, Pss, RsaPrivateKey, RsaPublicKey};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+#[cfg(feature = "es512")]
+use zeroize::Zeroizing;
+
+use super::Backend;
+use crate::crypto::algorithm::AlgorithmID;
+use crate::error::{Error, ErrorDetails};
+use crate::prelude::*;
+
+/// Uses the pure Rust crates from the RustCrypto project, which support all of
+/// the algorithms in `AlgorithmID` (ES512 and ES256K with the `es512` and `es256k`
+/// features)
+#[derive(Debug)]
+pub(crate) struct RustCrypto;
+
+/// The largest RSA modulus (in bits) that's accepted for verifying, the same as for `ring`
+const MAX_MODULUS_BITS: usize = 8192;
+
+#[derive(Clone)]
+pub(crate) enum HmacKey {
+    Sha256(Hmac<Sha256>),
+    Sha384(Hmac<Sha384>),
+    Sha512(Hmac<Sha512>),
+}
+impl fmt::Debug for HmacKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let algorithm = match self {
+            HmacKey::Sha256(_) => "SHA256",
+            HmacKey::Sha384(_) => "SHA384",
+            HmacKey::Sha512(_) => "SHA512",
+        };
+        f.debug_struct("HmacKey")
+            .field("algorithm", &algorithm)
+            .finish()
+    }
+}
+
+enum EcdsaSigningKey {
+    P256(p256::ecdsa::SigningKey),
+    P384(p384::ecdsa::SigningKey),
+    #[cfg(feature = "es512")]
+    P521(p521::ecdsa::SigningKey),
+    #[cfg(feature = "es256k")]
+    Secp256k1(k256::ecdsa::SigningKey),
+}
+
+pub(crate) struct EcdsaKeyPair {
+    signing_key: EcdsaSigningKey,
+    public_key: Vec<u8>, // (uncompressed point)
+}
+impl fmt::Debug for EcdsaKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EcdsaKeyPair")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+pub(crate) struct Ed25519KeyPair {
+    signing_key: ed25519_dalek::SigningKey,
+    public_key: [u8; ed25519_dalek::PUBLIC_KEY_LENGTH],
+}
+impl fmt::Debug for Ed25519KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ed25519KeyPair")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+pub(crate) struct RsaKeyPair(RsaPrivateKey);
+impl fmt::Debug for RsaKeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RsaKeyPair")
+            .field("modulus_bits", &self.0.n().bits())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A parsed ECDSA public key
+pub(crate) enum EcdsaPublicKey {
+    P256(p256::ecdsa::VerifyingKey),
+    P384(p384::ecdsa::VerifyingKey),
+    #[cfg(feature = "es512")]
+    P521(p521::ecdsa::VerifyingKey),
+    #[cfg(feature = "es256k")]
+    Secp256k1(k256::ecdsa::VerifyingKey),
+    /// A point that isn't an uncompressed point on the algorithm's curve
+    Invalid,
+}
+impl fmt::Debug for EcdsaPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let curve = match self {
+            EcdsaPublicKey::P256(_) => "P-256",
+            EcdsaPublicKey::P384(_) => "P-384",
+            #[cfg(feature = "es512")]
+            EcdsaPublicKey::P521(_) => "P-521",
+            #[cfg(feature = "es256k")]
+            EcdsaPublicKey::Secp256k1(_) => "secp256k1",
+            EcdsaPublicKey::Invalid => "invalid",
+        };
+        f.debug_struct("EcdsaPublicKey")
+            .field("curve", &curve)
+            .finish_non_exhaustive()
+    }
+}
+
+enum RsaPadding {
+    Pkcs1v15(Pkcs1v15Sign),
+    Pss(Pss),
+}
+
+/// The padding scheme for an RSA algorithm, and the digest of a message that's signed
+fn rsa_padding(id: AlgorithmID, message: &[u8]) -> (RsaPadding, Vec<u8>) {
+    match id {
+        AlgorithmID::RS256 => (
+            RsaPadding::Pkcs1v15(Pkcs1v15Sign::new::<Sha256>()),
+            Sha256::digest(message).to_vec(),
+        ),
+        AlgorithmID::RS384 => (
+            RsaPadding::Pkcs1v15(Pkcs1v15Sign::new::<Sha384>()),
+            Sha384::digest(message).to_vec(),
+        ),
+        AlgorithmID::RS512 => (
+            RsaPadding::Pkcs1v15(Pkcs1v15Sign::new::<Sha512>()),
+            Sha512::digest(message).to_vec(),
+        ),
+        // The salt is the same size as the digest, as required by RFC 7518
+        AlgorithmID::PS256 => (
+            RsaPadding::Pss(Pss::new::<Sha256>()),
+            Sha256::digest(message).to_vec(),
+        ),
+        AlgorithmID::PS384 => (
+            RsaPadding::Pss(Pss::new::<Sha384>()),
+            Sha384::digest(message).to_vec(),
+        ),
+        AlgorithmID::PS512 => (
+            RsaPadding::Pss(Pss::new::<Sha512>()),
+            Sha512::digest(message).to_vec(),
+        ),
+        _ => unreachable!("Tried to get RSA padding for a non-rsa algorithm"),
+    }
+}
+
+impl Backend for RustCrypto {
+    type HmacKey = HmacKey;
+    type EcdsaKeyPair = EcdsaKeyPair;
+    type Ed25519KeyPair = Ed25519KeyPair;
+    type RsaKeyPair = RsaKeyPair;
+    type EcdsaPublicKey = EcdsaPublicKey;
+    // `None` for a key that isn't valid
+    type Ed25519PublicKey = Option<ed25519_dalek::VerifyingKey>;
+    type RsaPublicKey = Option<RsaPublicKey>;
+
+    fn supports(id: AlgorithmID) -> bool {
+        // P-521 and secp256k1 each need their own feature (and crate)
+        let feature = match id {
+            AlgorithmID::ES512 => Some(cfg!(feature = "es512")),
+            AlgorithmID::ES256K => Some(cfg!(feature = "es256k")),
+            _ => None,
+        };
+        feature.unwrap_or(true)
+    }
+
+    fn sha256(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).into()
+    }
+
+    fn hmac_key(id: AlgorithmID, secret: &[u8]) -> Self::HmacKey {
+        const ANY_LENGTH: &str = "HMAC accepts secrets of any length";
+        match id {
+            AlgorithmID::HS256 => HmacKey::Sha256(Hmac::new_from_slice(secret).expect(ANY_LENGTH)),
+            AlgorithmID::HS384 => HmacKey::Sha384(Hmac::new_from_slice(secret).expect(ANY_LENGTH)),
+            AlgorithmID::HS512 => HmacKey::Sha512(Hmac::new_from_slice(secret).expect(ANY_LENGTH)),
+            _ => unreachable!("Tried to map HMAC type for a non-HMAC algorithm"),
+        }
+    }
+
+    fn hmac_sign(key: &Self::HmacKey, message: &[u8]) -> Vec<u8> {
+        match key {
+            HmacKey::Sha256(mac) => mac
+                .clone()
+                .chain_update(message)
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+            HmacKey::Sha384(mac) => mac
+                .clone()
+                .chain_update(message)
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+            HmacKey::Sha512(mac) => mac
+                .clone()
+                .chain_update(message)
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+        }
+    }
+
+    fn hmac_verify(key: &Self::HmacKey, message: &[u8], mac: &[u8]) -> Result<(), Error> {
+        match key {
+            HmacKey::Sha256(key) => key.clone().chain_update(message).verify_slice(mac),
+            HmacKey::Sha384(key) => key.clone().chain_update(message).verify_slice(mac),
+            HmacKey::Sha512(key) => key.clone().chain_update(message).verify_slice(mac),
+        }
+        .map_err(|_| Error::InvalidSignature())
+    }
+
+    fn ecdsa_key_pair(id: AlgorithmID, pkcs8: &[u8]) -> Result<Self::EcdsaKeyPair, Error> {
+        let rejected = |e| {
+            Error::InvalidInput(ErrorDetails::map_dep(
+                "Failed to create ECDSA key pair for signing",
+                e,
+            ))
+        };
+        let (signing_key, public_key) = match id {
+            AlgorithmID::ES256 => {
+                let key = p256::ecdsa::SigningKey::from_pkcs8_der(pkcs8).map_err(rejected)?;
+                let point = key.verifying_key().to_encoded_point(false);
+                (EcdsaSigningKey::P256(key), point.as_bytes().to_vec())
+            }
+            AlgorithmID::ES384 => {
+                let key = p384::ecdsa::SigningKey::from_pkcs8_der(pkcs8).map_err(rejected)?;
+                let point = key.verifying_key().to_encoded_point(false);
+                (EcdsaSigningKey::P384(key), point.as_bytes().to_vec())
+            }
+            // p521 only implements PKCS#8 decoding for its `SecretKey`
+            #[cfg(feature = "es512")]
+            AlgorithmID::ES512 => {
+                let secret = p521::SecretKey::from_pkcs8_der(pkcs8).map_err(rejected)?;
+                let key = p521::ecdsa::SigningKey::from_bytes(&Zeroizing::new(secret.to_bytes()))
+                    .expect("A SecretKey is a valid signing key");
+                let point = p521::ecdsa::VerifyingKey::from(&key).to_encoded_point(false);
+                (EcdsaSigningKey::P521(key), point.as_bytes().to_vec())
+            }
+            #[cfg(feature = "es256k")]
+            AlgorithmID::ES256K => {
+                let key = k256::ecdsa::SigningKey::from_pkcs8_der(pkcs8).map_err(rejected)?;
+                let point = key.verifying_key().to_encoded_point(false);
+                (EcdsaSigningKey::Secp256k1(key), point.as_bytes().to_vec())
+            }
+            _ => unreachable!("Tried to get EC alg for a non-EC algorithm"),
+        };
+        Ok(EcdsaKeyPair {
+            signing_key,
+            public_key,
+        })
+    }
+
+    fn ecdsa_public_key(key_pair: &Self::EcdsaKeyPair) -> &[u8] {
+        &key_pair.public_key
+    }
+
+    fn ecdsa_sign(key_pair: &Self::EcdsaKeyPair, message: &[u8]) -> Result<Vec<u8>, Error> {
+        let failed =
+            |e| Error::InvalidInput(ErrorDetails::map_dep("Failed to sign JWT with ECDSA", e));
+        match &key_pair.signing_key {
+            EcdsaSigningKey::P256(key) => {
+                let signature: p256::ecdsa::Signature = key.try_sign(message).map_err(failed)?;
+                Ok(signature.to_bytes().to_vec())
+            }
+            EcdsaSigningKey::P384(key) => {
+                let signature: p384::ecdsa::Signature = key.try_sign(message).map_err(failed)?;
+                Ok(signature.to_bytes().to_vec())
+            }
+            #[cfg(feature = "es512")]
+            EcdsaSigningKey::P521(key) => {
+                let signature: p521::ecdsa::Signature =
+                    key.try_sign_with_rng(&mut OsRng, message).map_err(failed)?;
+                Ok(signature.to_bytes().to_vec())
+            }
+            #[cfg(feature = "es256k")]
+            EcdsaSigningKey::Secp256k1(key) => {
+                let signature: k256::ecdsa::Signature = key.try_sign(message).map_err(failed)?;
+                Ok(signature.to_bytes().to_vec())
+            }
+        }
+    }
+
+    fn ecdsa_parse_public_key(id: AlgorithmID, point: &[u8]) -> Self::EcdsaPublicKey {
+        // Only an uncompressed point is accepted, which is the only encoding
+        // that `ring` accepts
+        if point.first() != Some(&0x04) {
+            return EcdsaPublicKey::Invalid;
+        }
+        let public_key = match id {
+            AlgorithmID::ES256 => p256::ecdsa::VerifyingKey::from_sec1_bytes(point)
+                .ok()
+                .map(EcdsaPublicKey::P256),
+            AlgorithmID::ES384 => p384::ecdsa::VerifyingKey::from_sec1_bytes(point)
+                .ok()
+                .map(EcdsaPublicKey::P384),
+            #[cfg(feature = "es512")]
+            AlgorithmID::ES512 => p521::ecdsa::VerifyingKey::from_sec1_bytes(point)
+                .ok()
+                .map(EcdsaPublicKey::P521),
+            #[cfg(feature = "es256k")]
+            AlgorithmID::ES256K => k256::ecdsa::VerifyingKey::from_sec1_bytes(point)
+                .ok()
+                .map(EcdsaPublicKey::Secp256k1),
+            _ => unreachable!("Tried to get EC alg for a non-EC algorithm"),
+        };
+        public_key.unwrap_or(EcdsaPublicKey::Invalid)
+    }
+
+    fn ecdsa_verify(
+        public_key: &Self::EcdsaPublicKey,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        // Parsing the fixed size signatures also checks their length
+        let invalid = |_| Error::InvalidSignature();
+        match public_key {
+            EcdsaPublicKey::P256(key) => {
+                let signature = p256::ecdsa::Signature::from_slice(signature).map_err(invalid)?;
+                key.verify(message, &signature)
+            }
+            EcdsaPublicKey::P384(key) => {
+                let signature = p384::ecdsa::Signature::from_slice(signature).map_err(invalid)?;
+                key.verify(message, &signature)
+            }
+            #[cfg(feature = "es512")]
+            EcdsaPublicKey::P521(key) => {
+                let signature = p521::ecdsa::Signature::from_slice(signature).map_err(invalid)?;
+                key.verify(message, &signature)
+            }
+            #[cfg(feature = "es256k")]
+            EcdsaPublicKey::Secp256k1(key) => {
+                let signature = k256::ecdsa::Signature::from_slice(signature).map_err(invalid)?;
+                // k256 only accepts a "low S" signature (as required by Bitcoin), but
+                // RFC 8812 doesn't require it, so the equivalent low S is checked
+                key.verify(message, &signature.normalize_s().unwrap_or(signature))
+            }
+            EcdsaPublicKey::Invalid => return Err(Error::InvalidSignature()),
+        }
+        .map_err(|_| Error::InvalidSignature())
+    }
+
+    fn ed25519_key_pair(seed: &[u8]) -> Result<Self::Ed25519KeyPair, Error> {
+        let seed: &[u8; ed25519_dalek::SECRET_KEY_LENGTH] = seed.try_into().map_err(|_| {
+            Error::InvalidInput(ErrorDetails::new(format!(
+                "Failed to create Ed25519 key pair for signing: expected a {} byte seed, not {} bytes",
+                ed25519_dalek::SECRET_KEY_LENGTH,
+                seed.len()
+            )))
+        })?;
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(seed);
+        Ok(Ed25519KeyPair {
+            public_key: signing_key.verifying_key().to_bytes(),
+            signing_key,
+        })
+    }
+
+    fn ed25519_public_key(key_pair: &Self::Ed25519KeyPair) -> &[u8] {
+        &key_pair.public_key
+    }
+
+    fn ed25519_sign(key_pair: &Self::Ed25519KeyPair, message: &[u8]) -> Vec<u8> {
+        key_pair.signing_key.sign(message).to_bytes().to_vec()
+    }
+
+    fn ed25519_parse_public_key(public_key: &[u8]) -> Self::Ed25519PublicKey {
+        public_key
+            .try_into()
+            .ok()
+            .and_then(|bytes| ed25519_dalek::VerifyingKey::from_bytes(bytes).ok())
+    }
+
+    fn ed25519_verify(
+        public_key: &Self::Ed25519PublicKey,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let public_key = public_key.as_ref().ok_or(Error::InvalidSignature())?;
+        let signature = ed25519_dalek::Signature::from_slice(signature)
+            .map_err(|_| Error::InvalidSignature())?;
+        public_key
+            .verify_strict(message, &signature)
+            .map_err(|_| Error::InvalidSignature())
+    }
+
+    fn rsa_key_pair(pkcs1: &[u8]) -> Result<Self::RsaKeyPair, Error> {
+        let key = RsaPrivateKey::from_pkcs1_der(pkcs1).map_err(|e| {
+            Error::InvalidInput(ErrorDetails::map_dep(
+                "Failed to create RSA key for signing",
+                e,
+            ))
+        })?;
+        Ok(RsaKeyPair(key))
+    }
+
+    fn rsa_public_components(key_pair: &Self::RsaKeyPair) -> (Vec<u8>, Vec<u8>) {
+        (key_pair.0.n().to_bytes_be(), key_pair.0.e().to_bytes_be())
+    }
+
+    fn rsa_sign(
+        id: AlgorithmID,
+        key_pair: &Self::RsaKeyPair,
+        message: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        // Signing with an RNG also blinds the private key operation
+        let (padding, hashed) = rsa_padding(id, message);
+        match padding {
+            RsaPadding::Pkcs1v15(padding) => key_pair.0.sign_with_rng(&mut OsRng, padding, &hashed),
+            RsaPadding::Pss(padding) => key_pair.0.sign_with_rng(&mut OsRng, padding, &hashed),
+        }
+        .map_err(|e| Error::InvalidInput(ErrorDetails::map_dep("Failed to sign JWT with RSA", e)))
+    }
+
+    fn rsa_parse_public_key(n: &[u8], e: &[u8]) -> Self::RsaPublicKey {
+        RsaPublicKey::new_with_max_size(
+            BigUint::from_bytes_be(n),
+            BigUint::from_bytes_be(e),
+            MAX_MODULUS_BITS,
+        )
+        .ok()
+    }
+
+    fn rsa_verify(
+        id: AlgorithmID,
+        public_key: &Self::RsaPublicKey,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let public_key = public_key.as_ref().ok_or(Error::InvalidSignature())?;
+        let (padding, hashed) = rsa_padding(id, message);
+        match padding {
+            RsaPadding::Pkcs1v15(padding) => public_key.verify(padding, &hashed, signature),
+            RsaPadding::Pss(padding) => public_key.verify(padding, &hashed, signature),
+        }
+        .map_err(|_| Error::InvalidSignature())
+    }
+}
@@ -1,36 +1,27 @@
-use ring::{rand, signature};
-
 use crate::crypto::algorithm::AlgorithmID;
+use crate::crypto::backend::{Backend, Selected};
 use crate::crypto::SecretOrKey;
 use crate::error::{Error, ErrorDetails};
-use crate::raw::*;
+use crate::prelude::*;
 
-impl From<AlgorithmID> for &signature::EcdsaSigningAlgorithm {
-    fn from(alg: AlgorithmID) -> Self {
-        match alg {
-            AlgorithmID::ES256 => &signature::ECDSA_P256_SHA256_FIXED_SIGNING,
-            AlgorithmID::ES384 => &signature::ECDSA_P384_SHA384_FIXED_SIGNING,
-            _ => unreachable!("Tried to get EC alg for a non-EC algorithm"),
-        }
+/// The size of each (x, y) coordinate of a public key, or (r, s) component of a signature
+pub(crate) fn coordinate_len(alg: AlgorithmID) -> usize {
+    match alg {
+        AlgorithmID::ES256 => 32,
+        AlgorithmID::ES384 => 48,
+        AlgorithmID::ES512 => 66,
+        AlgorithmID::ES256K => 32,
+        _ => unreachable!("Tried to get EC coordinate size for a non-EC algorithm"),
     }
 }
 
 pub fn sign(
     _algorithm: AlgorithmID,
     secret_or_key: &SecretOrKey,
-    message: &str,
-) -> Result<String, Error> {
+    message: &[u8],
+) -> Result<Vec<u8>, Error> {
     match secret_or_key {
-        SecretOrKey::EcdsaKeyPair(signing_key) => {
-            let rng = rand::SystemRandom::new();
-            let out = signing_key.sign(&rng, message.as_bytes()).map_err(|e| {
-                Error::InvalidInput(ErrorDetails::map(
-                    "Failed to sign JWT with ECDSA",
-                    Box::new(e),
-                ))
-            })?;
-            Ok(b64_encode(out.as_ref()))
-        }
+        SecretOrKey::EcdsaKeyPair(signing_key, _) => Selected::ecdsa_sign(signing_key, message),
         _ => Err(Error::InvalidInput(ErrorDetails::new(
             "Missing ECDSA private key for signing",
         ))),
@@ -38,22 +29,17 @@ pub fn sign(
 }
 
 pub fn verify(
-    algorithm: AlgorithmID,
+    _algorithm: AlgorithmID,
     secret_or_key: &SecretOrKey,
-    message: &str,
-    signature: &str,
+    message: &[u8],
+    signature: &[u8],
 ) -> Result<(), Error> {
-    let ring_alg = algorithm.into();
     match secret_or_key {
-        SecretOrKey::EcdsaUnparsedKey(key) => {
-            let public_key = signature::UnparsedPublicKey::new(ring_alg, key);
-            let signature_bytes = b64_decode(signature)?;
-            public_key
-                .verify(message.as_bytes(), &signature_bytes)
-                .map_err(|_| Error::InvalidSignature())
+        SecretOrKey::EcdsaPublicKey(_, public_key) | SecretOrKey::EcdsaKeyPair(_, public_key) => {
+            Selected::ecdsa_verify(public_key, message, signature)
         }
         _ => Err(Error::InvalidInput(ErrorDetails::new(
-            "Missing ECDSA public key for signing",
+            "Missing ECDSA public key for verifying",
         ))),
     }
 }
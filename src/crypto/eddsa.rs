@@ -0,0 +1,38 @@
+use crate::crypto::algorithm::AlgorithmID;
+use crate::crypto::backend::{Backend, Selected};
+use crate::crypto::SecretOrKey;
+use crate::error::{Error, ErrorDetails};
+use crate::prelude::*;
+
+/// The size of an Ed25519 public key, or private key seed
+pub(crate) const KEY_LEN: usize = 32;
+
+pub fn sign(
+    _algorithm: AlgorithmID,
+    secret_or_key: &SecretOrKey,
+    message: &[u8],
+) -> Result<Vec<u8>, Error> {
+    match secret_or_key {
+        SecretOrKey::Ed25519KeyPair(key_pair, _) => Ok(Selected::ed25519_sign(key_pair, message)),
+        _ => Err(Error::InvalidInput(ErrorDetails::new(
+            "Missing Ed25519 private key for signing",
+        ))),
+    }
+}
+
+pub fn verify(
+    _algorithm: AlgorithmID,
+    secret_or_key: &SecretOrKey,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), Error> {
+    match secret_or_key {
+        SecretOrKey::Ed25519PublicKey(_, public_key)
+        | SecretOrKey::Ed25519KeyPair(_, public_key) => {
+            Selected::ed25519_verify(public_key, message, signature)
+        }
+        _ => Err(Error::InvalidInput(ErrorDetails::new(
+            "Missing Ed25519 public key for verifying",
+        ))),
+    }
+}
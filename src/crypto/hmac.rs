@@ -1,31 +1,39 @@
 use crate::crypto::algorithm::AlgorithmID;
+use crate::crypto::backend::{Backend, HmacKey, Selected};
 use crate::crypto::SecretOrKey;
 use crate::error::{Error, ErrorDetails};
-use crate::raw::*;
-use ring::constant_time::verify_slices_are_equal;
-use ring::hmac;
-
-impl From<AlgorithmID> for hmac::Algorithm {
-    fn from(alg: AlgorithmID) -> Self {
-        match alg {
-            AlgorithmID::HS256 => ring::hmac::HMAC_SHA256,
-            AlgorithmID::HS384 => ring::hmac::HMAC_SHA384,
-            AlgorithmID::HS512 => ring::hmac::HMAC_SHA512,
-            _ => unreachable!("Tried to map HMAC type for a non-HMAC algorithm"),
-        }
+use crate::prelude::*;
+use zeroize::Zeroizing;
+
+/// The minimum secret length (in bytes) for HMAC, which is the size of the hash output
+fn min_secret_len(alg: AlgorithmID) -> usize {
+    match alg {
+        AlgorithmID::HS256 => 32,
+        AlgorithmID::HS384 => 48,
+        AlgorithmID::HS512 => 64,
+        _ => unreachable!("Tried to get HMAC secret length for a non-HMAC algorithm"),
     }
 }
 
+pub(crate) fn ensure_secret_len(alg: AlgorithmID, secret: &[u8]) -> Result<(), Error> {
+    let min_len = min_secret_len(alg);
+    if secret.len() < min_len {
+        return Err(Error::WeakKey(ErrorDetails::new(format!(
+            "HMAC secret too short ({} bytes), must be at least {min_len} bytes for {alg}",
+            secret.len()
+        ))));
+    }
+    Ok(())
+}
+
 pub(crate) fn sign(
-    alg: AlgorithmID,
+    _algorithm: AlgorithmID,
     secret_or_key: &SecretOrKey,
-    message: &str,
-) -> Result<String, Error> {
+    message: &[u8],
+) -> Result<Vec<u8>, Error> {
     match secret_or_key {
-        SecretOrKey::Secret(key) => {
-            let ring_alg = alg.into();
-            let digest = hmac::sign(&hmac::Key::new(ring_alg, key), message.as_bytes());
-            Ok(b64_encode(digest.as_ref()))
+        SecretOrKey::Secret(_, key) | SecretOrKey::HmacKey(key) => {
+            Ok(Selected::hmac_sign(key, message))
         }
         _ => Err(Error::InvalidInput(ErrorDetails::new(
             "Missing secret for HMAC signing",
@@ -33,14 +41,74 @@ pub(crate) fn sign(
     }
 }
 
-pub fn verify(
-    algorithm: AlgorithmID,
+/// Keeps a secret along with its key state, which is computed once rather than for every message
+///
+/// The secret itself is kept for computing JWK thumbprints
+pub(crate) fn secret_key(alg: AlgorithmID, secret: Zeroizing<Vec<u8>>) -> SecretOrKey {
+    let key = Selected::hmac_key(alg, &secret);
+    SecretOrKey::Secret(secret, key)
+}
+
+/// Returns the precomputed key state for a secret
+pub(crate) fn prepare_key(secret_or_key: &SecretOrKey) -> Option<HmacKey> {
+    match secret_or_key {
+        SecretOrKey::Secret(_, key) | SecretOrKey::HmacKey(key) => Some(key.clone()),
+        _ => None,
+    }
+}
+
+/// Like `verify()` but with a key from `prepare_key()`
+pub(crate) fn verify_prepared(
+    key: &HmacKey,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), Error> {
+    if signature.is_empty() {
+        return Err(Error::InvalidSignature());
+    }
+    Selected::hmac_verify(key, message, signature)
+}
+
+pub(crate) fn verify(
+    _algorithm: AlgorithmID,
     secret_or_key: &SecretOrKey,
-    message: &str,
-    signature: &str,
+    message: &[u8],
+    signature: &[u8],
 ) -> Result<(), Error> {
-    // we just re-sign the message with the key and compare if they are equal
-    let signed = sign(algorithm, secret_or_key, message)?;
-    verify_slices_are_equal(signature.as_bytes(), signed.as_ref())
-        .map_err(|_| Error::InvalidSignature())
+    match secret_or_key {
+        SecretOrKey::Secret(_, key) | SecretOrKey::HmacKey(key) => {
+            verify_prepared(key, message, signature)
+        }
+        _ => Err(Error::InvalidInput(ErrorDetails::new(
+            "Missing secret for HMAC verification",
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_one_byte_different_signature() {
+        let secret = secret_key(AlgorithmID::HS256, Zeroizing::new(b"secret".to_vec()));
+        let mut mac = sign(AlgorithmID::HS256, &secret, b"hello world").unwrap();
+        verify(AlgorithmID::HS256, &secret, b"hello world", &mac).unwrap();
+
+        for i in [0, mac.len() - 1] {
+            mac[i] ^= 1;
+            match verify(AlgorithmID::HS256, &secret, b"hello world", &mac) {
+                Err(Error::InvalidSignature()) => {}
+                other => panic!("Expected InvalidSignature, got {other:?}"),
+            }
+            mac[i] ^= 1;
+        }
+
+        // A truncated MAC must also be rejected
+        let truncated = &mac[..mac.len() - 1];
+        match verify(AlgorithmID::HS256, &secret, b"hello world", truncated) {
+            Err(Error::InvalidSignature()) => {}
+            other => panic!("Expected InvalidSignature, got {other:?}"),
+        }
+    }
 }
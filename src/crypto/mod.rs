@@ -1,24 +1,34 @@
-use ring::signature;
+use zeroize::Zeroizing;
+
+use crate::prelude::*;
 
 pub(crate) mod algorithm;
+pub(crate) mod backend;
 pub(crate) mod ecdsa;
+pub(crate) mod eddsa;
 pub(crate) mod hmac;
 pub(crate) mod rsa;
 
+// Without `std` there's no PEM/DER parsing to construct some of the keys
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
 #[derive(Debug)]
 pub enum SecretOrKey {
     // Unsecured
     None,
 
     // HMAC
-    Secret(Vec<u8>),
+    Secret(Zeroizing<Vec<u8>>, backend::HmacKey), // (secret, precomputed key state)
+    HmacKey(backend::HmacKey), // precomputed from a borrowed secret, which isn't kept
 
     // ECDSA
-    EcdsaKeyPair(Box<signature::EcdsaKeyPair>),
-    EcdsaUnparsedKey(Vec<u8>),
+    EcdsaKeyPair(backend::EcdsaKeyPair, backend::EcdsaPublicKey), // (key pair, parsed public key)
+    EcdsaPublicKey(Vec<u8>, backend::EcdsaPublicKey), // (uncompressed point, parsed key)
+
+    // EdDSA
+    Ed25519KeyPair(backend::Ed25519KeyPair, backend::Ed25519PublicKey),
+    Ed25519PublicKey(Vec<u8>, backend::Ed25519PublicKey), // (key, parsed key)
 
     // RSA
-    RsaKeyPair(Box<signature::RsaKeyPair>),
-    RsaUnparsedKey(Vec<u8>),
-    RsaParameters(Vec<u8>, Vec<u8>), // (n, e)
+    RsaKeyPair(backend::RsaKeyPair, backend::RsaPublicKey),
+    RsaPublicKey(Vec<u8>, Vec<u8>, backend::RsaPublicKey), // (n, e, parsed key)
 }
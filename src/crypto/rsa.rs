@@ -1,59 +1,106 @@
-use ring::{rand, signature};
+#[cfg(feature = "std")]
+use simple_asn1::ASN1Block;
 
 use crate::crypto::algorithm::AlgorithmID;
+use crate::crypto::backend::{Backend, Selected};
 use crate::crypto::SecretOrKey;
 use crate::error::{Error, ErrorDetails};
-use crate::raw::*;
+#[cfg(feature = "std")]
+use crate::pem::decoder::der_split;
+use crate::prelude::*;
 
-impl From<AlgorithmID> for &signature::RsaParameters {
-    fn from(alg: AlgorithmID) -> Self {
-        match alg {
-            AlgorithmID::RS256 => &signature::RSA_PKCS1_2048_8192_SHA256,
-            AlgorithmID::RS384 => &signature::RSA_PKCS1_2048_8192_SHA384,
-            AlgorithmID::RS512 => &signature::RSA_PKCS1_2048_8192_SHA512,
-            AlgorithmID::PS256 => &signature::RSA_PSS_2048_8192_SHA256,
-            AlgorithmID::PS384 => &signature::RSA_PSS_2048_8192_SHA384,
-            AlgorithmID::PS512 => &signature::RSA_PSS_2048_8192_SHA512,
-            _ => unreachable!("Tried to get EC alg for a non-EC algorithm"),
-        }
+/// The smallest RSA modulus size (in bits) that's accepted for signing or verifying
+///
+/// This matches the minimum that ring requires, and checking it up-front means it's
+/// enforced with either backend, with a clear error while constructing an `Algorithm`
+/// instead of only failing later when trying to sign or verify.
+const MIN_MODULUS_BITS: u64 = 2048;
+
+/// Strips the leading zero bytes from a big-endian, unsigned integer
+pub(crate) fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// Checks the size of a modulus, given as big-endian bytes without leading zeros
+pub(crate) fn ensure_modulus_size(n: &[u8]) -> Result<(), Error> {
+    let bits = match n.first() {
+        Some(first) => (n.len() as u64 - 1) * 8 + u64::from(8 - first.leading_zeros()),
+        None => 0,
+    };
+    if bits < MIN_MODULUS_BITS {
+        return Err(Error::InvalidInput(ErrorDetails::new(format!(
+            "RSA key too small ({bits} bits), must be at least {MIN_MODULUS_BITS} bits"
+        ))));
     }
+    Ok(())
 }
-impl From<AlgorithmID> for &dyn signature::RsaEncoding {
-    fn from(alg: AlgorithmID) -> Self {
-        match alg {
-            AlgorithmID::RS256 => &signature::RSA_PKCS1_SHA256,
-            AlgorithmID::RS384 => &signature::RSA_PKCS1_SHA384,
-            AlgorithmID::RS512 => &signature::RSA_PKCS1_SHA512,
-            AlgorithmID::PS256 => &signature::RSA_PSS_SHA256,
-            AlgorithmID::PS384 => &signature::RSA_PSS_SHA384,
-            AlgorithmID::PS512 => &signature::RSA_PSS_SHA512,
-            _ => unreachable!("Tried to get RSA signature for a non-rsa algorithm"),
-        }
+
+/// Finds the modulus of a DER encoded PKCS#1 RSAPrivateKey, a sequence of
+/// (version, n, e, d, ...)
+///
+/// Unlike `der_public_components()` this doesn't parse the key's (private)
+/// integers into `BigInt`s, which couldn't be wiped afterwards
+#[cfg(feature = "std")]
+pub(crate) fn der_private_modulus(der: &[u8]) -> Result<&[u8], Error> {
+    let missing = || Error::InvalidInput(ErrorDetails::new("Failed to find RSA key modulus"));
+    let sequence = match der_split(der) {
+        Some((0x30, sequence, _)) => sequence,
+        _ => return Err(missing()),
+    };
+    let n = match der_split(sequence) {
+        Some((0x02, _version, rest)) => match der_split(rest) {
+            Some((0x02, n, _)) => n,
+            _ => return Err(missing()),
+        },
+        _ => return Err(missing()),
+    };
+    match n.first() {
+        Some(first) if first & 0x80 != 0 => Err(Error::InvalidInput(ErrorDetails::new(
+            "Negative RSA key modulus",
+        ))),
+        _ => Ok(trim_leading_zeros(n)),
+    }
+}
+
+/// Finds the (modulus, exponent) of a DER encoded PKCS#1 RSAPublicKey
+///
+/// The components are returned as big-endian bytes without leading zeros
+#[cfg(feature = "std")]
+pub(crate) fn der_public_components(der: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let asn1 = simple_asn1::from_der(der).map_err(|e| {
+        Error::InvalidInput(ErrorDetails::map("Failed to parse RSA key", Box::new(e)))
+    })?;
+    match asn1.first() {
+        Some(ASN1Block::Sequence(_, entries)) => match entries.as_slice() {
+            [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => {
+                match (n.to_biguint(), e.to_biguint()) {
+                    (Some(n), Some(e)) => Ok((
+                        trim_leading_zeros(&n.to_bytes_be()).to_vec(),
+                        trim_leading_zeros(&e.to_bytes_be()).to_vec(),
+                    )),
+                    _ => Err(Error::InvalidInput(ErrorDetails::new(
+                        "Negative RSA key component",
+                    ))),
+                }
+            }
+            _ => Err(Error::InvalidInput(ErrorDetails::new(
+                "Failed to find RSA key modulus and exponent",
+            ))),
+        },
+        _ => Err(Error::InvalidInput(ErrorDetails::new(
+            "Failed to find RSA key modulus and exponent",
+        ))),
     }
 }
 
 pub fn sign(
     algorithm: AlgorithmID,
     secret_or_key: &SecretOrKey,
-    message: &str,
-) -> Result<String, Error> {
-    let ring_alg = algorithm.into();
-
+    message: &[u8],
+) -> Result<Vec<u8>, Error> {
     match secret_or_key {
-        SecretOrKey::RsaKeyPair(key_pair) => {
-            let mut signature = vec![0; key_pair.public_modulus_len()];
-            let rng = rand::SystemRandom::new();
-            key_pair
-                .sign(ring_alg, &rng, message.as_bytes(), &mut signature)
-                .map_err(|e| {
-                    Error::InvalidInput(ErrorDetails::map(
-                        "Failed to sign JWT with RSA",
-                        Box::new(e),
-                    ))
-                })?;
-
-            Ok(b64_encode(&signature))
-        }
+        SecretOrKey::RsaKeyPair(key_pair, _) => Selected::rsa_sign(algorithm, key_pair, message),
         _ => Err(Error::InvalidInput(ErrorDetails::new(
             "Missing RSA private key for signing",
         ))),
@@ -63,25 +110,12 @@ pub fn sign(
 pub fn verify(
     algorithm: AlgorithmID,
     secret_or_key: &SecretOrKey,
-    message: &str,
-    signature: &str,
+    message: &[u8],
+    signature: &[u8],
 ) -> Result<(), Error> {
     match secret_or_key {
-        SecretOrKey::RsaUnparsedKey(key) => {
-            let ring_alg = algorithm.into();
-            let public_key = signature::UnparsedPublicKey::new(ring_alg, key);
-            let signature_bytes = b64_decode(signature)?;
-            public_key
-                .verify(message.as_bytes(), &signature_bytes)
-                .map_err(|_| Error::InvalidSignature())
-        }
-        SecretOrKey::RsaParameters(n, e) => {
-            let rsa_params = algorithm.into();
-            let pubkey = signature::RsaPublicKeyComponents { n, e };
-            let signature_bytes = b64_decode(signature)?;
-            pubkey
-                .verify(rsa_params, message.as_ref(), &signature_bytes)
-                .map_err(|_| Error::InvalidSignature())
+        SecretOrKey::RsaPublicKey(_, _, public_key) | SecretOrKey::RsaKeyPair(_, public_key) => {
+            Selected::rsa_verify(algorithm, public_key, message, signature)
         }
         _ => Err(Error::InvalidInput(ErrorDetails::new(
             "Missing RSA public key for verifying",
@@ -1,7 +1,11 @@
+use miniz_oxide::deflate::{compress_to_vec, CompressionLevel};
 use serde::ser::Serialize;
 
+#[cfg(feature = "std")]
+use crate::clock::{Clock, SystemClock};
 use crate::crypto::algorithm::Algorithm;
-use crate::error::Error;
+use crate::error::{Error, ErrorDetails};
+use crate::prelude::*;
 use crate::raw::*;
 
 /// Encodes a Json Web Token
@@ -36,14 +40,338 @@ use crate::raw::*;
 /// # Ok(())
 /// # }
 /// ```
+///
+/// The header may contain any custom parameters, which will be preserved, but
+/// its 'alg' will always be set to match the given `Algorithm`, and if the
+/// `Algorithm` has an associated 'kid' it will be added if the header doesn't
+/// have one. Returns `Error::AlgorithmMismatch` if the header has a different
+/// 'alg' to the `Algorithm` or `Error::InvalidInput` if the header isn't a JSON
+/// object. Returns `Error::CannotSign` if the `Algorithm` can only be used for
+/// verifying (see `Algorithm::can_sign()`).
 pub fn encode<H: Serialize, C: Serialize>(
     header: &H,
     claims: &C,
     algorithm: &Algorithm,
 ) -> Result<String, Error> {
-    let encoded_header = b64_encode_part(&header)?;
-    let encoded_claims = b64_encode_part(&claims)?;
-    let message = [encoded_header.as_ref(), encoded_claims.as_ref()].join(".");
-    let signature = algorithm.sign(&message)?;
-    Ok([message, signature].join("."))
+    let header = complete_header(header, algorithm)?;
+
+    // The token is built up in a single buffer, starting with the signing input
+    let mut token = signing_input(&header, claims)?;
+    let signature = algorithm.sign_bytes(token.as_bytes())?;
+    token.push('.');
+    b64_encode_into(&signature, &mut token);
+    Ok(token)
+}
+
+/// Returns the signing input for a token, `base64url(header).base64url(claims)`,
+/// as it would be serialized by `encode()`
+///
+/// This is the exact string that's signed, which can be useful for debugging
+/// signature mismatches, such as by comparing it with another library's
+/// signing input. Unlike `encode()` the header is used as-is, without adding
+/// an 'alg' or 'kid', so it should be the complete header.
+///
+/// For example:
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonwebtokens as jwt;
+/// # use jwt::{Algorithm, AlgorithmID};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret")?;
+/// let header = json!({ "alg": alg.name() });
+/// let claims = json!({ "foo": "bar" });
+/// let token = jwt::encode(&header, &claims, &alg)?;
+/// assert!(token.starts_with(&jwt::signing_input(&header, &claims)?));
+/// # Ok(())
+/// # }
+/// ```
+pub fn signing_input<H: Serialize, C: Serialize>(header: &H, claims: &C) -> Result<String, Error> {
+    let mut json = Vec::with_capacity(256);
+    let mut input = String::with_capacity(512);
+    b64_encode_part_into(header, &mut json, &mut input)?;
+    input.push('.');
+    b64_encode_part_into(claims, &mut json, &mut input)?;
+    Ok(input)
+}
+
+/// Encodes a Json Web Token, like `encode()`, but with already serialized claims
+///
+/// The claims are encoded as-is, preserving their exact bytes, after checking
+/// that they're well-formed JSON. Returns `Error::InvalidInput` if the claims
+/// aren't a JSON object.
+///
+/// For example:
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonwebtokens as jwt;
+/// # use jwt::{Algorithm, AlgorithmID};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret")?;
+/// let header = json!({ "alg": alg.name() });
+/// let token = jwt::encode_raw(&header, br#"{"sub":"user","admin":true}"#, &alg)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode_raw<H: Serialize>(
+    header: &H,
+    claims: &[u8],
+    algorithm: &Algorithm,
+) -> Result<String, Error> {
+    let is_object = claims
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_some_and(|&b| b == b'{');
+    if !is_object {
+        return Err(Error::InvalidInput(ErrorDetails::new(
+            "Expected claims to be a JSON object",
+        )));
+    }
+    serde_json::from_slice::<serde::de::IgnoredAny>(claims)
+        .map_err(|e| Error::InvalidInput(ErrorDetails::map("json parse failure", Box::new(e))))?;
+
+    let header = complete_header(header, algorithm)?;
+    let mut json = Vec::with_capacity(256);
+    let mut token = String::with_capacity(512);
+    b64_encode_part_into(&header, &mut json, &mut token)?;
+    token.push('.');
+    b64_encode_into(claims, &mut token);
+    let signature = algorithm.sign_bytes(token.as_bytes())?;
+    token.push('.');
+    b64_encode_into(&signature, &mut token);
+    Ok(token)
+}
+
+/// Encodes a Json Web Token, like `encode()`, but also returns the JSON header
+/// and claims that were actually signed
+///
+/// The header includes any 'alg' and 'kid' parameters that were added, which
+/// can be useful for logging or auditing. Returns `(token, header, claims)`.
+pub fn encode_with_parts<H: Serialize, C: Serialize>(
+    header: &H,
+    claims: &C,
+    algorithm: &Algorithm,
+) -> Result<(String, serde_json::value::Value, serde_json::value::Value), Error> {
+    let header = complete_header(header, algorithm)?;
+    let claims = serde_json::to_value(claims).map_err(|e| {
+        Error::InvalidInput(ErrorDetails::map("json serialize failure", Box::new(e)))
+    })?;
+    let mut token = signing_input(&header, &claims)?;
+    let signature = algorithm.sign_bytes(token.as_bytes())?;
+    token.push('.');
+    b64_encode_into(&signature, &mut token);
+    Ok((token, header, claims))
+}
+
+/// Encodes a Json Web Token, like `encode()`, but with a closure that sets the
+/// header's parameters
+///
+/// The closure is given a header that already has its 'alg' (and any 'kid'
+/// associated with the `Algorithm`) set, so it only needs to add the dynamic
+/// parameters, such as a 'kid' chosen along with the key. As with `encode()`,
+/// returns `Error::AlgorithmMismatch` if the closure changes the 'alg' or
+/// `Error::InvalidInput` if the header is no longer a JSON object.
+///
+/// For example:
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonwebtokens as jwt;
+/// # use jwt::{Algorithm, AlgorithmID};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret")?;
+/// let claims = json!({ "foo": "bar" });
+/// let token = jwt::encode_with(|header| header["kid"] = json!("key0"), &claims, &alg)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode_with<F: FnOnce(&mut serde_json::value::Value), C: Serialize>(
+    header_fn: F,
+    claims: &C,
+    algorithm: &Algorithm,
+) -> Result<String, Error> {
+    let mut header = complete_header(&serde_json::Map::new(), algorithm)?;
+    header_fn(&mut header);
+    encode(&header, claims, algorithm)
+}
+
+/// Encodes a Json Web Token, like `encode()`, but returns the token as bytes
+///
+/// This is convenient when the token will be written to a network buffer, for example.
+pub fn encode_to_bytes<H: Serialize, C: Serialize>(
+    header: &H,
+    claims: &C,
+    algorithm: &Algorithm,
+) -> Result<Vec<u8>, Error> {
+    encode(header, claims, algorithm).map(String::into_bytes)
+}
+
+/// Encodes a Json Web Token, like `encode()`, but with DEFLATE compressed claims
+///
+/// This sets a `"zip": "DEF"` header parameter, which isn't standard for signed
+/// tokens (only for JWE) but is used by some issuers with large claim sets, such
+/// as SMART Health Cards. Tokens are inflated automatically when verified.
+pub fn encode_compressed<H: Serialize, C: Serialize>(
+    header: &H,
+    claims: &C,
+    algorithm: &Algorithm,
+) -> Result<String, Error> {
+    let mut header = complete_header(header, algorithm)?;
+    if let serde_json::value::Value::Object(map) = &mut header {
+        map.insert("zip".to_owned(), "DEF".into());
+    }
+    let claims = serde_json::to_vec(claims).map_err(|e| {
+        Error::InvalidInput(ErrorDetails::map("json serialize failure", Box::new(e)))
+    })?;
+
+    let mut json = Vec::with_capacity(256);
+    let mut token = String::with_capacity(512);
+    b64_encode_part_into(&header, &mut json, &mut token)?;
+    token.push('.');
+    b64_encode_into(
+        &compress_to_vec(&claims, CompressionLevel::BestCompression as u8),
+        &mut token,
+    );
+    let signature = algorithm.sign_bytes(token.as_bytes())?;
+    token.push('.');
+    b64_encode_into(&signature, &mut token);
+    Ok(token)
+}
+
+/// Encodes a token with a detached, unencoded payload (RFC 7797)
+///
+/// The payload isn't included in the token (which has the form
+/// `"HEADER..SIGNATURE"`) and is signed as-is, without being base64 encoded,
+/// so it needs to be passed separately to `Verifier::verify_detached()`.
+///
+/// For example:
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonwebtokens as jwt;
+/// # use jwt::{Algorithm, AlgorithmID, Verifier};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret")?;
+/// let payload = b"some large document";
+/// let token = jwt::encode_detached(&json!({ "alg": alg.name() }), payload, &alg)?;
+///
+/// let verifier = Verifier::create().build()?;
+/// let header = verifier.verify_detached(&token, payload, &alg)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The header is completed like with `encode()` and will also have its 'b64'
+/// parameter set to `false`, with "b64" added to its 'crit' parameters.
+/// Returns `Error::InvalidInput` if the header has a non-array 'crit' parameter.
+pub fn encode_detached<H: Serialize>(
+    header: &H,
+    payload: &[u8],
+    algorithm: &Algorithm,
+) -> Result<String, Error> {
+    let mut header = complete_header(header, algorithm)?;
+    if let serde_json::value::Value::Object(map) = &mut header {
+        map.insert("b64".to_owned(), false.into());
+        match map
+            .entry("crit")
+            .or_insert_with(|| serde_json::value::Value::Array(vec![]))
+        {
+            serde_json::value::Value::Array(crit) => {
+                if !crit.iter().any(|name| name == "b64") {
+                    crit.push("b64".into());
+                }
+            }
+            _ => {
+                return Err(Error::InvalidInput(ErrorDetails::new(
+                    "Expected 'crit' header parameter to be an array",
+                )))
+            }
+        }
+    }
+
+    let mut json = Vec::with_capacity(256);
+    let mut token = String::with_capacity(512);
+    b64_encode_part_into(&header, &mut json, &mut token)?;
+    token.push('.');
+
+    // The payload is signed as-is, after the encoded header, but isn't included in the token
+    let mut message = Vec::with_capacity(token.len() + payload.len());
+    message.extend_from_slice(token.as_bytes());
+    message.extend_from_slice(payload);
+    let signature = algorithm.sign_bytes(&message)?;
+    token.push('.');
+    b64_encode_into(&signature, &mut token);
+    Ok(token)
+}
+
+fn complete_header<H: Serialize>(
+    header: &H,
+    algorithm: &Algorithm,
+) -> Result<serde_json::value::Value, Error> {
+    let mut header = serde_json::to_value(header).map_err(|e| {
+        Error::InvalidInput(ErrorDetails::map("json serialize failure", Box::new(e)))
+    })?;
+    let map = match &mut header {
+        serde_json::value::Value::Object(map) => map,
+        _ => {
+            return Err(Error::InvalidInput(ErrorDetails::new(
+                "Expected header to be a JSON object",
+            )))
+        }
+    };
+
+    match map.get("alg") {
+        Some(alg) if alg != algorithm.name() => return Err(Error::AlgorithmMismatch()),
+        Some(_) => {}
+        None => {
+            map.insert("alg".to_owned(), algorithm.name().into());
+        }
+    }
+    if let Some(kid) = algorithm.kid() {
+        if !map.contains_key("kid") {
+            map.insert("kid".to_owned(), kid.into());
+        }
+    }
+
+    Ok(header)
+}
+
+/// Encodes a Json Web Token, setting an 'iat' (issued at) claim to the current time
+///
+/// An existing 'iat' claim won't be overwritten.
+///
+/// For example:
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonwebtokens as jwt;
+/// # use jwt::{Algorithm, AlgorithmID};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret")?;
+/// let header = json!({ "alg": alg.name() });
+/// let claims = json!({ "foo": "bar" });
+/// let token = jwt::encode_with_iat(&header, &claims, &alg)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Returns `Error::InvalidInput` if the claims don't serialize to a JSON object
+#[cfg(feature = "std")]
+pub fn encode_with_iat<H: Serialize, C: Serialize>(
+    header: &H,
+    claims: &C,
+    algorithm: &Algorithm,
+) -> Result<String, Error> {
+    let mut claims = serde_json::to_value(claims).map_err(|e| {
+        Error::InvalidInput(ErrorDetails::map("json serialize failure", Box::new(e)))
+    })?;
+    match &mut claims {
+        serde_json::value::Value::Object(map) => {
+            if !map.contains_key("iat") {
+                map.insert("iat".to_owned(), SystemClock.now().into());
+            }
+        }
+        _ => {
+            return Err(Error::InvalidInput(ErrorDetails::new(
+                "Expected claims to be a JSON object",
+            )))
+        }
+    }
+    encode(header, &claims, algorithm)
 }
@@ -1,11 +1,14 @@
-use std::error::Error as StdError;
-use std::fmt;
+use core::error::Error as StdError;
+use core::fmt;
+
+use crate::prelude::*;
 
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct ErrorDetails {
     desc: String,
     src: Option<Box<dyn StdError + Send + Sync + 'static>>,
+    category: Option<ErrorCategory>,
 }
 
 impl ErrorDetails {
@@ -13,6 +16,7 @@ impl ErrorDetails {
         ErrorDetails {
             desc: desc.into(),
             src: None,
+            category: None,
         }
     }
 
@@ -23,16 +27,85 @@ impl ErrorDetails {
         ErrorDetails {
             desc: desc.into(),
             src: Some(src),
+            category: None,
         }
     }
+
+    /// Like `map()`, for errors from dependencies that only implement `Error` with `std`
+    ///
+    /// Without the `std` feature the source error is formatted into the description.
+    #[cfg(feature = "std")]
+    pub(crate) fn map_dep(
+        desc: impl Into<String>,
+        src: impl StdError + Send + Sync + 'static,
+    ) -> ErrorDetails {
+        ErrorDetails::map(desc, Box::new(src))
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn map_dep(desc: impl Into<String>, src: impl fmt::Display) -> ErrorDetails {
+        ErrorDetails::new(format!("{}: {src}", desc.into()))
+    }
+
+    /// Overrides the category of the error, where it's more specific than the variant's
+    pub(crate) fn with_category(mut self, category: ErrorCategory) -> ErrorDetails {
+        self.category = Some(category);
+        self
+    }
 }
 
 impl From<String> for ErrorDetails {
     fn from(s: String) -> Self {
-        ErrorDetails { desc: s, src: None }
+        ErrorDetails::new(s)
     }
 }
 
+/// A broad classification of errors, as returned by `Error::category()`
+///
+/// This is more stable than matching on every `Error` variant, such as for
+/// mapping errors to HTTP status codes (typically 401 for `Expired`,
+/// `NotYetValid`, `BadSignature` and `PolicyFailed`, 400 for `Malformed`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// The token has expired, or is older than allowed
+    Expired,
+    /// The token isn't valid yet, according to its 'nbf' or 'iat' claims
+    NotYetValid,
+    /// The token's signature (or algorithm) is invalid
+    BadSignature,
+    /// The token couldn't be parsed, or uses unsupported features
+    Malformed,
+    /// The token's claims don't meet the `Verifier`'s requirements
+    PolicyFailed,
+    /// No key was available to verify the token
+    KeyUnavailable,
+    /// The given keys, secrets or other inputs (not the token) are invalid
+    InvalidInput,
+}
+
+/// Identifies one of the three dot-separated segments of a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSegment {
+    Header,
+    Claims,
+    Signature,
+}
+
+impl fmt::Display for TokenSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenSegment::Header => write!(f, "header"),
+            TokenSegment::Claims => write!(f, "claims"),
+            TokenSegment::Signature => write!(f, "signature"),
+        }
+    }
+}
+
+/// The errors that may be returned by this crate
+///
+/// Where an error is caused by an underlying error (such as a serde_json,
+/// base64, PEM or cryptography backend error) it can be accessed via `core::error::Error::source()`
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
@@ -48,31 +121,181 @@ pub enum Error {
     /// The token expired at this time (unix epoch timestamp)
     TokenExpiredAt(u64),
 
+    /// The token was issued at this time (unix epoch timestamp) which is older than the `Verifier` allows
+    ///
+    /// Also returned with the time of any other claim that's older than its maximum age (see
+    /// `VerifierBuilder::max_claim_age`)
+    TokenTooOld(u64),
+
     /// Any of: header.payload.signature split error, json parser error, header or claim validation error
     MalformedToken(ErrorDetails),
+
+    /// No key was found matching the token's 'kid' (key ID)
+    NoMatchingKey(String),
+
+    /// Failed to fetch or parse a remote JWKS key set
+    KeyFetchFailed(ErrorDetails),
+
+    /// The token's 'aud' claim is missing or doesn't contain an expected audience
+    InvalidAudience(),
+
+    /// The token's 'iss' claim is missing or isn't an expected issuer
+    InvalidIssuer(),
+
+    /// A claim that's required by the `Verifier` was not found
+    MissingRequiredClaim(String),
+
+    /// A claim didn't have a value that's accepted by the `Verifier`
+    InvalidClaim(String),
+
+    /// The token's claims couldn't be deserialized into the requested type
+    ///
+    /// Also returned if the 'aud' claim isn't a string or an array of strings, or if the claims are
+    /// inconsistent, such as an 'exp' time before the 'nbf' or 'iat' time
+    MalformedClaims(ErrorDetails),
+
+    /// A key or secret is too weak for the requested algorithm
+    WeakKey(ErrorDetails),
+
+    /// A token segment wasn't valid base64url (without padding)
+    Base64(TokenSegment, ErrorDetails),
+
+    /// The token's header listed this 'crit' (critical) parameter which isn't understood
+    UnsupportedCriticalParam(String),
+
+    /// The token had this many dot-separated segments, instead of three
+    WrongSegmentCount(usize),
+
+    /// The token looks like an (encrypted) JWE, with five segments, which isn't supported
+    UnsupportedJwe(),
+
+    /// This algorithm name isn't a JOSE algorithm that's supported
+    UnsupportedAlgorithm(String),
+
+    /// The token was this many bytes, which is larger than the `Verifier` allows
+    TokenTooLarge(usize),
+
+    /// The token's 'jti' (JWT ID) was reported as already seen, by a `Verifier`'s `jti_seen()` callback
+    TokenReplayed(String),
+
+    /// This header parameter (such as 'typ') was missing or didn't have a value accepted by the `Verifier`
+    InvalidHeader(String),
+
+    /// The `Algorithm` only has a public key, for verifying, so it can't sign a token
+    CannotSign(),
+
+    /// The token's claims didn't match the `Verifier`'s claims schema, with each
+    /// failure given as the JSON Pointer of the failing value and a reason (or only
+    /// a reason for the claims object itself)
+    SchemaValidation(Vec<String>),
+}
+
+impl Error {
+    /// Classifies the error into a broad `ErrorCategory`
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Error::InvalidInput(_) => ErrorCategory::InvalidInput,
+            Error::AlgorithmMismatch() => ErrorCategory::BadSignature,
+            Error::InvalidSignature() => ErrorCategory::BadSignature,
+            Error::TokenExpiredAt(_) => ErrorCategory::Expired,
+            Error::TokenTooOld(_) => ErrorCategory::Expired,
+            Error::MalformedToken(details) => details.category.unwrap_or(ErrorCategory::Malformed),
+            Error::NoMatchingKey(_) => ErrorCategory::KeyUnavailable,
+            Error::KeyFetchFailed(_) => ErrorCategory::KeyUnavailable,
+            Error::InvalidAudience() => ErrorCategory::PolicyFailed,
+            Error::InvalidIssuer() => ErrorCategory::PolicyFailed,
+            Error::MissingRequiredClaim(_) => ErrorCategory::PolicyFailed,
+            Error::InvalidClaim(_) => ErrorCategory::PolicyFailed,
+            Error::MalformedClaims(_) => ErrorCategory::Malformed,
+            Error::WeakKey(_) => ErrorCategory::InvalidInput,
+            Error::Base64(..) => ErrorCategory::Malformed,
+            Error::UnsupportedCriticalParam(_) => ErrorCategory::Malformed,
+            Error::WrongSegmentCount(_) => ErrorCategory::Malformed,
+            Error::UnsupportedJwe() => ErrorCategory::Malformed,
+            Error::UnsupportedAlgorithm(_) => ErrorCategory::Malformed,
+            Error::TokenTooLarge(_) => ErrorCategory::Malformed,
+            Error::TokenReplayed(_) => ErrorCategory::PolicyFailed,
+            Error::InvalidHeader(_) => ErrorCategory::PolicyFailed,
+            Error::CannotSign() => ErrorCategory::InvalidInput,
+            Error::SchemaValidation(_) => ErrorCategory::PolicyFailed,
+        }
+    }
 }
 
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
-            Error::InvalidInput(ErrorDetails { src: Some(s), .. }) => Some(s.as_ref()),
-            Error::AlgorithmMismatch() => None,
-            Error::InvalidSignature() => None,
-            Error::TokenExpiredAt(_) => None,
-            Error::MalformedToken(ErrorDetails { src: Some(s), .. }) => Some(s.as_ref()),
+            Error::InvalidInput(ErrorDetails { src: Some(s), .. })
+            | Error::MalformedToken(ErrorDetails { src: Some(s), .. })
+            | Error::KeyFetchFailed(ErrorDetails { src: Some(s), .. })
+            | Error::MalformedClaims(ErrorDetails { src: Some(s), .. })
+            | Error::Base64(_, ErrorDetails { src: Some(s), .. }) => Some(s.as_ref()),
             _ => None,
         }
     }
 }
 
-impl std::fmt::Display for Error {
+impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::InvalidInput(details) => write!(f, "Invalid Input: {}", details.desc),
             Error::AlgorithmMismatch() => write!(f, "JWT Algorithm Mismatch"),
             Error::InvalidSignature() => write!(f, "JWT Signature Invalid"),
             Error::TokenExpiredAt(when) => write!(f, "JWT token expired at {when}"),
+            Error::TokenTooOld(iat) => write!(f, "JWT token issued at {iat} is too old"),
             Error::MalformedToken(details) => write!(f, "JWT claims invalid: {}", details.desc),
+            Error::NoMatchingKey(kid) => write!(f, "No key found matching 'kid' {kid}"),
+            Error::KeyFetchFailed(details) => write!(f, "JWKS fetch failed: {}", details.desc),
+            Error::InvalidAudience() => write!(f, "JWT Audience Invalid"),
+            Error::InvalidIssuer() => write!(f, "JWT Issuer Invalid"),
+            Error::MissingRequiredClaim(claim) => write!(f, "JWT missing required claim '{claim}'"),
+            Error::InvalidClaim(claim) => write!(f, "JWT claim '{claim}' invalid"),
+            Error::MalformedClaims(details) => write!(f, "JWT claims malformed: {}", details.desc),
+            Error::WeakKey(details) => write!(f, "Weak key: {}", details.desc),
+            Error::Base64(segment, details) => {
+                write!(f, "JWT {segment} base64 decode failure: {}", details.desc)
+            }
+            Error::UnsupportedCriticalParam(name) => {
+                write!(f, "JWT has unsupported critical header parameter '{name}'")
+            }
+            Error::WrongSegmentCount(found) => {
+                write!(
+                    f,
+                    "JWT has {found} segments, expected 3 (header.claims.signature)"
+                )
+            }
+            Error::UnsupportedJwe() => write!(f, "JWE (encrypted) tokens are not supported"),
+            Error::UnsupportedAlgorithm(name) => write!(f, "Unsupported algorithm '{name}'"),
+            Error::TokenTooLarge(size) => write!(f, "JWT of {size} bytes is too large"),
+            Error::TokenReplayed(jti) => write!(f, "JWT with 'jti' {jti} has already been used"),
+            Error::InvalidHeader(name) => write!(f, "JWT header parameter '{name}' invalid"),
+            Error::CannotSign() => write!(f, "Algorithm can only be used for verifying"),
+            Error::SchemaValidation(failures) => {
+                write!(f, "JWT claims don't match schema: {}", failures.join(", "))
+            }
         }
     }
 }
+
+impl From<serde_json::Error> for Error {
+    /// Converts a JSON (de)serialization error, such as when building claims, into `Error::InvalidInput`
+    fn from(e: serde_json::Error) -> Self {
+        let desc = match e.classify() {
+            serde_json::error::Category::Io => "json io failure",
+            serde_json::error::Category::Syntax | serde_json::error::Category::Eof => {
+                "json parse failure"
+            }
+            serde_json::error::Category::Data => "json data failure",
+        };
+        Error::InvalidInput(ErrorDetails::map(desc, Box::new(e)))
+    }
+}
+
+impl From<base64::DecodeError> for Error {
+    /// Converts a base64 decode error into `Error::InvalidInput`
+    ///
+    /// Errors for a token's segments are instead reported as `Error::Base64`
+    fn from(e: base64::DecodeError) -> Self {
+        Error::InvalidInput(ErrorDetails::map_dep("base64 decode failure", e))
+    }
+}
@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use serde_json::map::Map;
+use serde_json::value::Value;
+
+use core::str::FromStr;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::crypto::algorithm::{Algorithm, AlgorithmID};
+use crate::crypto::backend::{Backend, Selected};
+use crate::error::{Error, ErrorDetails};
+use crate::prelude::*;
+use crate::raw::b64_encode;
+
+/// A token's (JOSE) header
+///
+/// This can be passed to `encode()` in place of a JSON header, or be converted
+/// from a decoded JSON header to conveniently read standard parameters.
+///
+/// For example:
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonwebtokens as jwt;
+/// # use jwt::{Algorithm, AlgorithmID, Header};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret")?;
+/// let header = Header::for_algorithm(&alg).with_kid("key0");
+/// let token = jwt::encode(&header, &json!({ "foo": "bar" }), &alg)?;
+///
+/// let unverified = jwt::decode_unverified(&token)?;
+/// let header = Header::try_from(unverified.header)?;
+/// assert_eq!(header.kid(), Some("key0"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Header {
+    params: Map<String, Value>,
+}
+
+impl Header {
+    /// Constructs an empty header
+    pub fn new() -> Header {
+        Header { params: Map::new() }
+    }
+
+    /// Constructs a header with the 'alg' (and 'kid', if any) of the given `Algorithm`
+    pub fn for_algorithm(algorithm: &Algorithm) -> Header {
+        let header = Header::new().with_param("alg", algorithm.name());
+        match algorithm.kid() {
+            Some(kid) => header.with_kid(kid),
+            None => header,
+        }
+    }
+
+    /// Sets the 'kid' (key ID) parameter
+    pub fn with_kid(self, kid: impl Into<String>) -> Header {
+        self.with_param("kid", kid.into())
+    }
+
+    /// Sets the 'typ' (type) parameter, such as "JWT" or "at+jwt" for an access token
+    pub fn with_typ(self, typ: impl Into<String>) -> Header {
+        self.with_param("typ", typ.into())
+    }
+
+    /// Sets the 'x5c' (X.509 certificate chain) parameter from DER encoded certificates
+    ///
+    /// The chain must start with the certificate of the signing key, which
+    /// can be checked by a `Verifier` via `require_x5c_key_binding()`
+    pub fn with_x5c<C: AsRef<[u8]>>(self, chain: &[C]) -> Header {
+        let chain: Vec<Value> = chain
+            .iter()
+            .map(|certificate| Value::from(STANDARD.encode(certificate)))
+            .collect();
+        self.with_param("x5c", chain)
+    }
+
+    /// Sets the 'x5t#S256' (X.509 certificate SHA-256 thumbprint) parameter
+    /// for a DER encoded certificate
+    pub fn with_x5t_s256(self, certificate: &[u8]) -> Header {
+        self.with_param("x5t#S256", x5t_s256_thumbprint(certificate))
+    }
+
+    /// Sets any (custom) parameter to the given JSON value
+    pub fn with_param(mut self, name: impl Into<String>, value: impl Into<Value>) -> Header {
+        self.params.insert(name.into(), value.into());
+        self
+    }
+
+    /// Returns the 'alg' parameter, if it's a string
+    pub fn alg(&self) -> Option<&str> {
+        self.params.get("alg").and_then(Value::as_str)
+    }
+
+    /// Parses the 'alg' parameter as an `AlgorithmID`
+    ///
+    /// This can be used to select a key before verifying a token, but note that
+    /// the 'alg' of an unverified header can't be trusted to choose between
+    /// symmetric and asymmetric algorithms.
+    ///
+    /// Returns `Error::MalformedToken` if the 'alg' is missing or not a string
+    /// and `Error::UnsupportedAlgorithm` if it's not a known algorithm name
+    pub fn algorithm_id(&self) -> Result<AlgorithmID, Error> {
+        match self.params.get("alg") {
+            Some(Value::String(alg)) => AlgorithmID::from_str(alg),
+            Some(_) => Err(Error::MalformedToken(ErrorDetails::new(
+                "Expected 'alg' header parameter to be a string",
+            ))),
+            None => Err(Error::MalformedToken(ErrorDetails::new(
+                "Missing 'alg' header parameter",
+            ))),
+        }
+    }
+
+    /// Returns the 'kid' (key ID) parameter, if it's a string
+    pub fn kid(&self) -> Option<&str> {
+        self.params.get("kid").and_then(Value::as_str)
+    }
+
+    /// Returns the 'typ' (type) parameter, if it's a string
+    pub fn typ(&self) -> Option<&str> {
+        self.params.get("typ").and_then(Value::as_str)
+    }
+
+    /// Decodes the 'x5c' (X.509 certificate chain) parameter into DER encoded certificates
+    ///
+    /// Returns `Error::InvalidHeader` if it's not an array of base64 strings
+    pub fn x5c(&self) -> Result<Option<Vec<Vec<u8>>>, Error> {
+        match self.params.get("x5c") {
+            Some(Value::Array(chain)) => chain
+                .iter()
+                .map(|certificate| match certificate {
+                    Value::String(certificate) => STANDARD
+                        .decode(certificate)
+                        .map_err(|_| Error::InvalidHeader("x5c".to_owned())),
+                    _ => Err(Error::InvalidHeader("x5c".to_owned())),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Some),
+            Some(_) => Err(Error::InvalidHeader("x5c".to_owned())),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the 'x5t#S256' (X.509 certificate SHA-256 thumbprint) parameter, if it's a string
+    pub fn x5t_s256(&self) -> Option<&str> {
+        self.params.get("x5t#S256").and_then(Value::as_str)
+    }
+
+    /// Returns any parameter's JSON value
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.params.get(name)
+    }
+}
+
+/// The base64url encoded SHA-256 digest of a DER encoded certificate, as used for 'x5t#S256'
+pub(crate) fn x5t_s256_thumbprint(certificate: &[u8]) -> String {
+    b64_encode(&Selected::sha256(certificate))
+}
+
+/// Decodes the first certificate of an 'x5c' header parameter, which has the signing key
+#[cfg(feature = "std")]
+pub(crate) fn x5c_leaf_certificate(header: &Value) -> Result<Vec<u8>, Error> {
+    match header.get("x5c") {
+        Some(Value::Array(chain)) => match chain.first() {
+            Some(Value::String(certificate)) => STANDARD
+                .decode(certificate)
+                .map_err(|_| Error::InvalidHeader("x5c".to_owned())),
+            _ => Err(Error::InvalidHeader("x5c".to_owned())),
+        },
+        _ => Err(Error::InvalidHeader("x5c".to_owned())),
+    }
+}
+
+impl TryFrom<Value> for Header {
+    type Error = Error;
+
+    /// Converts a decoded JSON header, which must be a JSON object
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Object(params) => Ok(Header { params }),
+            _ => Err(Error::MalformedToken(ErrorDetails::new(
+                "Expected header to be a JSON object",
+            ))),
+        }
+    }
+}
+
+impl From<Header> for Value {
+    fn from(header: Header) -> Self {
+        Value::Object(header.params)
+    }
+}
@@ -0,0 +1,70 @@
+use alloc::collections::BTreeMap;
+
+use crate::crypto::algorithm::{Algorithm, AlgorithmID};
+use crate::error::Error;
+use crate::prelude::*;
+
+/// A set of HMAC secrets, indexed by `kid` (key ID), to support rotating secrets
+///
+/// This serves the same purpose as a `JwkSet` but for symmetric keys, such as by
+/// [Verifier::verify_for_time_with_hmac_keys](crate::Verifier::verify_for_time_with_hmac_keys)
+/// to select the secret matching a token's `kid` header.
+///
+/// For example:
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonwebtokens as jwt;
+/// # use jwt::{Algorithm, AlgorithmID, HmacKeyStore, Verifier};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut keys = HmacKeyStore::new();
+/// keys.insert("2024", AlgorithmID::HS256, "old secret")?;
+/// keys.insert("2025", AlgorithmID::HS256, "new secret")?;
+///
+/// let alg = keys.find("2025").unwrap();
+/// let token = jwt::encode(&json!({}), &json!({ "sub": "user" }), alg)?;
+///
+/// let verifier = Verifier::create().build()?;
+/// let claims = verifier.verify_with_hmac_keys(&token, &keys)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct HmacKeyStore {
+    keys: BTreeMap<String, Algorithm>,
+}
+
+impl HmacKeyStore {
+    /// Constructs an empty key store
+    pub fn new() -> HmacKeyStore {
+        HmacKeyStore {
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Adds (or replaces) the secret associated with the given `kid`
+    ///
+    /// The secret is checked like with `Algorithm::new_hmac()` and the resulting
+    /// `Algorithm` has the given `kid`, so it can also be used for signing.
+    pub fn insert(
+        &mut self,
+        kid: impl Into<String>,
+        id: AlgorithmID,
+        secret: impl Into<Vec<u8>>,
+    ) -> Result<(), Error> {
+        let kid = kid.into();
+        let mut algorithm = Algorithm::new_hmac(id, secret)?;
+        algorithm.set_kid(kid.clone());
+        self.keys.insert(kid, algorithm);
+        Ok(())
+    }
+
+    /// Removes the secret associated with the given `kid`, returning whether it was found
+    pub fn remove(&mut self, kid: &str) -> bool {
+        self.keys.remove(kid).is_some()
+    }
+
+    /// Finds the key associated with the given `kid`
+    pub fn find(&self, kid: &str) -> Option<&Algorithm> {
+        self.keys.get(kid)
+    }
+}
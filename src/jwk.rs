@@ -0,0 +1,213 @@
+use alloc::sync::Arc;
+use core::str::FromStr;
+use serde_json::value::Value;
+
+use crate::crypto::algorithm::{Algorithm, AlgorithmID};
+use crate::error::{Error, ErrorDetails};
+use crate::prelude::*;
+use crate::raw::*;
+
+/// Looks up an optional string member of a JWK
+fn optional_member<'a>(jwk: &'a Value, member: &str) -> Result<Option<&'a str>, Error> {
+    match jwk.get(member) {
+        Some(Value::String(value)) => Ok(Some(value.as_ref())),
+        Some(_) => Err(Error::InvalidInput(ErrorDetails::new(format!(
+            "JWK '{member}' member is not a string"
+        )))),
+        None => Ok(None),
+    }
+}
+
+/// Looks up a string member of a JWK that must be present
+fn required_member<'a>(jwk: &'a Value, member: &str) -> Result<&'a str, Error> {
+    optional_member(jwk, member)?.ok_or_else(|| {
+        Error::InvalidInput(ErrorDetails::new(format!(
+            "JWK is missing required '{member}' member"
+        )))
+    })
+}
+
+/// Determines the `AlgorithmID` for a key that can be used with more than one algorithm
+fn required_alg(jwk: &Value, kty: &str) -> Result<AlgorithmID, Error> {
+    match optional_member(jwk, "alg")? {
+        Some(alg) => AlgorithmID::from_str(alg),
+        None => Err(Error::InvalidInput(ErrorDetails::new(format!(
+            "JWK with kty '{kty}' is missing required 'alg' member"
+        )))),
+    }
+}
+
+/// Determines the `AlgorithmID` for a key whose curve implies a specific algorithm
+///
+/// In case the JWK also has an `alg` member then it's checked for consistency
+fn curve_alg(jwk: &Value, implied: AlgorithmID) -> Result<AlgorithmID, Error> {
+    match optional_member(jwk, "alg")? {
+        Some(alg) if AlgorithmID::from_str(alg)? != implied => {
+            Err(Error::InvalidInput(ErrorDetails::new(format!(
+                "JWK 'alg' ({alg}) is inconsistent with its curve, expected {implied}"
+            ))))
+        }
+        _ => Ok(implied),
+    }
+}
+
+impl Algorithm {
+    /// Constructs an algorithm based on a JSON Web Key, as described in
+    /// [RFC 7517](https://tools.ietf.org/html/rfc7517)
+    ///
+    /// The type of key is determined by the `kty` member:
+    /// - `"oct"`: a symmetric HMAC secret (requires an `alg` member)
+    /// - `"RSA"`: an RSA public key (requires an `alg` member)
+    /// - `"EC"`: an ECDSA public key on the `P-256` or `P-384` curve, or `P-521` and
+    ///   `secp256k1` with the `es512` and `es256k` features (otherwise returning
+    ///   `Error::UnsupportedAlgorithm`)
+    /// - `"OKP"`: an EdDSA public key on the `Ed25519` curve
+    ///
+    /// If the key has a `kid` member then it is associated with the `Algorithm`,
+    /// the same as with `set_kid()`.
+    ///
+    /// Except for symmetric secrets, the returned algorithm may only be used for
+    /// verifying tokens
+    pub fn from_jwk(jwk: &Value) -> Result<Self, Error> {
+        if !jwk.is_object() {
+            return Err(Error::InvalidInput(ErrorDetails::new(
+                "Expected JWK to be a JSON object",
+            )));
+        }
+
+        if let Some(key_use) = optional_member(jwk, "use")? {
+            if key_use != "sig" {
+                return Err(Error::InvalidInput(ErrorDetails::new(format!(
+                    "JWK with 'use' ({key_use}) isn't for signatures"
+                ))));
+            }
+        }
+
+        let kty = required_member(jwk, "kty")?;
+        let mut alg = match kty {
+            "oct" => {
+                let id = required_alg(jwk, kty)?;
+                Algorithm::new_hmac_b64(id, required_member(jwk, "k")?)?
+            }
+            "RSA" => {
+                let id = required_alg(jwk, kty)?;
+                let n = required_member(jwk, "n")?;
+                let e = required_member(jwk, "e")?;
+                Algorithm::new_rsa_n_e_b64_verifier(id, n, e)?
+            }
+            "EC" => {
+                let id = match required_member(jwk, "crv")? {
+                    "P-256" => curve_alg(jwk, AlgorithmID::ES256)?,
+                    "P-384" => curve_alg(jwk, AlgorithmID::ES384)?,
+                    "P-521" => curve_alg(jwk, AlgorithmID::ES512)?,
+                    "secp256k1" => curve_alg(jwk, AlgorithmID::ES256K)?,
+                    crv => {
+                        return Err(Error::InvalidInput(ErrorDetails::new(format!(
+                            "Unsupported JWK curve '{crv}' for kty 'EC'"
+                        ))))
+                    }
+                };
+                let x = required_member(jwk, "x")?;
+                let y = required_member(jwk, "y")?;
+                Algorithm::new_ecdsa_x_y_b64_verifier(id, x, y)?
+            }
+            "OKP" => {
+                match required_member(jwk, "crv")? {
+                    "Ed25519" => curve_alg(jwk, AlgorithmID::EdDSA)?,
+                    crv => {
+                        return Err(Error::InvalidInput(ErrorDetails::new(format!(
+                            "Unsupported JWK curve '{crv}' for kty 'OKP'"
+                        ))))
+                    }
+                };
+                let x = b64_decode(required_member(jwk, "x")?)?;
+                Algorithm::new_ed25519_verifier(&x)?
+            }
+            _ => {
+                return Err(Error::InvalidInput(ErrorDetails::new(format!(
+                    "Unsupported JWK kty '{kty}'"
+                ))))
+            }
+        };
+
+        if let Some(kid) = optional_member(jwk, "kid")? {
+            alg.set_kid(kid);
+        }
+
+        Ok(alg)
+    }
+}
+
+/// A set of keys, as found in a JSON Web Key Set (JWKS) document
+///
+/// Keys are looked up according to their `kid` (key ID), such as by
+/// [Verifier::verify_for_time_with_jwks](crate::Verifier::verify_for_time_with_jwks)
+/// to select the key matching a token's `kid` header.
+#[derive(Debug)]
+pub struct JwkSet {
+    keys: Vec<Arc<Algorithm>>,
+}
+
+impl JwkSet {
+    /// Parses a JWKS document in the form `{ "keys": [ JWK, ... ] }`
+    ///
+    /// Any keys that can't be used for verifying signatures are skipped, such as
+    /// encryption keys (with a `use` other than `"sig"`) and unsupported key types,
+    /// since provider key sets often mix key types. See
+    /// [Algorithm::from_jwk](Algorithm::from_jwk) for the supported key types.
+    ///
+    /// Returns `Error::InvalidInput` if the document isn't a key set, or none of
+    /// its keys can be used.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let value: Value = serde_json::from_str(json).map_err(|e| {
+            Error::InvalidInput(ErrorDetails::map("json parse failure", Box::new(e)))
+        })?;
+        Self::from_value(&value)
+    }
+
+    /// Constructs a key set from an already parsed JWKS document
+    pub fn from_value(jwks: &Value) -> Result<Self, Error> {
+        let jwks = match jwks.get("keys") {
+            Some(Value::Array(jwks)) => jwks,
+            _ => {
+                return Err(Error::InvalidInput(ErrorDetails::new(
+                    "Expected JWKS to have a 'keys' array",
+                )))
+            }
+        };
+
+        let mut keys = Vec::with_capacity(jwks.len());
+        for jwk in jwks {
+            if !jwk.is_object() {
+                return Err(Error::InvalidInput(ErrorDetails::new(
+                    "Expected each JWKS key to be an object",
+                )));
+            }
+            // So that one foreign key doesn't make every other key unusable
+            if let Ok(alg) = Algorithm::from_jwk(jwk) {
+                keys.push(Arc::new(alg));
+            }
+        }
+        if keys.is_empty() {
+            return Err(Error::InvalidInput(ErrorDetails::new(
+                "JWKS has no keys that can be used for verifying signatures",
+            )));
+        }
+
+        Ok(JwkSet { keys })
+    }
+
+    /// Finds the key associated with the given `kid`
+    pub fn find(&self, kid: &str) -> Option<&Algorithm> {
+        self.find_shared(kid).map(Arc::as_ref)
+    }
+
+    /// Iterates over all the keys in the set
+    pub fn keys(&self) -> impl Iterator<Item = &Algorithm> {
+        self.keys.iter().map(Arc::as_ref)
+    }
+
+    pub(crate) fn find_shared(&self, kid: &str) -> Option<&Arc<Algorithm>> {
+        self.keys.iter().find(|alg| alg.kid() == Some(kid))
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use serde::Serialize;
+
+use crate::crypto::algorithm::Algorithm;
+use crate::error::Error;
+
+/// Holds the current signing key for a token-issuing service, so that it can be
+/// swapped (such as to rotate keys) while other threads are signing tokens
+///
+/// Signers take a reference to the whole current `Algorithm`, via
+/// [current()](KeyRotator::current) or [encode()](KeyRotator::encode), so a
+/// token is always signed with a single, consistent key (and its `kid`) even
+/// if the key is rotated concurrently. The key is held in an [`ArcSwap`], so
+/// there's no lock: readers never block each other or a rotation, and a
+/// rotation never blocks readers.
+///
+/// For example:
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonwebtokens as jwt;
+/// # use jwt::{Algorithm, AlgorithmID, KeyRotator};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut alg = Algorithm::new_hmac(AlgorithmID::HS256, "old secret")?;
+/// alg.set_kid("2024");
+/// let rotator = KeyRotator::new(alg);
+/// let token = rotator.encode(&json!({}), &json!({ "sub": "user" }))?;
+///
+/// let mut alg = Algorithm::new_hmac(AlgorithmID::HS256, "new secret")?;
+/// alg.set_kid("2025");
+/// let old = rotator.rotate(alg);
+/// assert_eq!(old.kid(), Some("2024"));
+/// assert_eq!(rotator.current().kid(), Some("2025"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct KeyRotator {
+    current: ArcSwap<Algorithm>,
+}
+
+impl KeyRotator {
+    /// Constructs a rotator with the given initial signing key
+    pub fn new(algorithm: impl Into<Arc<Algorithm>>) -> KeyRotator {
+        KeyRotator {
+            current: ArcSwap::new(algorithm.into()),
+        }
+    }
+
+    /// Returns the current signing key
+    ///
+    /// The returned key stays valid, and unchanged, even if it's rotated
+    pub fn current(&self) -> Arc<Algorithm> {
+        self.current.load_full()
+    }
+
+    /// Replaces the current signing key, returning the previous key
+    ///
+    /// Tokens being signed concurrently use either the previous or the new key
+    /// in full. The previous key is dropped once no signer is still using it.
+    pub fn rotate(&self, algorithm: impl Into<Arc<Algorithm>>) -> Arc<Algorithm> {
+        self.current.swap(algorithm.into())
+    }
+
+    /// Encodes and signs a token with the current signing key, like `encode()`
+    pub fn encode<H: Serialize, C: Serialize>(
+        &self,
+        header: &H,
+        claims: &C,
+    ) -> Result<String, Error> {
+        crate::encode(header, claims, &self.current())
+    }
+}
@@ -1,23 +1,199 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+/// The `alloc` types that are otherwise in the std prelude, for `no_std` builds
+#[allow(unused_imports)]
+mod prelude {
+    pub(crate) use alloc::borrow::ToOwned;
+    pub(crate) use alloc::boxed::Box;
+    pub(crate) use alloc::format;
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec;
+    pub(crate) use alloc::vec::Vec;
+}
+
+use crate::prelude::*;
+
 pub mod error;
 
 mod verifier;
 pub use verifier::*;
 
+mod clock;
+pub use clock::Clock;
+#[cfg(feature = "std")]
+pub use clock::SystemClock;
+
 mod crypto;
 pub use crypto::algorithm::{Algorithm, AlgorithmID};
 
+#[cfg(feature = "std")]
 mod pem;
 
+#[cfg(feature = "std")]
+mod x509;
+
+mod strict_json;
+
+#[cfg(feature = "claims-schema")]
+mod claims_schema;
+
+mod rfc3339;
+
+mod jwk;
+pub use jwk::JwkSet;
+
+mod hmac_keys;
+pub use hmac_keys::HmacKeyStore;
+
+#[cfg(feature = "std")]
+mod key_rotator;
+#[cfg(feature = "std")]
+pub use key_rotator::KeyRotator;
+
+#[cfg(feature = "remote-jwks")]
+mod remote_jwks;
+#[cfg(feature = "remote-jwks")]
+pub use remote_jwks::{JwksFetchFuture, JwksFetcher, JwksResponse, RemoteJwks};
+
 pub mod raw;
 
 mod encode;
-pub use encode::encode;
+#[cfg(feature = "std")]
+pub use encode::encode_with_iat;
+pub use encode::{
+    encode, encode_compressed, encode_detached, encode_raw, encode_to_bytes, encode_with,
+    encode_with_parts, signing_input,
+};
+
+#[cfg(feature = "std")]
+mod claims;
+#[cfg(feature = "std")]
+pub use claims::ClaimsBuilder;
+
+mod header;
+pub use header::Header;
+
+mod decode;
+pub use decode::decode_unverified;
+
+#[cfg(feature = "testing")]
+pub mod testing;
 
 /// For lower-level APIs to return decoded header and claim values
-pub struct TokenData {
+///
+/// The claims may be deserialized into a custom type via `Verifier::verify_typed_for_time`
+pub struct TokenData<C = serde_json::value::Value> {
     pub header: serde_json::value::Value,
-    pub claims: serde_json::value::Value,
+    pub claims: C,
 
     #[doc(hidden)]
     pub _extensible: (),
 }
+
+impl<C> TokenData<C> {
+    /// The token's (verified, if returned by a `Verifier`) header
+    ///
+    /// This can be converted into a `Header` to read standard parameters like 'kid'
+    pub fn header(&self) -> &serde_json::value::Value {
+        &self.header
+    }
+
+    /// The token's (verified, if returned by a `Verifier`) claims
+    pub fn claims(&self) -> &C {
+        &self.claims
+    }
+
+    /// Takes ownership of the token's claims
+    ///
+    /// For example, to re-sign a token's claims (such as narrowing the scopes
+    /// of a token as a gateway) the claims can be modified and then encoded again:
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use jsonwebtokens as jwt;
+    /// # use jwt::{Algorithm, AlgorithmID, Header, Verifier};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let upstream_alg = Algorithm::new_hmac(AlgorithmID::HS256, "upstream secret")?;
+    /// # let token = jwt::encode(&Header::for_algorithm(&upstream_alg), &json!({ "sub": "user", "admin": true }), &upstream_alg)?;
+    /// let verifier = Verifier::create().build()?;
+    /// let data = verifier.verify_for_time(&token, &upstream_alg, 0)?;
+    ///
+    /// let mut claims = data.into_claims();
+    /// claims.as_object_mut().unwrap().remove("admin");
+    ///
+    /// let alg = Algorithm::new_hmac(AlgorithmID::HS256, "downstream secret")?;
+    /// let token = jwt::encode(&Header::for_algorithm(&alg), &claims, &alg)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn into_claims(self) -> C {
+        self.claims
+    }
+}
+
+impl TokenData {
+    /// Borrows the token's claims as a JSON value, without cloning
+    pub fn claims_ref(&self) -> &serde_json::value::Value {
+        &self.claims
+    }
+
+    /// Returns the named claim, if it's a string
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.claims
+            .get(name)
+            .and_then(serde_json::value::Value::as_str)
+    }
+
+    /// Returns the named claim, if it's an integer that fits in an `i64`
+    pub fn get_i64(&self, name: &str) -> Option<i64> {
+        self.claims
+            .get(name)
+            .and_then(serde_json::value::Value::as_i64)
+    }
+
+    /// Returns the named claim, if it's a non-negative integer that fits in a `u64`
+    pub fn get_u64(&self, name: &str) -> Option<u64> {
+        self.claims
+            .get(name)
+            .and_then(serde_json::value::Value::as_u64)
+    }
+
+    /// Returns the named claim, if it's an integer that fits in an `i128`
+    ///
+    /// Integers outside the range of `i64` and `u64` can only be read exactly
+    /// with the `arbitrary-precision` feature, otherwise they will have been
+    /// parsed as an (imprecise) `f64` and `None` is returned instead.
+    pub fn get_i128(&self, name: &str) -> Option<i128> {
+        match self.claims.get(name)? {
+            serde_json::value::Value::Number(n) => n
+                .as_i64()
+                .map(i128::from)
+                .or_else(|| n.as_u64().map(i128::from))
+                .or_else(|| n.to_string().parse().ok()),
+            _ => None,
+        }
+    }
+
+    /// Returns the token's 'jti' (JWT ID) claim, if it's a string
+    pub fn jti(&self) -> Option<&str> {
+        self.get_str("jti")
+    }
+
+    /// Returns the number of seconds from `now` (a unix epoch timestamp) until the token's 'exp' time
+    ///
+    /// This is negative if the token has already expired, and `None` if
+    /// there's no (valid) numeric 'exp' claim. For a `Verifier` that
+    /// `accept_string_dates()`, use `Verifier::expires_in()` instead, which also
+    /// reads an RFC 3339 string 'exp'.
+    pub fn expires_in(&self, now: u64) -> Option<i64> {
+        let exp = verifier::numeric_date(self.claims.as_object()?, "exp").ok()??;
+        Some(seconds_until(exp, now))
+    }
+}
+
+/// The (saturated) number of seconds from `now` until `time`
+pub(crate) fn seconds_until(time: u64, now: u64) -> i64 {
+    let seconds = time as i128 - now as i128;
+    seconds.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
@@ -1,3 +1,6 @@
+use simple_asn1::ASN1Block;
+use zeroize::{Zeroize, Zeroizing};
+
 use crate::error::{Error, ErrorDetails};
 
 /// Supported PEM files for EC and RSA Public and Private Keys
@@ -32,17 +35,17 @@ enum Classification {
 ///
 /// PEM public private keys are encoded PKCS#1 or PKCS#8
 /// You will find that with PKCS#8 RSA keys that the PKCS#1 content
-/// is embedded inside. This is what is provided to ring via `Key::Der`
+/// is embedded inside. This is what is provided to the cryptography backend
 /// For EC keys, they are always PKCS#8 on the outside but like RSA keys
 /// EC keys contain a section within that ultimately has the configuration
-/// that ring uses.
+/// that the backend uses.
 /// Documentation about these formats is at
 /// PKCS#1: https://tools.ietf.org/html/rfc8017
 /// PKCS#8: https://tools.ietf.org/html/rfc5958
 #[derive(Debug)]
 pub(crate) struct PemEncodedKey {
-    content: Vec<u8>,
-    asn1: Vec<simple_asn1::ASN1Block>,
+    content: Zeroizing<Vec<u8>>,
+    asn1: Vec<ASN1Block>,
     pem_type: PemType,
     standard: Standard,
 }
@@ -52,15 +55,20 @@ impl PemEncodedKey {
     pub fn new(input: &[u8]) -> Result<PemEncodedKey, Error> {
         match pem::parse(input) {
             Ok(content) => {
-                let pem_contents = content.contents;
-                let asn1_content = match simple_asn1::from_der(pem_contents.as_slice()) {
-                    Ok(asn1) => asn1,
-                    Err(e) => {
-                        return Err(Error::InvalidInput(ErrorDetails::map(
-                            "Failed to parse PEM file",
-                            Box::new(e),
-                        )))
-                    }
+                let pem_contents = Zeroizing::new(content.contents);
+                let asn1_content = match content.tag.as_ref() {
+                    // PKCS#1 private keys don't need to be classified, and their
+                    // private integers can't be wiped once parsed into ASN.1 blocks
+                    "RSA PRIVATE KEY" => Vec::new(),
+                    _ => match simple_asn1::from_der(pem_contents.as_slice()) {
+                        Ok(asn1) => asn1,
+                        Err(e) => {
+                            return Err(Error::InvalidInput(ErrorDetails::map(
+                                "Failed to parse PEM file",
+                                Box::new(e),
+                            )))
+                        }
+                    },
                 };
 
                 match content.tag.as_ref() {
@@ -137,6 +145,47 @@ impl PemEncodedKey {
         }
     }
 
+    /// Read a DER encoded (PKCS#1 or PKCS#8) RSA key, without any PEM armor
+    ///
+    /// Since DER has no markers, a PKCS#8 key is recognized by its OID and
+    /// anything else is assumed to be a PKCS#1 key.
+    pub fn from_rsa_der(input: &[u8], is_private: bool) -> Result<PemEncodedKey, Error> {
+        let pem_type = if is_private {
+            PemType::RsaPrivate
+        } else {
+            PemType::RsaPublic
+        };
+        // As with PEM, PKCS#1 private keys aren't parsed into ASN.1 blocks
+        if is_private && is_pkcs1_key(input) {
+            return Ok(PemEncodedKey {
+                content: Zeroizing::new(input.to_vec()),
+                asn1: Vec::new(),
+                pem_type,
+                standard: Standard::Pkcs1,
+            });
+        }
+
+        let asn1 = simple_asn1::from_der(input).map_err(|e| {
+            Error::InvalidInput(ErrorDetails::map("Failed to parse DER key", Box::new(e)))
+        })?;
+        let standard = match classify_pem(&asn1) {
+            Some(Classification::Rsa) => Standard::Pkcs8,
+            Some(Classification::Ec) => {
+                return Err(Error::InvalidInput(ErrorDetails::new(
+                    "DER key type mismatch (expected RSA key, found EC key)",
+                )))
+            }
+            None => Standard::Pkcs1,
+        };
+
+        Ok(PemEncodedKey {
+            content: Zeroizing::new(input.to_vec()),
+            asn1,
+            pem_type,
+            standard,
+        })
+    }
+
     pub fn as_ec_private_key(&self) -> Result<&[u8], Error> {
         match self.standard {
             Standard::Pkcs1 => Err(Error::InvalidInput(ErrorDetails::new(
@@ -212,6 +261,72 @@ impl PemEncodedKey {
     }
 }
 
+impl Drop for PemEncodedKey {
+    fn drop(&mut self) {
+        // The content is wiped by `Zeroizing`, but the blocks parsed from it may
+        // also contain private key material
+        zeroize_asn1(&mut self.asn1);
+    }
+}
+
+/// Wipes the bytes and strings of ASN.1 blocks
+///
+/// Integers are left as-is, since `BigInt` doesn't expose its digits, which is
+/// why PKCS#1 private keys (a sequence of private integers) are never parsed.
+fn zeroize_asn1(blocks: &mut [ASN1Block]) {
+    for block in blocks {
+        match block {
+            ASN1Block::BitString(_, _, bytes)
+            | ASN1Block::OctetString(_, bytes)
+            | ASN1Block::Unknown(_, _, _, _, bytes) => bytes.zeroize(),
+            ASN1Block::UTF8String(_, string)
+            | ASN1Block::PrintableString(_, string)
+            | ASN1Block::TeletexString(_, string)
+            | ASN1Block::IA5String(_, string)
+            | ASN1Block::UniversalString(_, string)
+            | ASN1Block::BMPString(_, string) => string.zeroize(),
+            ASN1Block::Sequence(_, blocks) | ASN1Block::Set(_, blocks) => zeroize_asn1(blocks),
+            ASN1Block::Explicit(_, _, _, block) => zeroize_asn1(core::slice::from_mut(block)),
+            _ => {}
+        }
+    }
+}
+
+/// Returns whether DER is a PKCS#1 key, a sequence whose first two elements are
+/// integers, rather than a PKCS#8 key, whose second element is a sequence
+fn is_pkcs1_key(der: &[u8]) -> bool {
+    let Some((0x30, sequence, _)) = der_split(der) else {
+        return false;
+    };
+    match der_split(sequence) {
+        Some((0x02, _, rest)) => matches!(der_split(rest), Some((0x02, ..))),
+        _ => false,
+    }
+}
+
+/// Splits the first DER element of `der` into its (tag, contents, remaining input)
+pub(crate) fn der_split(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = der.split_first()?;
+    let (&len, rest) = rest.split_first()?;
+    let (len, rest) = if len < 0x80 {
+        (usize::from(len), rest)
+    } else {
+        let len_bytes = usize::from(len & 0x7f);
+        if len_bytes == 0 || len_bytes > core::mem::size_of::<usize>() || rest.len() < len_bytes {
+            return None;
+        }
+        let (len, rest) = rest.split_at(len_bytes);
+        (
+            len.iter().fold(0, |len, &b| (len << 8) | usize::from(b)),
+            rest,
+        )
+    };
+    (rest.len() >= len).then(|| {
+        let (contents, rest) = rest.split_at(len);
+        (tag, contents, rest)
+    })
+}
+
 // This really just finds and returns the first bitstring or octet string
 // Which is the x coordinate for EC public keys
 // And the DER contents of an RSA key
@@ -267,3 +382,67 @@ fn classify_pem(asn1: &[simple_asn1::ASN1Block]) -> Option<Classification> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::rsa;
+
+    #[test]
+    fn classify_rsa_der_keys() {
+        assert!(is_pkcs1_key(include_bytes!(
+            "../../tests/rsa/private_rsa_key_pkcs1.der"
+        )));
+        assert!(is_pkcs1_key(include_bytes!(
+            "../../tests/rsa/public_rsa_key_pkcs1.der"
+        )));
+        assert!(!is_pkcs1_key(include_bytes!(
+            "../../tests/rsa/private_rsa_key_pkcs8.der"
+        )));
+        assert!(!is_pkcs1_key(b"\x30\x82\xff"));
+        assert!(!is_pkcs1_key(b""));
+    }
+
+    #[test]
+    fn private_rsa_key_modulus() {
+        let private_der = include_bytes!("../../tests/rsa/private_rsa_key_pkcs1.der");
+        let public_der = include_bytes!("../../tests/rsa/public_rsa_key_pkcs1.der");
+        assert_eq!(
+            rsa::der_private_modulus(private_der).unwrap(),
+            rsa::der_public_components(public_der).unwrap().0
+        );
+        assert!(rsa::der_private_modulus(b"\x30\x03\x02\x01\x00").is_err());
+    }
+
+    #[test]
+    fn wipe_asn1_blocks() {
+        let mut blocks = vec![ASN1Block::Sequence(
+            0,
+            vec![
+                ASN1Block::OctetString(0, b"secret".to_vec()),
+                ASN1Block::Explicit(
+                    simple_asn1::ASN1Class::ContextSpecific,
+                    0,
+                    0u8.into(),
+                    Box::new(ASN1Block::BitString(0, 48, b"secret".to_vec())),
+                ),
+            ],
+        )];
+        zeroize_asn1(&mut blocks);
+        assert_eq!(
+            blocks,
+            vec![ASN1Block::Sequence(
+                0,
+                vec![
+                    ASN1Block::OctetString(0, vec![]),
+                    ASN1Block::Explicit(
+                        simple_asn1::ASN1Class::ContextSpecific,
+                        0,
+                        0u8.into(),
+                        Box::new(ASN1Block::BitString(0, 48, vec![])),
+                    ),
+                ],
+            )]
+        );
+    }
+}
@@ -1,9 +1,11 @@
-use std::str::FromStr;
+use core::str::FromStr;
 
+use miniz_oxide::inflate::{decompress_to_vec_with_limit, TINFLStatus};
 use serde::ser::Serialize;
 
 use crate::crypto::algorithm::{Algorithm, AlgorithmID};
-use crate::error::{Error, ErrorDetails};
+use crate::error::{Error, ErrorDetails, TokenSegment};
+use crate::prelude::*;
 use crate::TokenData;
 
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
@@ -12,13 +14,177 @@ pub(crate) fn b64_encode(input: &[u8]) -> String {
     URL_SAFE_NO_PAD.encode(input)
 }
 
+pub(crate) fn b64_encode_into(input: &[u8], output: &mut String) {
+    URL_SAFE_NO_PAD.encode_string(input, output)
+}
+
 pub(crate) fn b64_decode(input: &str) -> Result<Vec<u8>, Error> {
     URL_SAFE_NO_PAD
         .decode(input)
-        .map_err(|e| Error::InvalidInput(ErrorDetails::map("base64 decode failure", Box::new(e))))
+        .map_err(|e| Error::InvalidInput(ErrorDetails::map_dep("base64 decode failure", e)))
+}
+
+/// The details of a base64 error, whose message is enough to describe a segment's failure
+///
+/// base64's errors only implement `Error` with `std`, otherwise there's no source error
+#[cfg(feature = "std")]
+fn base64_details(e: impl core::error::Error + Send + Sync + 'static) -> ErrorDetails {
+    ErrorDetails::map(e.to_string(), Box::new(e))
+}
+
+#[cfg(not(feature = "std"))]
+fn base64_details(e: impl core::fmt::Display) -> ErrorDetails {
+    ErrorDetails::new(e.to_string())
+}
+
+/// Decodes one of a token's segments, reporting failures as `Error::Base64`
+///
+/// As required for JWTs, this is strict base64url: padding, the standard
+/// alphabet's '+' and '/', and non-zero trailing bits (which would allow
+/// multiple encodings of the same bytes) are all rejected.
+pub(crate) fn b64_decode_segment(input: &str, segment: TokenSegment) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::new();
+    b64_decode_segment_into(input, segment, &mut buf)?;
+    Ok(buf)
+}
+
+/// Like `b64_decode_segment` but decodes into `buf`, replacing its contents,
+/// so that the buffer can be reused
+pub(crate) fn b64_decode_segment_into(
+    input: &str,
+    segment: TokenSegment,
+    buf: &mut Vec<u8>,
+) -> Result<(), Error> {
+    buf.clear();
+    URL_SAFE_NO_PAD
+        .decode_vec(input, buf)
+        .map_err(|e| Error::Base64(segment, base64_details(e)))
+}
+
+/// Like `decode_json_token_slice` but reports base64 failures as `Error::Base64`
+/// and UTF-8 failures as `Error::MalformedToken`
+pub(crate) fn decode_json_token_segment(
+    encoded_slice: &str,
+    segment: TokenSegment,
+    strict_json: bool,
+) -> Result<serde_json::value::Value, Error> {
+    decode_json_token_segment_with_buf(encoded_slice, segment, strict_json, &mut Vec::new())
+}
+
+/// Like `decode_json_token_segment` but uses `buf` for the decoded JSON
+pub(crate) fn decode_json_token_segment_with_buf(
+    encoded_slice: &str,
+    segment: TokenSegment,
+    strict_json: bool,
+    buf: &mut Vec<u8>,
+) -> Result<serde_json::value::Value, Error> {
+    b64_decode_segment_into(encoded_slice, segment, buf)?;
+    parse_json_segment(buf, segment, strict_json)
+}
+
+/// Parses a decoded token segment as JSON, optionally rejecting duplicate member names
+fn parse_json_segment(
+    json: &[u8],
+    segment: TokenSegment,
+    strict_json: bool,
+) -> Result<serde_json::value::Value, Error> {
+    if strict_json {
+        crate::strict_json::check_no_duplicate_members(json, segment)?;
+    }
+    let s = core::str::from_utf8(json).map_err(|e| {
+        Error::MalformedToken(ErrorDetails::map(
+            format!("utf8 decode failure for {segment}"),
+            Box::new(e),
+        ))
+    })?;
+    let value = serde_json::from_str(s)
+        .map_err(|e| Error::MalformedToken(ErrorDetails::map("json parse failure", Box::new(e))))?;
+    Ok(value)
+}
+
+/// The default limit on the size of tokens given to a `Verifier`
+pub(crate) const DEFAULT_MAX_TOKEN_BYTES: usize = 1024 * 1024;
+
+/// The default limit on the size of `"zip": "DEF"` compressed claims once inflated
+pub(crate) const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 256 * 1024;
+
+/// Decodes a token's claims, first inflating them if the header has a
+/// `"zip": "DEF"` parameter
+///
+/// Returns `Error::MalformedToken` for any other 'zip' algorithm or if the
+/// inflated claims would be larger than `max_decompressed_size`
+pub(crate) fn decode_claims_segment(
+    header: &serde_json::value::Value,
+    encoded_claims: &str,
+    max_decompressed_size: usize,
+    strict_json: bool,
+) -> Result<serde_json::value::Value, Error> {
+    decode_claims_segment_with_buf(
+        header,
+        encoded_claims,
+        max_decompressed_size,
+        strict_json,
+        &mut Vec::new(),
+    )
+}
+
+/// Like `decode_claims_segment` but uses `buf` for the decoded claims
+pub(crate) fn decode_claims_segment_with_buf(
+    header: &serde_json::value::Value,
+    encoded_claims: &str,
+    max_decompressed_size: usize,
+    strict_json: bool,
+    buf: &mut Vec<u8>,
+) -> Result<serde_json::value::Value, Error> {
+    match header.get("zip") {
+        None => decode_json_token_segment_with_buf(
+            encoded_claims,
+            TokenSegment::Claims,
+            strict_json,
+            buf,
+        ),
+        Some(zip) if zip == "DEF" => {
+            b64_decode_segment_into(encoded_claims, TokenSegment::Claims, buf)?;
+            let claims = decompress_to_vec_with_limit(buf, max_decompressed_size).map_err(|e| {
+                Error::MalformedToken(ErrorDetails::new(match e.status {
+                    TINFLStatus::HasMoreOutput => format!(
+                        "Decompressed claims exceed the maximum size of {max_decompressed_size} bytes"
+                    ),
+                    status => format!("DEFLATE decode failure: {status:?}"),
+                }))
+            })?;
+            parse_json_segment(&claims, TokenSegment::Claims, strict_json)
+        }
+        Some(zip) => Err(Error::MalformedToken(ErrorDetails::new(format!(
+            "Unsupported 'zip' compression algorithm {zip}"
+        )))),
+    }
+}
+
+/// Serializes a struct to JSON and appends it to `output`, encoded in base64
+///
+/// The `json` buffer is used for the intermediate JSON, so it can be reused
+/// across multiple parts
+pub(crate) fn b64_encode_part_into<T: Serialize>(
+    input: &T,
+    json: &mut Vec<u8>,
+    output: &mut String,
+) -> Result<(), Error> {
+    json.clear();
+    // serde_json can only write into an existing buffer with `std`
+    #[cfg(feature = "std")]
+    let result = serde_json::to_writer(&mut *json, input);
+    #[cfg(not(feature = "std"))]
+    let result = serde_json::to_vec(input).map(|encoded| *json = encoded);
+    result.map_err(|e| {
+        Error::InvalidInput(ErrorDetails::map("json serialize failure", Box::new(e)))
+    })?;
+    URL_SAFE_NO_PAD.encode_string(&json, output);
+    Ok(())
 }
 
 /// Serializes a struct to JSON and encodes it in base64
+#[cfg(feature = "testing")]
 pub(crate) fn b64_encode_part<T: Serialize>(input: &T) -> Result<String, Error> {
     let json = serde_json::to_string(input).map_err(|e| {
         Error::InvalidInput(ErrorDetails::map("json serialize failure", Box::new(e)))
@@ -88,7 +254,16 @@ pub struct TokenSlices<'a> {
 /// [Verifier::verify_claims_only](Verifier::verify_claims_only) api can be used
 /// to check the claims.
 ///
-pub fn split_token(token: &str) -> Result<TokenSlices, Error> {
+/// Returns `Error::WrongSegmentCount` if the token doesn't have exactly three
+/// dot-separated segments, or `Error::UnsupportedJwe` for a five segment token
+/// (the compact serialization of an encrypted JWE).
+pub fn split_token(token: &str) -> Result<TokenSlices<'_>, Error> {
+    match token.split('.').count() {
+        3 => {}
+        5 => return Err(Error::UnsupportedJwe()),
+        segment_count => return Err(Error::WrongSegmentCount(segment_count)),
+    }
+
     let (signature, message) = expect_two!(token.rsplitn(2, '.'));
     let (header, claims) = expect_two!(message.splitn(2, '.'));
 
@@ -180,6 +355,17 @@ pub fn decode_only(token: impl AsRef<str>) -> Result<TokenData, Error> {
     })
 }
 
+/// Looks up the (optional) 'kid' from a token's header
+pub(crate) fn header_kid(header: &serde_json::value::Value) -> Result<Option<&str>, Error> {
+    match header.get("kid") {
+        Some(serde_json::value::Value::String(k)) => Ok(Some(k.as_ref())),
+        Some(_) => Err(Error::MalformedToken(ErrorDetails::new(
+            "Non-string 'kid' found",
+        ))),
+        None => Ok(None),
+    }
+}
+
 /// Just verifies the signature of a token's message
 ///
 /// For example:
@@ -201,6 +387,31 @@ pub fn verify_signature_only(
     message: impl AsRef<str>,
     signature: impl AsRef<str>,
     algorithm: &Algorithm,
+) -> Result<(), Error> {
+    verify_signature_bytes(
+        header,
+        message.as_ref().as_bytes(),
+        signature.as_ref(),
+        algorithm,
+    )
+}
+
+/// Like `verify_signature_only()` but for a signing input that may not be a
+/// string (such as with an unencoded, detached payload)
+pub(crate) fn verify_signature_bytes(
+    header: &serde_json::value::Value,
+    message: &[u8],
+    signature: &str,
+    algorithm: &Algorithm,
+) -> Result<(), Error> {
+    verify_header_matches_algorithm(header, algorithm)?;
+    verify_encoded_signature(message, signature, algorithm)
+}
+
+/// Checks that the header's 'alg' and 'kid' match the given algorithm
+pub(crate) fn verify_header_matches_algorithm(
+    header: &serde_json::value::Value,
+    algorithm: &Algorithm,
 ) -> Result<(), Error> {
     match header.get("alg") {
         Some(serde_json::value::Value::String(alg)) => {
@@ -211,20 +422,38 @@ pub fn verify_signature_only(
             }
 
             // An Algorithm may relate to a specific 'kid' which we verify...
-            let kid = match header.get("kid") {
-                Some(serde_json::value::Value::String(k)) => Some(k.as_ref()),
-                Some(_) => {
-                    return Err(Error::MalformedToken(ErrorDetails::new(
-                        "Non-string 'kid' found",
-                    )))
-                }
-                None => None,
-            };
-
-            algorithm.verify(kid, message, signature)?;
+            algorithm.verify_kid(header_kid(header)?)
         }
-        _ => return Err(Error::AlgorithmMismatch()),
+        _ => Err(Error::AlgorithmMismatch()),
     }
+}
 
-    Ok(())
+/// Decodes and verifies a signature, without checking the header's 'alg' or 'kid'
+pub(crate) fn verify_encoded_signature(
+    message: &[u8],
+    signature: &str,
+    algorithm: &Algorithm,
+) -> Result<(), Error> {
+    with_decoded_signature(signature, |signature| {
+        algorithm.verify_bytes(message, signature)
+    })
+}
+
+/// Decodes a base64url signature and passes it to `verify`
+pub(crate) fn with_decoded_signature(
+    signature: &str,
+    verify: impl FnOnce(&[u8]) -> Result<(), Error>,
+) -> Result<(), Error> {
+    // Signatures are decoded on the stack unless they're very large (such
+    // as for an RSA key bigger than 4096 bits)
+    let mut buf = [0u8; 512];
+    if base64::decoded_len_estimate(signature.len()) <= buf.len() {
+        let len = URL_SAFE_NO_PAD
+            .decode_slice(signature, &mut buf)
+            .map_err(|e| Error::Base64(TokenSegment::Signature, base64_details(e)))?;
+        verify(&buf[..len])
+    } else {
+        let signature = b64_decode_segment(signature, TokenSegment::Signature)?;
+        verify(&signature)
+    }
 }
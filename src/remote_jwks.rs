@@ -0,0 +1,242 @@
+use std::error::Error as StdError;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::crypto::algorithm::Algorithm;
+use crate::error::{Error, ErrorDetails};
+use crate::jwk::JwkSet;
+
+/// The response to fetching a JWKS document
+pub struct JwksResponse {
+    /// The JWKS document
+    pub body: String,
+
+    /// The value of the response's `Cache-Control` header, if any
+    pub cache_control: Option<String>,
+}
+
+/// The future returned by [JwksFetcher::fetch]
+pub type JwksFetchFuture<'a> = Pin<
+    Box<
+        dyn Future<Output = Result<JwksResponse, Box<dyn StdError + Send + Sync + 'static>>>
+            + Send
+            + 'a,
+    >,
+>;
+
+/// Fetches JWKS documents on behalf of a [RemoteJwks]
+///
+/// This lets a [RemoteJwks] be used with whichever HTTP client you're already using.
+/// For example with `reqwest`:
+/// ```rust,ignore
+/// struct ReqwestFetcher(reqwest::Client);
+///
+/// impl JwksFetcher for ReqwestFetcher {
+///     fn fetch<'a>(&'a self, url: &'a str) -> JwksFetchFuture<'a> {
+///         Box::pin(async move {
+///             let response = self.0.get(url).send().await?.error_for_status()?;
+///             let cache_control = response
+///                 .headers()
+///                 .get(reqwest::header::CACHE_CONTROL)
+///                 .and_then(|v| v.to_str().ok())
+///                 .map(|v| v.to_owned());
+///             let body = response.text().await?;
+///             Ok(JwksResponse { body, cache_control })
+///         })
+///     }
+/// }
+/// ```
+pub trait JwksFetcher: Send + Sync {
+    /// Fetches the JWKS document found at `url`
+    fn fetch<'a>(&'a self, url: &'a str) -> JwksFetchFuture<'a>;
+}
+
+struct CachedJwks {
+    jwks: Arc<JwkSet>,
+    fetched_at: Instant,
+    max_age: Duration,
+}
+
+#[derive(Default)]
+struct CacheState {
+    cached: Option<CachedJwks>,
+
+    // Bumped each time the key set is successfully fetched
+    generation: u64,
+}
+
+enum Lookup {
+    Found(Arc<Algorithm>),
+    NotFound,
+    NeedsRefresh,
+}
+
+/// A JWKS key set that's fetched from a remote URL and cached
+///
+/// The key set is cached according to the `max-age` directive of the
+/// response's `Cache-Control` header (or `default_max_age` otherwise) and it
+/// will be re-fetched once the cache has expired. The key set will also be
+/// re-fetched if asked for an unknown `kid`, in case the keys have been rotated,
+/// but no more often than `min_refresh_interval`.
+///
+/// Concurrent lookups that all need to refresh the key set will be coalesced
+/// into a single fetch.
+///
+/// This doesn't depend on any particular async runtime: the only tokio
+/// dependency is its runtime-independent `sync` module, for coalescing fetches,
+/// so it can be used with async-std, smol or any other executor.
+pub struct RemoteJwks<F> {
+    url: String,
+    fetcher: F,
+    default_max_age: Duration,
+    min_refresh_interval: Duration,
+
+    state: Mutex<CacheState>,
+    refresh_lock: tokio::sync::Mutex<()>,
+}
+
+impl<F: JwksFetcher> RemoteJwks<F> {
+    /// Constructs a key set that will be fetched from `url` via `fetcher`
+    ///
+    /// Nothing is fetched until a key is first looked up.
+    pub fn new(url: impl Into<String>, fetcher: F) -> Self {
+        RemoteJwks {
+            url: url.into(),
+            fetcher,
+            default_max_age: Duration::from_secs(300),
+            min_refresh_interval: Duration::from_secs(30),
+            state: Mutex::new(CacheState::default()),
+            refresh_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Sets how long to cache the key set if the response has no `max-age`
+    /// (Defaults to 5 minutes)
+    pub fn set_default_max_age(&mut self, max_age: Duration) {
+        self.default_max_age = max_age;
+    }
+
+    /// Sets how long to wait, after fetching the key set, before re-fetching
+    /// it due to an unknown `kid` (Defaults to 30 seconds)
+    ///
+    /// This limits how often a token with a bogus `kid` can trigger a fetch.
+    pub fn set_min_refresh_interval(&mut self, interval: Duration) {
+        self.min_refresh_interval = interval;
+    }
+
+    /// Returns the key matching the given `kid`, fetching the key set if necessary
+    ///
+    /// Returns `Error::NoMatchingKey` if the `kid` isn't found and `Error::KeyFetchFailed`
+    /// if the key set couldn't be fetched.
+    pub async fn get_key(&self, kid: &str) -> Result<Arc<Algorithm>, Error> {
+        let generation = {
+            let state = self.lock_state();
+            match self.lookup(&state, kid) {
+                Lookup::Found(alg) => return Ok(alg),
+                Lookup::NotFound => return Err(Error::NoMatchingKey(kid.to_owned())),
+                Lookup::NeedsRefresh => state.generation,
+            }
+        };
+
+        let _refresh_guard = self.refresh_lock.lock().await;
+
+        {
+            let state = self.lock_state();
+
+            // Another task may have already refreshed the key set while we were
+            // waiting, which is used even if it's already expired (such as with
+            // `max-age=0`), since it's as fresh as a fetch of our own would be
+            if state.generation != generation {
+                return state
+                    .cached
+                    .as_ref()
+                    .and_then(|cached| cached.jwks.find_shared(kid).cloned())
+                    .ok_or_else(|| Error::NoMatchingKey(kid.to_owned()));
+            }
+            match self.lookup(&state, kid) {
+                Lookup::Found(alg) => return Ok(alg),
+                Lookup::NotFound => return Err(Error::NoMatchingKey(kid.to_owned())),
+                Lookup::NeedsRefresh => {}
+            }
+        }
+
+        let jwks = self.refresh().await?;
+        jwks.find_shared(kid)
+            .cloned()
+            .ok_or_else(|| Error::NoMatchingKey(kid.to_owned()))
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, CacheState> {
+        // The state is never left inconsistent so we can ignore poisoning
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lookup(&self, state: &CacheState, kid: &str) -> Lookup {
+        match &state.cached {
+            Some(cached) => {
+                let age = cached.fetched_at.elapsed();
+                if age >= cached.max_age {
+                    Lookup::NeedsRefresh
+                } else if let Some(alg) = cached.jwks.find_shared(kid) {
+                    Lookup::Found(alg.clone())
+                } else if age >= self.min_refresh_interval {
+                    Lookup::NeedsRefresh
+                } else {
+                    Lookup::NotFound
+                }
+            }
+            None => Lookup::NeedsRefresh,
+        }
+    }
+
+    async fn refresh(&self) -> Result<Arc<JwkSet>, Error> {
+        let response = self.fetcher.fetch(&self.url).await.map_err(|e| {
+            Error::KeyFetchFailed(ErrorDetails::map(
+                format!("Failed to fetch JWKS from {}", self.url),
+                e,
+            ))
+        })?;
+        let jwks = JwkSet::from_json(&response.body).map_err(|e| {
+            Error::KeyFetchFailed(ErrorDetails::map(
+                format!("Failed to parse JWKS from {}", self.url),
+                Box::new(e),
+            ))
+        })?;
+        let jwks = Arc::new(jwks);
+
+        let max_age = response
+            .cache_control
+            .as_deref()
+            .and_then(parse_max_age)
+            .unwrap_or(self.default_max_age);
+
+        let mut state = self.lock_state();
+        state.cached = Some(CachedJwks {
+            jwks: jwks.clone(),
+            fetched_at: Instant::now(),
+            max_age,
+        });
+        state.generation += 1;
+
+        Ok(jwks)
+    }
+}
+
+/// Parses the `max-age` directive from a `Cache-Control` header value
+fn parse_max_age(cache_control: &str) -> Option<Duration> {
+    cache_control.split(',').find_map(|directive| {
+        let (name, value) = directive.trim().split_once('=')?;
+        if name.trim().eq_ignore_ascii_case("max-age") {
+            value
+                .trim()
+                .trim_matches('"')
+                .parse()
+                .ok()
+                .map(Duration::from_secs)
+        } else {
+            None
+        }
+    })
+}
@@ -0,0 +1,143 @@
+//! Parses RFC 3339 date-times, for issuers that encode time claims as strings
+//!
+//! JWTs should encode times as a NumericDate (seconds since the unix epoch),
+//! but some non-compliant issuers instead give strings such as
+//! `"2019-11-29T19:50:15Z"`, which can be accepted via
+//! `VerifierBuilder::accept_string_dates()`.
+
+/// Converts an RFC 3339 date-time (such as `"2019-11-29T19:50:15+01:00"`) into
+/// seconds since the unix epoch
+///
+/// Any fractional seconds are truncated. Returns `None` if the string isn't a
+/// valid RFC 3339 date-time or is before the unix epoch.
+pub(crate) fn parse_timestamp(date_time: &str) -> Option<u64> {
+    let bytes = date_time.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+
+    let year = digits(&bytes[0..4])?;
+    let month = digits(&bytes[5..7])?;
+    let day = digits(&bytes[8..10])?;
+    let hour = digits(&bytes[11..13])?;
+    let minute = digits(&bytes[14..16])?;
+    // A leap second (60) is allowed, and counted as the first second of the next minute
+    let second = digits(&bytes[17..19])?;
+    if !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &bytes[19..];
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let len = fraction.iter().take_while(|b| b.is_ascii_digit()).count();
+        if len == 0 {
+            return None;
+        }
+        rest = &fraction[len..];
+    }
+    let offset = match rest {
+        b"Z" | b"z" => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+            let hours = digits(&[*h1, *h2])?;
+            let minutes = digits(&[*m1, *m2])?;
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = (hours * 3600 + minutes * 60) as i64;
+            if *sign == b'+' {
+                offset
+            } else {
+                -offset
+            }
+        }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year, month, day);
+    let local = days * 86400 + (hour * 3600 + minute * 60 + second) as i64;
+    u64::try_from(local - offset).ok()
+}
+
+/// Parses a fixed number of ASCII digits
+fn digits(bytes: &[u8]) -> Option<u32> {
+    bytes.iter().try_fold(0, |value, b| {
+        b.is_ascii_digit().then(|| value * 10 + u32::from(b - b'0'))
+    })
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The number of days from 1970-01-01 to the given (proleptic Gregorian) date
+///
+/// This is Howard Hinnant's `days_from_civil` algorithm
+fn days_from_civil(year: u32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_date_times() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2019-11-29T19:50:15Z"), Some(1_575_057_015));
+        assert_eq!(
+            parse_timestamp("2019-11-29t19:50:15.999z"),
+            Some(1_575_057_015)
+        );
+        assert_eq!(
+            parse_timestamp("2019-11-29T20:50:15+01:00"),
+            Some(1_575_057_015)
+        );
+        assert_eq!(
+            parse_timestamp("2019-11-29T14:20:15-05:30"),
+            Some(1_575_057_015)
+        );
+        assert_eq!(parse_timestamp("2000-02-29T00:00:00Z"), Some(951_782_400));
+        assert_eq!(parse_timestamp("2016-12-31T23:59:60Z"), Some(1_483_228_800));
+
+        for invalid in [
+            "",
+            "2019-11-29",
+            "2019-11-29T19:50:15",
+            "2019-11-29T19:50:15.Z",
+            "2019-11-29T19:50:15+0100",
+            "2019-13-01T00:00:00Z",
+            "2019-02-29T00:00:00Z",
+            "2019-11-29T24:00:00Z",
+            "1969-12-31T23:59:59Z",
+            "+019-11-29T19:50:15Z",
+        ] {
+            assert_eq!(parse_timestamp(invalid), None, "{invalid:?}");
+        }
+    }
+}
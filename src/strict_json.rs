@@ -0,0 +1,86 @@
+//! Rejects JSON with duplicate member names
+//!
+//! serde_json silently keeps the last value for a duplicated member name,
+//! while other parsers may keep the first, so a token such as
+//! `{"alg":"HS256","alg":"none"}` could be interpreted differently by
+//! different implementations. This makes a separate validating pass over the
+//! raw JSON, before it's parsed into a `Value`.
+
+use alloc::collections::BTreeSet;
+use core::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::error::{Error, ErrorDetails, TokenSegment};
+use crate::prelude::*;
+
+/// Checks that no object within the given JSON has duplicate member names
+pub(crate) fn check_no_duplicate_members(json: &[u8], segment: TokenSegment) -> Result<(), Error> {
+    serde_json::from_slice::<UniqueMembers>(json)
+        .map(|_| ())
+        .map_err(|e| {
+            Error::MalformedToken(ErrorDetails::map(
+                format!("Strict json parse failure for {segment}"),
+                Box::new(e),
+            ))
+        })
+}
+
+/// Deserializes any JSON value, only validating that member names are unique
+struct UniqueMembers;
+
+impl<'de> Deserialize<'de> for UniqueMembers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(UniqueMembersVisitor)
+    }
+}
+
+struct UniqueMembersVisitor;
+
+impl<'de> Visitor<'de> for UniqueMembersVisitor {
+    type Value = UniqueMembers;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _v: bool) -> Result<Self::Value, E> {
+        Ok(UniqueMembers)
+    }
+
+    fn visit_i64<E: de::Error>(self, _v: i64) -> Result<Self::Value, E> {
+        Ok(UniqueMembers)
+    }
+
+    fn visit_u64<E: de::Error>(self, _v: u64) -> Result<Self::Value, E> {
+        Ok(UniqueMembers)
+    }
+
+    fn visit_f64<E: de::Error>(self, _v: f64) -> Result<Self::Value, E> {
+        Ok(UniqueMembers)
+    }
+
+    fn visit_str<E: de::Error>(self, _v: &str) -> Result<Self::Value, E> {
+        Ok(UniqueMembers)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(UniqueMembers)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        while seq.next_element::<UniqueMembers>()?.is_some() {}
+        Ok(UniqueMembers)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut names = BTreeSet::new();
+        while let Some(name) = map.next_key::<String>()? {
+            map.next_value::<UniqueMembers>()?;
+            if let Some(name) = names.replace(name) {
+                return Err(de::Error::custom(format!("duplicate member name '{name}'")));
+            }
+        }
+        Ok(UniqueMembers)
+    }
+}
@@ -0,0 +1,99 @@
+//! Helpers for deliberately corrupting tokens, for testing error handling
+//!
+//! These are only available with the `testing` feature, which isn't enabled
+//! by default and is intended to be used via `[dev-dependencies]`, such as:
+//! ```toml
+//! [dev-dependencies]
+//! jsonwebtokens = { version = "1", features = ["testing"] }
+//! ```
+//!
+//! Each helper takes a valid token and returns a copy that a `Verifier` will
+//! reject, with the otherwise unchanged segments kept as-is:
+//! ```rust
+//! # use jsonwebtokens as jwt;
+//! # use jwt::{error::Error, Algorithm, AlgorithmID, Verifier};
+//! # use serde_json::json;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret")?;
+//! let token = jwt::encode(&json!({ "alg": "HS256" }), &json!({ "sub": "b@b.com" }), &alg)?;
+//! let verifier = Verifier::create().build()?;
+//!
+//! let tampered = jwt::testing::tampered_payload(&token)?;
+//! assert!(matches!(verifier.verify(&tampered, &alg), Err(Error::InvalidSignature())));
+//! # Ok(())
+//! # }
+//! ```
+
+use serde_json::value::Value;
+
+use crate::crypto::algorithm::AlgorithmID;
+use crate::error::{Error, ErrorDetails, TokenSegment};
+use crate::prelude::*;
+use crate::raw::*;
+
+/// Returns the token with an extra `"tampered": true` claim, without re-signing it
+///
+/// Verifying the token fails with `Error::InvalidSignature`
+///
+/// Returns `Error::MalformedToken` if the token's claims aren't a JSON object
+pub fn tampered_payload(token: &str) -> Result<String, Error> {
+    let slices = split_token(token)?;
+    let mut claims = decode_json_token_segment(slices.claims, TokenSegment::Claims, false)?;
+    match &mut claims {
+        Value::Object(map) => {
+            map.insert("tampered".to_owned(), Value::Bool(true));
+        }
+        _ => {
+            return Err(Error::MalformedToken(ErrorDetails::new(
+                "Expected claims to be a JSON object",
+            )))
+        }
+    }
+    Ok(format!(
+        "{}.{}.{}",
+        slices.header,
+        b64_encode_part(&claims)?,
+        slices.signature
+    ))
+}
+
+/// Returns the token with its header's 'alg' replaced, without re-signing it
+///
+/// Verifying the token with its original algorithm fails with
+/// `Error::AlgorithmMismatch`
+///
+/// Returns `Error::MalformedToken` if the token's header isn't a JSON object
+pub fn wrong_alg(token: &str, id: AlgorithmID) -> Result<String, Error> {
+    let slices = split_token(token)?;
+    let mut header = decode_json_token_segment(slices.header, TokenSegment::Header, false)?;
+    match &mut header {
+        Value::Object(map) => {
+            map.insert("alg".to_owned(), Value::String(id.to_string()));
+        }
+        _ => {
+            return Err(Error::MalformedToken(ErrorDetails::new(
+                "Expected header to be a JSON object",
+            )))
+        }
+    }
+    Ok(format!(
+        "{}.{}.{}",
+        b64_encode_part(&header)?,
+        slices.claims,
+        slices.signature
+    ))
+}
+
+/// Returns the token with one bit of its signature flipped (or, for an
+/// unsecured token, with a non-empty signature)
+///
+/// Verifying the token fails with `Error::InvalidSignature`
+pub fn bad_signature(token: &str) -> Result<String, Error> {
+    let slices = split_token(token)?;
+    let mut signature = b64_decode_segment(slices.signature, TokenSegment::Signature)?;
+    match signature.first_mut() {
+        Some(byte) => *byte ^= 1,
+        None => signature.push(0),
+    }
+    Ok(format!("{}.{}", slices.message, b64_encode(&signature)))
+}
@@ -1,23 +1,39 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
+use core::fmt;
+use core::time::Duration;
+use serde::de::DeserializeOwned;
 use serde_json::value::Value;
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+#[cfg(feature = "std")]
 use std::time::SystemTime;
 
 #[cfg(feature = "matching")]
-use regex::Regex;
-#[cfg(feature = "matching")]
-use std::fmt;
+use core::cmp::Ordering;
 #[cfg(feature = "matching")]
-use std::hash::{Hash, Hasher};
+use core::ops::Deref;
 #[cfg(feature = "matching")]
-use std::ops::Deref;
+use regex::Regex;
 
-use crate::crypto::algorithm::Algorithm;
-use crate::error::{Error, ErrorDetails};
+#[cfg(feature = "claims-schema")]
+use crate::claims_schema::ClaimsSchema;
+use crate::clock::Clock;
+#[cfg(feature = "std")]
+use crate::clock::SystemClock;
+use crate::crypto::algorithm::{Algorithm, AlgorithmID};
+use crate::crypto::backend::HmacKey;
+use crate::error::{Error, ErrorCategory, ErrorDetails, TokenSegment};
+use crate::hmac_keys::HmacKeyStore;
+use crate::jwk::JwkSet;
+use crate::prelude::*;
 use crate::raw::*;
+#[cfg(feature = "remote-jwks")]
+use crate::remote_jwks::{JwksFetcher, RemoteJwks};
+use crate::rfc3339;
 use crate::TokenData;
 
-// Regex doesn't implement PartialEq, Eq or Hash so we nee a wrapper...
+// Regex doesn't implement PartialEq, Eq or Ord so we nee a wrapper...
 #[cfg(feature = "matching")]
 #[derive(Debug, Clone)]
 struct Pattern(Regex);
@@ -44,9 +60,15 @@ impl PartialEq for Pattern {
 #[cfg(feature = "matching")]
 impl Eq for Pattern {}
 #[cfg(feature = "matching")]
-impl Hash for Pattern {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.as_str().hash(state);
+impl PartialOrd for Pattern {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+#[cfg(feature = "matching")]
+impl Ord for Pattern {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
     }
 }
 
@@ -57,47 +79,251 @@ struct VerifierClosure {
 impl Eq for VerifierClosure {}
 impl PartialEq for VerifierClosure {
     fn eq(&self, other: &Self) -> bool {
-        // The libs team has resolved to update Arc::ptr_eq so that it doesn't compare
-        // meta data for the wrapped value (such as a dyn trait vtable):
-        // https://github.com/rust-lang/rust/issues/103763
-        //
-        // In the meantime we can silence this clippy suggestion since the vtable is
-        // benign in this case anyway:
-        // https://github.com/rust-lang/rust-clippy/issues/6524
-        #[allow(clippy::vtable_address_comparisons)]
+        // Arc::ptr_eq doesn't compare meta data for the wrapped value (such as a
+        // dyn trait vtable): https://github.com/rust-lang/rust/issues/103763
         Arc::ptr_eq(&self.func, &other.func)
     }
 }
-impl std::fmt::Debug for VerifierClosure {
+impl fmt::Debug for VerifierClosure {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "VerifierClosure")
     }
 }
 
+/// A step of verifying a token, as reported to a `VerifierBuilder::on_validation()` callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ValidationStep {
+    /// Verifying the signature, including checking the token's 'alg'
+    Signature,
+    /// Checking the 'exp' claim
+    Expiry,
+    /// Checking the 'nbf' claim
+    NotBefore,
+    /// Checking the 'aud' claim
+    Audience,
+    /// Checking the 'iss' claim
+    Issuer,
+}
+
+/// The first and last unix epoch timestamps (inclusive) that a token is valid
+///
+/// Either bound is `None` if nothing limits it
+pub type ValidityWindow = (Option<u64>, Option<u64>);
+
+type ValidationFn = dyn Send + Sync + Fn(ValidationStep, Result<(), &Error>);
+
+#[derive(Clone)]
+struct ValidationCallback {
+    func: Arc<ValidationFn>,
+}
+impl Eq for ValidationCallback {}
+impl PartialEq for ValidationCallback {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.func, &other.func)
+    }
+}
+impl fmt::Debug for ValidationCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ValidationCallback")
+    }
+}
+
+#[derive(Clone)]
+struct JtiSeenCallback {
+    func: Arc<dyn Send + Sync + Fn(&str) -> bool>,
+}
+impl Eq for JtiSeenCallback {}
+impl PartialEq for JtiSeenCallback {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.func, &other.func)
+    }
+}
+impl fmt::Debug for JtiSeenCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "JtiSeenCallback")
+    }
+}
+
+#[derive(Clone)]
+struct SharedClock(Arc<dyn Clock>);
+impl SharedClock {
+    /// The default clock, which is shared so that Verifiers using it compare as equal
+    #[cfg(feature = "std")]
+    fn system() -> SharedClock {
+        static SYSTEM_CLOCK: OnceLock<Arc<dyn Clock>> = OnceLock::new();
+        SharedClock(SYSTEM_CLOCK.get_or_init(|| Arc::new(SystemClock)).clone())
+    }
+}
+impl Eq for SharedClock {}
+impl PartialEq for SharedClock {
+    fn eq(&self, other: &Self) -> bool {
+        // As for VerifierClosure, comparing a custom clock only compares the Arc pointer
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Clock")
+    }
+}
+
+/// The key for each issuer, for verifiers that select a key via the 'iss' claim
+#[derive(Clone, Default)]
+struct IssuerKeys(BTreeMap<String, Arc<Algorithm>>);
+impl Eq for IssuerKeys {}
+impl PartialEq for IssuerKeys {
+    fn eq(&self, other: &Self) -> bool {
+        // Algorithms can't be compared, so (like clocks) only shared keys are equal
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(other.0.iter())
+                .all(|((a, a_key), (b, b_key))| a == b && Arc::ptr_eq(a_key, b_key))
+    }
+}
+impl fmt::Debug for IssuerKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// HMAC keys for each of the secrets given via `VerifierBuilder::hmac_secrets()`
+/// (one for each HMAC algorithm), in the order they should be tried
+#[derive(Clone, Default)]
+struct HmacSecrets(Vec<Arc<Algorithm>>);
+impl Eq for HmacSecrets {}
+impl PartialEq for HmacSecrets {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(other.0.iter())
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+impl fmt::Debug for HmacSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HmacSecrets({})", self.0.len() / HMAC_ALGORITHMS.len())
+    }
+}
+
+const HMAC_ALGORITHMS: [AlgorithmID; 3] =
+    [AlgorithmID::HS256, AlgorithmID::HS384, AlgorithmID::HS512];
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 enum VerifierKind {
     Closure(VerifierClosure),
+    ValueConstant(Value),
 
     StringConstant(String),
-    StringSet(HashSet<String>),
+    StringSet(BTreeSet<String>),
 
     #[cfg(feature = "matching")]
     StringPattern(Pattern),
     #[cfg(feature = "matching")]
-    StringPatternSet(HashSet<Pattern>),
+    StringPatternSet(BTreeSet<Pattern>),
 
     StringOrArrayContains(String),
+    StringOrArrayContainsOneOf(BTreeSet<String>),
+    StringOrArrayContainsAll(BTreeSet<String>),
+}
+
+/// The error to report when a claim doesn't have an expected value
+fn claim_mismatch(claim_key: &str) -> Error {
+    match claim_key {
+        "aud" => Error::InvalidAudience(),
+        "iss" => Error::InvalidIssuer(),
+        _ => Error::InvalidClaim(claim_key.to_owned()),
+    }
+}
+
+/// The error to report when a claim that has a verifier is missing
+///
+/// A missing 'aud' or 'iss' is reported in the same way as a mismatch, so that
+/// configuring an audience or issuer can't be bypassed by omitting the claim
+fn claim_missing(claim_key: &str) -> Error {
+    match claim_key {
+        "aud" => Error::InvalidAudience(),
+        "iss" => Error::InvalidIssuer(),
+        _ => Error::MissingRequiredClaim(claim_key.to_owned()),
+    }
+}
+
+/// How failed claim checks are handled: either returning the first failure,
+/// collecting all of them, for `Verifier::verify_collect_errors()`, or keeping
+/// the first while still running every check, for `uniform_timing()`
+enum Failures {
+    First,
+    All(Vec<Error>),
+    Uniform(Option<Error>),
+}
+impl Failures {
+    fn report(&mut self, result: Result<(), Error>) -> Result<(), Error> {
+        match (self, result) {
+            (Failures::All(errors), Err(err)) => {
+                errors.push(err);
+                Ok(())
+            }
+            (Failures::Uniform(first), Err(err)) => {
+                first.get_or_insert(err);
+                Ok(())
+            }
+            (_, result) => result,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Failures::First => true,
+            Failures::All(errors) => errors.is_empty(),
+            Failures::Uniform(first) => first.is_none(),
+        }
+    }
 }
 
 /// Immutable requirements for checking token claims
-#[derive(Clone)]
+///
+/// Verifiers can be cloned and compared, though custom claim callbacks and
+/// clocks only compare as equal when they are shared, via a clone.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Verifier {
-    leeway: u32,
+    exp_leeway: u32,
+    nbf_leeway: u32,
+    iat_leeway: u32,
+    max_token_age: Option<u64>,
+    past_time_claims: BTreeMap<String, Option<u64>>,
+    algorithms: Option<Vec<AlgorithmID>>,
     ignore_exp: bool,
     ignore_nbf: bool,
     ignore_iat: bool,
+    require_exp: bool,
+    missing_exp_is_expired: bool,
+    require_nbf: bool,
+    allowed_crit: BTreeSet<String>,
+    required_typ: Option<String>,
+    max_decompressed_size: usize,
+    max_token_bytes: usize,
+    strip_bearer_prefix: bool,
+    jwks_without_kid: bool,
+    x5c_key_binding: bool,
+    insecure_disable_signature: bool,
+    check_claim_consistency: bool,
+    strict_json: bool,
+    accept_string_dates: bool,
+    uniform_timing: bool,
+    clock: SharedClock,
+    on_validation: Option<ValidationCallback>,
+    jti_seen: Option<JtiSeenCallback>,
+    issuer_keys: IssuerKeys,
+    hmac_secrets: HmacSecrets,
+    #[cfg(feature = "claims-schema")]
+    claims_schema: Option<ClaimsSchema>,
 
-    claim_verifiers: HashMap<String, VerifierKind>,
+    required_claims: Vec<String>,
+    claim_verifiers: BTreeMap<String, VerifierKind>,
 }
 
 impl Verifier {
@@ -106,13 +332,51 @@ impl Verifier {
         VerifierBuilder::new()
     }
 
+    /// Re-checks only the time-based claims of already verified token data
+    ///
+    /// This applies the same 'exp', 'nbf' and 'iat' checks (with any leeway and
+    /// maximum age) as verifying the token, given a unix epoch timestamp, such as
+    /// to check that a cached token is still valid without verifying its
+    /// signature again.
+    pub fn validate_time(&self, data: &TokenData, time_now: u64) -> Result<(), Error> {
+        match &data.claims {
+            serde_json::value::Value::Object(claims) => self.verify_time_claims(claims, time_now),
+            _ => Err(Error::MalformedToken(ErrorDetails::new(
+                "Expected claims to be a JSON object",
+            ))),
+        }
+    }
+
+    /// Returns the number of seconds from `now` (a unix epoch timestamp) until the
+    /// 'exp' time of a token's claims, such as for caching a verified token
+    ///
+    /// Like `TokenData::expires_in()`, except that an RFC 3339 string 'exp' is also
+    /// read if this verifier `accept_string_dates()`.
+    pub fn expires_in(&self, data: &TokenData, now: u64) -> Option<i64> {
+        let exp = self.claim_time(data.claims.as_object()?, "exp").ok()??;
+        Some(crate::seconds_until(exp, now))
+    }
+
     /// Verifies a token's claims but does not look at any header or verify any signature
     pub fn verify_claims_only(
         &self,
         claims: &serde_json::value::Value,
         time_now: u64,
     ) -> Result<(), Error> {
-        let claims = match claims {
+        self.check_claims(claims, time_now, &mut Failures::First)
+    }
+
+    /// Checks a token's claims, with any failures handled according to `failures`
+    ///
+    /// Claims that aren't a JSON object are always an error, even when
+    /// collecting failures
+    fn check_claims(
+        &self,
+        claims_value: &serde_json::value::Value,
+        time_now: u64,
+        failures: &mut Failures,
+    ) -> Result<(), Error> {
+        let claims = match claims_value {
             serde_json::value::Value::Object(map) => map,
             _ => {
                 return Err(Error::MalformedToken(ErrorDetails::new(
@@ -121,311 +385,1639 @@ impl Verifier {
             }
         };
 
-        if !self.ignore_iat {
-            match claims.get("iat") {
-                Some(serde_json::value::Value::Number(number)) => {
-                    if let Some(iat) = number.as_u64() {
-                        if iat > time_now + (self.leeway as u64) {
-                            return Err(Error::MalformedToken(ErrorDetails::new(
-                                "Issued with a future 'iat' time",
-                            )));
-                        }
-                    } else {
-                        return Err(Error::MalformedToken(ErrorDetails::new(
-                            "Failed to parse 'iat' as an integer",
-                        )));
-                    }
-                }
+        self.check_time_claims(claims, time_now, failures)?;
+
+        // At least verify the type for these standard claims
+        // (Values can separately be validated via .claim_verifiers)
+        for &string_claim in &["iss", "sub"] {
+            match claims.get(string_claim) {
+                Some(serde_json::value::Value::String(_)) => {}
                 Some(_) => {
-                    return Err(Error::MalformedToken(ErrorDetails::new(
-                        "Given 'iat' not a number",
-                    )));
+                    failures.report(Err(Error::MalformedToken(ErrorDetails::new(format!(
+                        "Given '{string_claim}' not a string"
+                    )))))?;
                 }
                 None => {}
             }
         }
 
-        if !self.ignore_nbf {
-            match claims.get("nbf") {
-                Some(serde_json::value::Value::Number(number)) => {
-                    if let Some(nbf) = number.as_u64() {
-                        if nbf > time_now + (self.leeway as u64) {
-                            return Err(Error::MalformedToken(ErrorDetails::new(
-                                "Time is before 'nbf'",
-                            )));
+        for &string_or_array in &["aud"] {
+            match claims.get(string_or_array) {
+                Some(serde_json::value::Value::String(_)) => {}
+                Some(serde_json::value::Value::Array(claim_array)) => {
+                    for subclaim in claim_array {
+                        if !subclaim.is_string() {
+                            failures.report(Err(Error::MalformedClaims(ErrorDetails::new(
+                                format!(
+                                    "Claim {string_or_array}: array elements are not all strings"
+                                ),
+                            ))))?;
+                            break;
                         }
-                    } else {
-                        return Err(Error::MalformedToken(ErrorDetails::new(
-                            "Failed to parse 'nbf' as an integer",
-                        )));
                     }
                 }
                 Some(_) => {
-                    return Err(Error::MalformedToken(ErrorDetails::new(
-                        "Given 'nbf' not a number",
-                    )));
+                    failures.report(Err(Error::MalformedClaims(ErrorDetails::new(format!(
+                        "Given '{string_or_array}' not a string or an array of strings"
+                    )))))?;
                 }
                 None => {}
             }
         }
 
+        for claim in &self.required_claims {
+            if !claims.contains_key(claim) {
+                failures.report(Err(Error::MissingRequiredClaim(claim.clone())))?;
+            }
+        }
+
+        let verifiers = &self.claim_verifiers;
+
+        for (claim_key, claim_verifier) in verifiers.iter() {
+            let result = verify_claim(claim_key, claim_verifier, claims.get(claim_key));
+            let result = match claim_key.as_str() {
+                "aud" => self.observe(ValidationStep::Audience, result),
+                "iss" => self.observe(ValidationStep::Issuer, result),
+                _ => result,
+            };
+            failures.report(result)?;
+        }
+
+        #[cfg(feature = "claims-schema")]
+        if let Some(schema) = &self.claims_schema {
+            failures.report(schema.validate(claims_value))?;
+        }
+
+        // Checked last, so that only otherwise valid tokens are reported
+        if let Some(jti_seen) = &self.jti_seen {
+            if failures.is_empty() {
+                failures.report(verify_jti(claims, jti_seen))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks a token's claims once its signature has been verified (or not)
+    ///
+    /// The signature's error is normally returned straight away, without even
+    /// decoding the claims, but with `uniform_timing()` the claims are still
+    /// decoded and checked so that the time taken doesn't reveal which check
+    /// failed. The first failure is returned either way.
+    fn verify_claims_after_signature(
+        &self,
+        verified: Result<(), Error>,
+        decode_claims: impl FnOnce() -> Result<Value, Error>,
+        time_now: u64,
+    ) -> Result<Value, Error> {
+        if !self.uniform_timing {
+            verified?;
+            let claims = decode_claims()?;
+            self.verify_claims_only(&claims, time_now)?;
+            return Ok(claims);
+        }
+
+        let mut failures = Failures::Uniform(verified.err());
+        let claims = decode_claims();
+        if let Ok(claims) = &claims {
+            let checked = self.check_claims(claims, time_now, &mut failures);
+            failures.report(checked)?;
+        }
+        match failures {
+            Failures::Uniform(Some(err)) => Err(err),
+            _ => claims,
+        }
+    }
+
+    /// Reads a time claim, also accepting an RFC 3339 string if enabled via
+    /// `accept_string_dates()`
+    fn claim_time(
+        &self,
+        claims: &serde_json::map::Map<String, Value>,
+        claim: &str,
+    ) -> Result<Option<u64>, Error> {
+        match claims.get(claim) {
+            Some(Value::String(date_time)) if self.accept_string_dates => {
+                match rfc3339::parse_timestamp(date_time) {
+                    Some(time) => Ok(Some(time)),
+                    None => Err(Error::MalformedToken(ErrorDetails::new(format!(
+                        "Failed to parse '{claim}' as an RFC 3339 date-time"
+                    )))),
+                }
+            }
+            _ => numeric_date(claims, claim),
+        }
+    }
+
+    /// Checks a token's time-based claims ('exp', 'nbf' and 'iat'), with any leeway
+    fn verify_time_claims(
+        &self,
+        claims: &serde_json::map::Map<String, Value>,
+        time_now: u64,
+    ) -> Result<(), Error> {
+        self.check_time_claims(claims, time_now, &mut Failures::First)
+    }
+
+    /// Checks a token's time-based claims, with any failures handled according to `failures`
+    fn check_time_claims(
+        &self,
+        claims: &serde_json::map::Map<String, Value>,
+        time_now: u64,
+        failures: &mut Failures,
+    ) -> Result<(), Error> {
+        if self.check_claim_consistency {
+            failures.report(self.verify_claim_consistency(claims))?;
+        }
+
+        if !self.ignore_iat {
+            failures.report(self.verify_iat(claims, time_now))?;
+        }
+
+        if let Some(max_token_age) = self.max_token_age {
+            failures.report(self.verify_token_age(claims, max_token_age, time_now))?;
+        }
+
+        for (claim, max_age) in &self.past_time_claims {
+            failures.report(self.verify_past_time_claim(claims, claim, *max_age, time_now))?;
+        }
+
+        if !self.ignore_nbf {
+            failures.report(
+                self.observe(ValidationStep::NotBefore, self.verify_nbf(claims, time_now)),
+            )?;
+        }
+
         if !self.ignore_exp {
-            match claims.get("exp") {
-                Some(serde_json::value::Value::Number(number)) => {
-                    if let Some(exp) = number.as_u64() {
-                        if exp <= time_now - (self.leeway as u64) {
-                            return Err(Error::TokenExpiredAt(exp));
-                        }
-                    } else {
-                        return Err(Error::MalformedToken(ErrorDetails::new(
-                            "Failed to parse 'exp' as an integer",
-                        )));
+            failures
+                .report(self.observe(ValidationStep::Expiry, self.verify_exp(claims, time_now)))?;
+        }
+
+        Ok(())
+    }
+
+    fn verify_claim_consistency(
+        &self,
+        claims: &serde_json::map::Map<String, Value>,
+    ) -> Result<(), Error> {
+        if let Some(exp) = self.claim_time(claims, "exp")? {
+            for claim in ["nbf", "iat"] {
+                if matches!(self.claim_time(claims, claim)?, Some(time) if exp < time) {
+                    return Err(Error::MalformedClaims(ErrorDetails::new(format!(
+                        "The 'exp' time is before the '{claim}' time"
+                    ))));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_iat(
+        &self,
+        claims: &serde_json::map::Map<String, Value>,
+        time_now: u64,
+    ) -> Result<(), Error> {
+        match self.claim_time(claims, "iat")? {
+            Some(iat) if iat > time_now + (self.iat_leeway as u64) => Err(Error::MalformedToken(
+                ErrorDetails::new("Issued with a future 'iat' time")
+                    .with_category(ErrorCategory::NotYetValid),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn verify_token_age(
+        &self,
+        claims: &serde_json::map::Map<String, Value>,
+        max_token_age: u64,
+        time_now: u64,
+    ) -> Result<(), Error> {
+        match self.claim_time(claims, "iat")? {
+            Some(iat) => {
+                if time_now.saturating_sub(iat)
+                    > max_token_age.saturating_add(u64::from(self.iat_leeway))
+                {
+                    return Err(Error::TokenTooOld(iat));
+                }
+                Ok(())
+            }
+            None => Err(Error::MissingRequiredClaim("iat".to_owned())),
+        }
+    }
+
+    fn verify_past_time_claim(
+        &self,
+        claims: &serde_json::map::Map<String, Value>,
+        claim: &str,
+        max_age: Option<u64>,
+        time_now: u64,
+    ) -> Result<(), Error> {
+        if let Some(time) = self.claim_time(claims, claim)? {
+            if time > time_now + (self.iat_leeway as u64) {
+                return Err(Error::MalformedToken(
+                    ErrorDetails::new(format!("Future '{claim}' time"))
+                        .with_category(ErrorCategory::NotYetValid),
+                ));
+            }
+            if let Some(max_age) = max_age {
+                if time_now.saturating_sub(time)
+                    > max_age.saturating_add(u64::from(self.iat_leeway))
+                {
+                    return Err(Error::TokenTooOld(time));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the first and last times (inclusive) that `check_time_claims()` accepts
+    fn validity_window(
+        &self,
+        claims: &serde_json::map::Map<String, Value>,
+    ) -> Result<ValidityWindow, Error> {
+        let mut not_before = None;
+        let mut not_after = None;
+        let mut starts_at = |time: u64| not_before = not_before.max(Some(time));
+        let mut ends_at = |time: u64| not_after = Some(not_after.unwrap_or(u64::MAX).min(time));
+
+        if !self.ignore_iat {
+            if let Some(iat) = self.claim_time(claims, "iat")? {
+                starts_at(iat.saturating_sub(self.iat_leeway as u64));
+            }
+        }
+        if let Some(max_token_age) = self.max_token_age {
+            if let Some(iat) = self.claim_time(claims, "iat")? {
+                ends_at(
+                    iat.saturating_add(max_token_age.saturating_add(u64::from(self.iat_leeway))),
+                );
+            }
+        }
+        for (claim, max_age) in &self.past_time_claims {
+            if let Some(time) = self.claim_time(claims, claim)? {
+                starts_at(time.saturating_sub(self.iat_leeway as u64));
+                if let Some(max_age) = max_age {
+                    ends_at(
+                        time.saturating_add(max_age.saturating_add(u64::from(self.iat_leeway))),
+                    );
+                }
+            }
+        }
+        if !self.ignore_nbf {
+            if let Some(nbf) = self.claim_time(claims, "nbf")? {
+                starts_at(nbf.saturating_sub(self.nbf_leeway as u64));
+            }
+        }
+        if !self.ignore_exp {
+            if let Some(exp) = self.claim_time(claims, "exp")? {
+                // Tokens expire at the 'exp' time, so they're valid until just before
+                ends_at((exp.saturating_add(self.exp_leeway as u64)).saturating_sub(1));
+            }
+        }
+
+        Ok((not_before, not_after))
+    }
+
+    fn verify_nbf(
+        &self,
+        claims: &serde_json::map::Map<String, Value>,
+        time_now: u64,
+    ) -> Result<(), Error> {
+        match self.claim_time(claims, "nbf")? {
+            Some(nbf) if nbf > time_now + (self.nbf_leeway as u64) => Err(Error::MalformedToken(
+                ErrorDetails::new("Time is before 'nbf'").with_category(ErrorCategory::NotYetValid),
+            )),
+            None if self.require_nbf => Err(Error::MissingRequiredClaim("nbf".to_owned())),
+            _ => Ok(()),
+        }
+    }
+
+    fn verify_exp(
+        &self,
+        claims: &serde_json::map::Map<String, Value>,
+        time_now: u64,
+    ) -> Result<(), Error> {
+        match self.claim_time(claims, "exp")? {
+            Some(exp) if exp <= time_now.saturating_sub(self.exp_leeway as u64) => {
+                Err(Error::TokenExpiredAt(exp))
+            }
+            None if self.require_exp => Err(Error::MissingRequiredClaim("exp".to_owned())),
+            None if self.missing_exp_is_expired => Err(Error::TokenExpiredAt(0)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reports the outcome of a validation step to any `on_validation()` callback
+    fn observe<T>(&self, step: ValidationStep, result: Result<T, Error>) -> Result<T, Error> {
+        if let Some(callback) = &self.on_validation {
+            (callback.func)(step, result.as_ref().map(|_| ()));
+        }
+        result
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp
+    pub fn verify_for_time(
+        &self,
+        token: impl AsRef<str>,
+        algorithm: &Algorithm,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        let slices = self.split_token_within_limit(token.as_ref())?;
+        let header =
+            decode_json_token_segment(slices.header, TokenSegment::Header, self.strict_json)?;
+        self.verify_slices_for_time(&slices, header, algorithm, time_now)
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp,
+    /// reporting every claim that fails to verify instead of just the first
+    ///
+    /// This is intended for debugging misconfigured tokens, such as to find that
+    /// a token has both expired and has the wrong audience. Since the claims can't
+    /// be trusted without a valid signature, a failure to decode the token or to
+    /// verify its header or signature is still returned straight away (as the
+    /// only error). Any replayed 'jti' is only checked if all other claims are
+    /// valid, as with `verify_for_time()`.
+    pub fn verify_collect_errors(
+        &self,
+        token: impl AsRef<str>,
+        algorithm: &Algorithm,
+        time_now: u64,
+    ) -> Result<TokenData, Vec<Error>> {
+        let fatal = |err| vec![err];
+        let slices = self
+            .split_token_within_limit(token.as_ref())
+            .map_err(fatal)?;
+        let header =
+            decode_json_token_segment(slices.header, TokenSegment::Header, self.strict_json)
+                .map_err(fatal)?;
+        self.observe(
+            ValidationStep::Signature,
+            self.verify_slices_signature(&slices, &header, algorithm),
+        )
+        .map_err(fatal)?;
+        let claims = decode_claims_segment(
+            &header,
+            slices.claims,
+            self.max_decompressed_size,
+            self.strict_json,
+        )
+        .map_err(fatal)?;
+
+        let mut failures = Failures::All(Vec::new());
+        self.check_claims(&claims, time_now, &mut failures)
+            .map_err(fatal)?;
+        match failures {
+            Failures::All(errors) if !errors.is_empty() => Err(errors),
+            _ => Ok(TokenData {
+                header,
+                claims,
+                _extensible: (),
+            }),
+        }
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp,
+    /// and also return the window of time in which its time claims are valid
+    ///
+    /// The window is returned as the first and last unix epoch timestamps
+    /// (inclusive) that the token would be accepted by this `Verifier`,
+    /// accounting for any leeway. The 'nbf' and any future 'iat' tolerance give
+    /// the start, while 'exp' and any maximum token or claim age give the end,
+    /// such as for a proxy to cache a decision until just before the token
+    /// would stop being valid. Either bound is `None` if there are no claims
+    /// (or checks) that limit it.
+    pub fn verify_for_time_with_validity(
+        &self,
+        token: impl AsRef<str>,
+        algorithm: &Algorithm,
+        time_now: u64,
+    ) -> Result<(ValidityWindow, TokenData), Error> {
+        let data = self.verify_for_time(token, algorithm, time_now)?;
+        let window = match &data.claims {
+            Value::Object(claims) => self.validity_window(claims)?,
+            _ => (None, None),
+        };
+        Ok((window, data))
+    }
+
+    /// Verify a token's signature and its claims, given a specific `SystemTime`
+    ///
+    /// Returns `Error::InvalidInput` if the time is before the unix epoch
+    #[cfg(feature = "std")]
+    pub fn verify_for_system_time(
+        &self,
+        token: impl AsRef<str>,
+        algorithm: &Algorithm,
+        time: SystemTime,
+    ) -> Result<TokenData, Error> {
+        let time_now = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(|e| {
+                Error::InvalidInput(ErrorDetails::map(
+                    "Time is before the unix epoch",
+                    Box::new(e),
+                ))
+            })?
+            .as_secs();
+        self.verify_for_time(token, algorithm, time_now)
+    }
+
+    /// INSECURE: Verify a token's claims, given a specific unix epoch timestamp,
+    /// WITHOUT verifying its signature
+    ///
+    /// This is only allowed if the Verifier was built with
+    /// `insecure_disable_signature()`, otherwise `Error::InvalidInput` is
+    /// returned. Unlike `decode_unverified()` the claims are still validated.
+    pub fn verify_insecure_for_time(
+        &self,
+        token: impl AsRef<str>,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        if !self.insecure_disable_signature {
+            return Err(Error::InvalidInput(ErrorDetails::new(
+                "Signature verification can only be skipped if disabled via insecure_disable_signature()",
+            )));
+        }
+        let slices = self.split_token_within_limit(token.as_ref())?;
+        let header =
+            decode_json_token_segment(slices.header, TokenSegment::Header, self.strict_json)?;
+        self.verify_crit(&header, &[])?;
+        self.verify_typ(&header)?;
+        self.verify_slices_claims_for_time(&slices, header, Ok(()), time_now)
+    }
+
+    /// INSECURE: Verify a token's claims WITHOUT verifying its signature
+    ///
+    /// See `verify_insecure_for_time()`
+    pub fn verify_insecure(
+        &self,
+        token: impl AsRef<str>,
+    ) -> Result<serde_json::value::Value, Error> {
+        let data = self.verify_insecure_for_time(token, self.clock.0.now())?;
+        Ok(data.claims)
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp,
+    /// where the token is given as bytes (such as read from a network buffer)
+    ///
+    /// Returns `Error::MalformedToken` if the token has any non-ASCII bytes
+    pub fn verify_for_time_bytes(
+        &self,
+        token: &[u8],
+        algorithm: &Algorithm,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        self.check_token_size(token.len())?;
+        self.verify_for_time(token_bytes_to_str(token)?, algorithm, time_now)
+    }
+
+    /// Verify a token's signature and its claims, where the token is given as bytes
+    pub fn verify_bytes(
+        &self,
+        token: &[u8],
+        algorithm: &Algorithm,
+    ) -> Result<serde_json::value::Value, Error> {
+        let data = self.verify_for_time_bytes(token, algorithm, self.clock.0.now())?;
+        Ok(data.claims)
+    }
+
+    /// Verify a token's signature and its claims
+    ///
+    /// The current time is read from the Verifier's `Clock`, which is a
+    /// `SystemClock` unless configured via `VerifierBuilder::clock()`, and all
+    /// leeway and ignore options apply just like with `verify_for_time()`.
+    pub fn verify(
+        &self,
+        token: impl AsRef<str>,
+        algorithm: &Algorithm,
+    ) -> Result<serde_json::value::Value, Error> {
+        match self.verify_for_time(token.as_ref(), algorithm, self.clock.0.now()) {
+            Ok(data) => Ok(data.claims),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp,
+    /// and deserialize the claims into a custom type
+    ///
+    /// Returns `Error::MalformedClaims` if the claims can't be deserialized
+    pub fn verify_typed_for_time<C: DeserializeOwned>(
+        &self,
+        token: impl AsRef<str>,
+        algorithm: &Algorithm,
+        time_now: u64,
+    ) -> Result<TokenData<C>, Error> {
+        let data = self.verify_for_time(token, algorithm, time_now)?;
+        let claims = serde_json::from_value(data.claims).map_err(|e| {
+            Error::MalformedClaims(ErrorDetails::map(
+                format!("Failed to deserialize claims: {e}"),
+                Box::new(e),
+            ))
+        })?;
+
+        Ok(TokenData {
+            header: data.header,
+            claims,
+            _extensible: (),
+        })
+    }
+
+    /// Verify a token's signature and its claims and deserialize the claims
+    /// into a custom type
+    pub fn verify_typed<C: DeserializeOwned>(
+        &self,
+        token: impl AsRef<str>,
+        algorithm: &Algorithm,
+    ) -> Result<C, Error> {
+        let data = self.verify_typed_for_time(token, algorithm, self.clock.0.now())?;
+        Ok(data.claims)
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp,
+    /// accepting a signature from any one of multiple keys
+    ///
+    /// This can be used during key rotation while both old and new keys are valid.
+    /// The index of the first key that verified the signature is returned along
+    /// with the token data, and the claims are only checked once.
+    ///
+    /// Returns `Error::InvalidSignature` if none of the keys verified the signature
+    pub fn verify_for_time_with_keys(
+        &self,
+        token: impl AsRef<str>,
+        algorithms: &[&Algorithm],
+        time_now: u64,
+    ) -> Result<(usize, TokenData), Error> {
+        let slices = self.split_token_within_limit(token.as_ref())?;
+        let header =
+            decode_json_token_segment(slices.header, TokenSegment::Header, self.strict_json)?;
+        self.ensure_signature_enabled()?;
+        self.verify_crit(&header, &[])?;
+        self.verify_typ(&header)?;
+        let index = algorithms.iter().position(|algorithm| {
+            self.verify_slices_signature(&slices, &header, algorithm)
+                .is_ok()
+        });
+        let verified = index.map(|_| ()).ok_or(Error::InvalidSignature());
+        let verified = self.observe(ValidationStep::Signature, verified);
+        let data = self.verify_slices_claims_for_time(&slices, header, verified, time_now)?;
+        // The claims are only verified along with the signature, so a key matched
+        Ok((index.unwrap_or_default(), data))
+    }
+
+    /// Verify a token's signature and its claims, accepting a signature from any
+    /// one of multiple keys, and returning the index of the key that matched
+    pub fn verify_with_keys(
+        &self,
+        token: impl AsRef<str>,
+        algorithms: &[&Algorithm],
+    ) -> Result<(usize, serde_json::value::Value), Error> {
+        let (index, data) =
+            self.verify_for_time_with_keys(token, algorithms, self.clock.0.now())?;
+        Ok((index, data.claims))
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp,
+    /// using the key associated with the token's issuer via `VerifierBuilder::issuer_key()`
+    ///
+    /// The 'iss' claim is read to select the key before the claims can be
+    /// trusted, but they are only returned once the signature has been verified
+    /// with that issuer's key.
+    ///
+    /// Returns `Error::InvalidIssuer` if the token doesn't have an 'iss' claim
+    /// with an associated key
+    pub fn verify_for_time_with_issuer_keys(
+        &self,
+        token: impl AsRef<str>,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        let slices = self.split_token_within_limit(token.as_ref())?;
+        let header =
+            decode_json_token_segment(slices.header, TokenSegment::Header, self.strict_json)?;
+        let claims = decode_claims_segment(
+            &header,
+            slices.claims,
+            self.max_decompressed_size,
+            self.strict_json,
+        )?;
+        let algorithm = match claims.get("iss") {
+            Some(Value::String(issuer)) => self.issuer_keys.0.get(issuer),
+            _ => None,
+        }
+        .ok_or(Error::InvalidIssuer());
+        let algorithm = self.observe(ValidationStep::Issuer, algorithm)?;
+        self.verify_slices_for_time(&slices, header, algorithm, time_now)
+    }
+
+    /// Verify a token's signature and its claims, using the key associated with
+    /// the token's issuer via `VerifierBuilder::issuer_key()`
+    pub fn verify_with_issuer_keys(
+        &self,
+        token: impl AsRef<str>,
+    ) -> Result<serde_json::value::Value, Error> {
+        let data = self.verify_for_time_with_issuer_keys(token, self.clock.0.now())?;
+        Ok(data.claims)
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp,
+    /// using the key from a `JwkSet` that matches the token's `kid`
+    ///
+    /// Returns `Error::NoMatchingKey` if the token's `kid` isn't found in the set
+    ///
+    /// Tokens without a `kid` are rejected with `Error::MalformedToken` unless
+    /// enabled via `VerifierBuilder::jwks_without_kid()`.
+    pub fn verify_for_time_with_jwks(
+        &self,
+        token: impl AsRef<str>,
+        jwks: &JwkSet,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        let slices = self.split_token_within_limit(token.as_ref())?;
+        let header =
+            decode_json_token_segment(slices.header, TokenSegment::Header, self.strict_json)?;
+        self.ensure_signature_enabled()?;
+        if self.jwks_without_kid && header_kid(&header)?.is_none() {
+            return self.verify_slices_with_any_jwk_for_time(&slices, header, jwks, time_now);
+        }
+        let kid = required_jwks_kid(&header)?;
+        let algorithm = jwks
+            .find(kid)
+            .ok_or_else(|| Error::NoMatchingKey(kid.to_owned()))?;
+        self.verify_slices_for_time(&slices, header, algorithm, time_now)
+    }
+
+    /// Verify a token's signature and its claims, using the key from a `JwkSet`
+    /// that matches the token's `kid`
+    pub fn verify_with_jwks(
+        &self,
+        token: impl AsRef<str>,
+        jwks: &JwkSet,
+    ) -> Result<serde_json::value::Value, Error> {
+        let data = self.verify_for_time_with_jwks(token, jwks, self.clock.0.now())?;
+        Ok(data.claims)
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp,
+    /// using the HMAC secret from a `HmacKeyStore` that matches the token's `kid`
+    ///
+    /// Returns `Error::NoMatchingKey` if the token's `kid` isn't found in the store
+    pub fn verify_for_time_with_hmac_keys(
+        &self,
+        token: impl AsRef<str>,
+        keys: &HmacKeyStore,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        let slices = self.split_token_within_limit(token.as_ref())?;
+        let header =
+            decode_json_token_segment(slices.header, TokenSegment::Header, self.strict_json)?;
+        let kid = header_kid(&header)?.ok_or_else(|| {
+            Error::MalformedToken(ErrorDetails::new(
+                "Missing 'kid' needed to select an HMAC secret",
+            ))
+        })?;
+        let algorithm = keys
+            .find(kid)
+            .ok_or_else(|| Error::NoMatchingKey(kid.to_owned()))?;
+        self.verify_slices_for_time(&slices, header, algorithm, time_now)
+    }
+
+    /// Verify a token's signature and its claims, using the HMAC secret from a
+    /// `HmacKeyStore` that matches the token's `kid`
+    pub fn verify_with_hmac_keys(
+        &self,
+        token: impl AsRef<str>,
+        keys: &HmacKeyStore,
+    ) -> Result<serde_json::value::Value, Error> {
+        let data = self.verify_for_time_with_hmac_keys(token, keys, self.clock.0.now())?;
+        Ok(data.claims)
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp,
+    /// accepting a signature from any of the HMAC secrets given via
+    /// `VerifierBuilder::hmac_secrets()`
+    ///
+    /// The secrets are tried in order, with the token's 'alg' header selecting
+    /// the HMAC algorithm, and the claims are only checked once.
+    ///
+    /// Returns `Error::InvalidSignature` if none of the secrets verified the signature
+    pub fn verify_for_time_with_hmac_secrets(
+        &self,
+        token: impl AsRef<str>,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        let slices = self.split_token_within_limit(token.as_ref())?;
+        let header =
+            decode_json_token_segment(slices.header, TokenSegment::Header, self.strict_json)?;
+        self.ensure_signature_enabled()?;
+        self.verify_crit(&header, &[])?;
+        self.verify_typ(&header)?;
+        let alg = header.get("alg").and_then(Value::as_str);
+        let verified = self
+            .hmac_secrets
+            .0
+            .iter()
+            .filter(|algorithm| alg == Some(algorithm.id().into()))
+            .any(|algorithm| {
+                self.verify_slices_signature(&slices, &header, algorithm)
+                    .is_ok()
+            });
+        let verified = if verified {
+            Ok(())
+        } else {
+            Err(Error::InvalidSignature())
+        };
+        let verified = self.observe(ValidationStep::Signature, verified);
+        self.verify_slices_claims_for_time(&slices, header, verified, time_now)
+    }
+
+    /// Verify a token's signature and its claims, accepting a signature from any
+    /// of the HMAC secrets given via `VerifierBuilder::hmac_secrets()`
+    pub fn verify_with_hmac_secrets(
+        &self,
+        token: impl AsRef<str>,
+    ) -> Result<serde_json::value::Value, Error> {
+        let data = self.verify_for_time_with_hmac_secrets(token, self.clock.0.now())?;
+        Ok(data.claims)
+    }
+
+    /// Verify a token's signature and its claims, using the key from a `RemoteJwks`
+    /// that matches the token's `kid`
+    ///
+    /// The key set will be fetched if it's not already cached, or if the cache
+    /// has expired, or if the `kid` isn't found (in case keys have been rotated)
+    #[cfg(feature = "remote-jwks")]
+    pub async fn verify_with_remote_jwks<F: JwksFetcher>(
+        &self,
+        token: impl AsRef<str>,
+        jwks: &RemoteJwks<F>,
+    ) -> Result<serde_json::value::Value, Error> {
+        let slices = self.split_token_within_limit(token.as_ref())?;
+        let header =
+            decode_json_token_segment(slices.header, TokenSegment::Header, self.strict_json)?;
+        let algorithm = jwks.get_key(required_jwks_kid(&header)?).await?;
+        let data = self.verify_slices_for_time(&slices, header, &algorithm, self.clock.0.now())?;
+        Ok(data.claims)
+    }
+
+    fn verify_slices_for_time(
+        &self,
+        slices: &TokenSlices,
+        header: serde_json::value::Value,
+        algorithm: &Algorithm,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        let verified = self.observe(
+            ValidationStep::Signature,
+            self.verify_slices_signature(slices, &header, algorithm),
+        );
+        self.verify_slices_claims_for_time(slices, header, verified, time_now)
+    }
+
+    /// Verify the signature of a token with a detached payload (RFC 7797), such
+    /// as created by `encode_detached()`, returning the token's header
+    ///
+    /// The token must have an empty payload segment (`"HEADER..SIGNATURE"`) and
+    /// the signing input is reconstructed from the given payload, which is
+    /// signed as-is if the header has `"b64": false` or else is base64 encoded.
+    /// Since the payload isn't necessarily JSON, no claims are verified.
+    ///
+    /// Returns `Error::MalformedToken` if the token's payload segment isn't
+    /// empty or if the header has a 'b64' parameter that isn't also listed as
+    /// critical, and `Error::UnsupportedCriticalParam` for any 'crit' parameter
+    /// other than "b64" that hasn't been allowed via `allow_crit()`.
+    pub fn verify_detached(
+        &self,
+        token: impl AsRef<str>,
+        payload: &[u8],
+        algorithm: &Algorithm,
+    ) -> Result<serde_json::value::Value, Error> {
+        let slices = self.split_token_within_limit(token.as_ref())?;
+        if !slices.claims.is_empty() {
+            return Err(Error::MalformedToken(ErrorDetails::new(
+                "Expected detached token to have an empty payload",
+            )));
+        }
+        let header =
+            decode_json_token_segment(slices.header, TokenSegment::Header, self.strict_json)?;
+        self.verify_algorithm(&header, algorithm)?;
+        self.verify_crit(&header, &["b64"])?;
+        self.verify_typ(&header)?;
+        self.verify_x5c(&header, algorithm)?;
+
+        let mut message = slices.header.as_bytes().to_vec();
+        message.push(b'.');
+        if detached_payload_is_encoded(&header)? {
+            message.extend_from_slice(b64_encode(payload).as_bytes());
+        } else {
+            message.extend_from_slice(payload);
+        }
+        self.observe(
+            ValidationStep::Signature,
+            verify_signature_bytes(&header, &message, slices.signature, algorithm),
+        )?;
+
+        Ok(header)
+    }
+
+    fn check_token_size(&self, size: usize) -> Result<(), Error> {
+        if size > self.max_token_bytes {
+            return Err(Error::TokenTooLarge(size));
+        }
+        Ok(())
+    }
+
+    /// Splits a token, after checking it's no larger than `max_token_bytes()`
+    ///
+    /// Surrounding whitespace is trimmed, along with any "Bearer " prefix if
+    /// enabled via `strip_bearer_prefix()`
+    fn split_token_within_limit<'a>(&self, token: &'a str) -> Result<TokenSlices<'a>, Error> {
+        self.check_token_size(token.len())?;
+
+        let mut token = token.trim_matches(|c: char| c.is_ascii_whitespace());
+        if self.strip_bearer_prefix {
+            if let Some(prefix) = token.get(..BEARER_PREFIX.len()) {
+                if prefix.eq_ignore_ascii_case(BEARER_PREFIX) {
+                    token = token[BEARER_PREFIX.len()..]
+                        .trim_start_matches(|c: char| c.is_ascii_whitespace());
+                }
+            }
+        }
+        if token.contains(|c: char| c.is_ascii_whitespace()) {
+            let desc = if self.strip_bearer_prefix {
+                "Unexpected whitespace within token"
+            } else {
+                "Unexpected whitespace within token (see strip_bearer_prefix() to accept a 'Bearer ' prefix)"
+            };
+            return Err(Error::MalformedToken(ErrorDetails::new(desc)));
+        }
+
+        split_token(token)
+    }
+
+    /// Verifies a token without a 'kid' with any key in the set for its 'alg'
+    ///
+    /// The keys' own 'kid's are ignored, since the token doesn't have one to match.
+    fn verify_slices_with_any_jwk_for_time(
+        &self,
+        slices: &TokenSlices,
+        header: serde_json::value::Value,
+        jwks: &JwkSet,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        let alg = header.get("alg").and_then(Value::as_str);
+        let mut keys = jwks.keys().filter(|key| Some(key.name()) == alg).peekable();
+        if keys.peek().is_none() {
+            return Err(Error::AlgorithmMismatch());
+        }
+        self.verify_crit(&header, &[])?;
+        self.verify_typ(&header)?;
+        let verified = keys
+            .any(|key| {
+                self.verify_algorithm(&header, key).is_ok()
+                    && self.verify_x5c(&header, key).is_ok()
+                    && verify_encoded_signature(slices.message.as_bytes(), slices.signature, key)
+                        .is_ok()
+            })
+            .then_some(())
+            .ok_or(Error::InvalidSignature());
+        let verified = self.observe(ValidationStep::Signature, verified);
+        self.verify_slices_claims_for_time(slices, header, verified, time_now)
+    }
+
+    fn verify_slices_signature(
+        &self,
+        slices: &TokenSlices,
+        header: &serde_json::value::Value,
+        algorithm: &Algorithm,
+    ) -> Result<(), Error> {
+        self.verify_algorithm(header, algorithm)?;
+        self.verify_crit(header, &[])?;
+        self.verify_typ(header)?;
+        self.verify_x5c(header, algorithm)?;
+        verify_signature_only(header, slices.message, slices.signature, algorithm)
+    }
+
+    /// Checks that every 'crit' header parameter is either understood by
+    /// the caller or has been allowed by the application
+    fn verify_crit(
+        &self,
+        header: &serde_json::value::Value,
+        understood: &[&str],
+    ) -> Result<(), Error> {
+        let crit = match header.get("crit") {
+            None => return Ok(()),
+            Some(Value::Array(crit)) if !crit.is_empty() => crit,
+            Some(_) => {
+                return Err(Error::MalformedToken(ErrorDetails::new(
+                    "Expected 'crit' header parameter to be a non-empty array",
+                )))
+            }
+        };
+        for name in crit {
+            match name {
+                Value::String(name) => {
+                    if !understood.contains(&name.as_str()) && !self.allowed_crit.contains(name) {
+                        return Err(Error::UnsupportedCriticalParam(name.clone()));
                     }
                 }
-                Some(_) => {
+                _ => {
                     return Err(Error::MalformedToken(ErrorDetails::new(
-                        "Given 'exp' not a number",
-                    )));
+                        "Non-string 'crit' header parameter",
+                    )))
                 }
-                None => {}
             }
         }
+        Ok(())
+    }
 
-        // At least verify the type for these standard claims
-        // (Values can separately be validated via .claim_verifiers)
-        for &string_claim in &["iss", "sub"] {
-            match claims.get(string_claim) {
-                Some(serde_json::value::Value::String(_)) => {}
-                Some(_) => {
-                    return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                        "Given '{string_claim}' not a string"
-                    ))));
+    /// Checks the 'typ' header parameter, if a type is required via `require_typ()`
+    fn verify_typ(&self, header: &serde_json::value::Value) -> Result<(), Error> {
+        let required = match &self.required_typ {
+            Some(required) => required,
+            None => return Ok(()),
+        };
+        match header.get("typ") {
+            Some(Value::String(typ)) if media_type_eq(typ, required) => Ok(()),
+            _ => Err(Error::InvalidHeader("typ".to_owned())),
+        }
+    }
+
+    /// Checks that the first certificate of the 'x5c' header parameter has the
+    /// verifying key, if required via `require_x5c_key_binding()`
+    ///
+    /// Any 'x5t#S256' thumbprint must also match that certificate
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    fn verify_x5c(
+        &self,
+        header: &serde_json::value::Value,
+        algorithm: &Algorithm,
+    ) -> Result<(), Error> {
+        if !self.x5c_key_binding {
+            return Ok(());
+        }
+        #[cfg(feature = "std")]
+        {
+            let certificate = crate::header::x5c_leaf_certificate(header)?;
+            if let Some(thumbprint) = header.get("x5t#S256") {
+                if *thumbprint != crate::header::x5t_s256_thumbprint(&certificate) {
+                    return Err(Error::InvalidHeader("x5t#S256".to_owned()));
                 }
-                None => {}
             }
+            match crate::x509::certificate_public_key(&certificate) {
+                Some(key) if algorithm.has_public_key(&key) => {}
+                _ => return Err(Error::InvalidHeader("x5c".to_owned())),
+            }
+        }
+        Ok(())
+    }
+
+    /// A Verifier with signature verification disabled must never be given a
+    /// key, in case the caller expects the signature to be verified
+    fn ensure_signature_enabled(&self) -> Result<(), Error> {
+        if self.insecure_disable_signature {
+            return Err(Error::InvalidInput(ErrorDetails::new(
+                "Signature verification is disabled for this Verifier, via insecure_disable_signature()",
+            )));
+        }
+        Ok(())
+    }
+
+    fn verify_algorithm(
+        &self,
+        header: &serde_json::value::Value,
+        algorithm: &Algorithm,
+    ) -> Result<(), Error> {
+        self.ensure_signature_enabled()?;
+
+        // Unsecured tokens are never considered valid, even if the caller has
+        // (mistakenly) given an unsecured Algorithm, and we check the header
+        // too in case the given algorithm is more permissive than expected
+        if algorithm.id() == AlgorithmID::NONE || header.get("alg") == Some(&Value::from("none")) {
+            return Err(Error::AlgorithmMismatch());
+        }
+
+        // Guard against the classic RS256 -> HS256 downgrade, where a token is
+        // signed with HMAC using a public key as the secret, so that we never
+        // entertain a symmetric 'alg' when we've been given an asymmetric key
+        if !is_hmac(algorithm.id()) {
+            if let Some(Value::String(alg)) = header.get("alg") {
+                if alg.starts_with("HS") {
+                    return Err(Error::AlgorithmMismatch());
+                }
+            }
+        }
+
+        // NB: verify_signature_only() checks the header 'alg' matches the algorithm
+        if let Some(algorithms) = &self.algorithms {
+            if !algorithms.contains(&algorithm.id()) {
+                return Err(Error::AlgorithmMismatch());
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes and checks a token's claims, given the result of verifying its signature
+    fn verify_slices_claims_for_time(
+        &self,
+        slices: &TokenSlices,
+        header: serde_json::value::Value,
+        verified: Result<(), Error>,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        let claims = self.verify_claims_after_signature(
+            verified,
+            || {
+                decode_claims_segment(
+                    &header,
+                    slices.claims,
+                    self.max_decompressed_size,
+                    self.strict_json,
+                )
+            },
+            time_now,
+        )?;
+
+        Ok(TokenData {
+            header,
+            claims,
+            _extensible: (),
+        })
+    }
+}
+
+/// A `Verifier` paired with a single key, for verifying many tokens signed by that key
+///
+/// This is intended for gateways and proxies that verify a stream of tokens
+/// from one issuer. Buffers for decoding each token are reused, instead of
+/// being allocated per token, and the most recently seen header is kept
+/// already decoded and checked, since consecutive tokens from the same
+/// issuer typically have identical headers.
+///
+/// Verification requires `&mut self`, so for use across threads there should
+/// be a `PreparedVerifier` per thread, which can share the same key via an
+/// `Arc<Algorithm>`:
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonwebtokens as jwt;
+/// # use jwt::{Algorithm, AlgorithmID, PreparedVerifier, Verifier};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret")?;
+/// # let token = jwt::encode(&json!({ "alg": "HS256" }), &json!({ "sub": "user" }), &alg)?;
+/// let verifier = Verifier::create().build()?;
+/// let mut prepared = PreparedVerifier::new(verifier, alg);
+///
+/// let data = prepared.verify_for_time(&token, 0)?;
+/// assert_eq!(data.get_str("sub"), Some("user"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PreparedVerifier {
+    verifier: Verifier,
+    algorithm: Arc<Algorithm>,
+    hmac_key: Option<HmacKey>,
+    buf: Vec<u8>,
+    checked_header: Option<(String, Value)>,
+}
+
+impl PreparedVerifier {
+    /// Pairs a `Verifier` with the key used to verify all tokens
+    pub fn new(verifier: Verifier, algorithm: impl Into<Arc<Algorithm>>) -> Self {
+        let algorithm = algorithm.into();
+        PreparedVerifier {
+            verifier,
+            hmac_key: algorithm.prepare_hmac_key(),
+            algorithm,
+            buf: Vec::new(),
+            checked_header: None,
+        }
+    }
+
+    /// The `Verifier` used to check tokens
+    pub fn verifier(&self) -> &Verifier {
+        &self.verifier
+    }
+
+    /// The key used to verify tokens
+    pub fn algorithm(&self) -> &Algorithm {
+        &self.algorithm
+    }
+
+    /// Verify a token's signature and its claims, given a specific unix epoch timestamp
+    ///
+    /// This is equivalent to `Verifier::verify_for_time()` with the prepared key.
+    pub fn verify_for_time(
+        &mut self,
+        token: impl AsRef<str>,
+        time_now: u64,
+    ) -> Result<TokenData, Error> {
+        let verifier = &self.verifier;
+        let slices = verifier.split_token_within_limit(token.as_ref())?;
+
+        let header = match &self.checked_header {
+            Some((encoded, header)) if encoded == slices.header => header,
+            _ => {
+                // Forget the previous header first, in case this one fails its checks
+                self.checked_header = None;
+                let header = decode_json_token_segment_with_buf(
+                    slices.header,
+                    TokenSegment::Header,
+                    verifier.strict_json,
+                    &mut self.buf,
+                )?;
+                let checked = verifier
+                    .verify_algorithm(&header, &self.algorithm)
+                    .and_then(|()| verifier.verify_crit(&header, &[]))
+                    .and_then(|()| verifier.verify_typ(&header))
+                    .and_then(|()| verifier.verify_x5c(&header, &self.algorithm))
+                    .and_then(|()| verify_header_matches_algorithm(&header, &self.algorithm));
+                if let Err(error) = checked {
+                    return verifier.observe(ValidationStep::Signature, Err(error));
+                }
+                &self
+                    .checked_header
+                    .insert((slices.header.to_owned(), header))
+                    .1
+            }
+        };
+
+        let message = slices.message.as_bytes();
+        let verified = with_decoded_signature(slices.signature, |signature| match &self.hmac_key {
+            Some(key) => crate::crypto::hmac::verify_prepared(key, message, signature),
+            None => self.algorithm.verify_bytes(message, signature),
+        });
+        let verified = verifier.observe(ValidationStep::Signature, verified);
+
+        let buf = &mut self.buf;
+        let claims = verifier.verify_claims_after_signature(
+            verified,
+            || {
+                decode_claims_segment_with_buf(
+                    header,
+                    slices.claims,
+                    verifier.max_decompressed_size,
+                    verifier.strict_json,
+                    buf,
+                )
+            },
+            time_now,
+        )?;
+
+        Ok(TokenData {
+            header: header.clone(),
+            claims,
+            _extensible: (),
+        })
+    }
+
+    /// Verify a token's signature and its claims, using the `Verifier`'s `Clock`
+    pub fn verify(&mut self, token: impl AsRef<str>) -> Result<Value, Error> {
+        let time_now = self.verifier.clock.0.now();
+        let data = self.verify_for_time(token, time_now)?;
+        Ok(data.claims)
+    }
+}
+
+/// Checks a token's 'jti' hasn't been seen before, via a `jti_seen()` callback
+fn verify_jti(
+    claims: &serde_json::map::Map<String, Value>,
+    jti_seen: &JtiSeenCallback,
+) -> Result<(), Error> {
+    match claims.get("jti") {
+        Some(Value::String(jti)) => {
+            if (jti_seen.func)(jti) {
+                return Err(Error::TokenReplayed(jti.clone()));
+            }
+            Ok(())
         }
+        Some(_) => Err(Error::MalformedToken(ErrorDetails::new(
+            "Given 'jti' not a string",
+        ))),
+        None => Err(Error::MissingRequiredClaim("jti".to_owned())),
+    }
+}
 
-        for &string_or_array in &["aud"] {
-            match claims.get(string_or_array) {
-                Some(serde_json::value::Value::String(_)) => {}
-                Some(serde_json::value::Value::Array(claim_array)) => {
-                    for subclaim in claim_array {
-                        if !subclaim.is_string() {
-                            return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                                "Claim {string_or_array}: array elements are not all strings"
-                            ))));
-                        }
-                    }
+/// Checks a claim's value (if found) against a claim verifier
+fn verify_claim(
+    claim_key: &str,
+    claim_verifier: &VerifierKind,
+    claim: Option<&Value>,
+) -> Result<(), Error> {
+    match claim {
+        Some(claim_value) => {
+            if let VerifierKind::Closure(closure_container) = claim_verifier {
+                let closure = closure_container.func.as_ref();
+                if !closure(claim_value) {
+                    return Err(claim_mismatch(claim_key));
                 }
-                Some(_) => {
-                    return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                        "Given '{string_or_array}' not a string or an array of strings"
-                    ))));
+            } else if let VerifierKind::ValueConstant(constant) = claim_verifier {
+                if claim_value != constant {
+                    return Err(claim_mismatch(claim_key));
                 }
-                None => {}
-            }
-        }
-
-        let verifiers = &self.claim_verifiers;
-
-        for (claim_key, claim_verifier) in verifiers.iter() {
-            match claims.get(claim_key) {
-                Some(claim_value) => {
-                    if let VerifierKind::Closure(closure_container) = claim_verifier {
-                        let closure = closure_container.func.as_ref();
-                        if !closure(claim_value) {
-                            return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                                "Claim {claim_key}: verifier callback returned false for '{claim_value}'"
-                            ))));
+            } else if let Value::String(claim_string) = claim_value {
+                match claim_verifier {
+                    VerifierKind::StringConstant(constant) => {
+                        if claim_string != constant {
+                            return Err(claim_mismatch(claim_key));
                         }
-                    } else if let Value::String(claim_string) = claim_value {
-                        match claim_verifier {
-                            VerifierKind::StringConstant(constant) => {
-                                if claim_string != constant {
-                                    return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                                        "Claim {claim_key}: {claim_string} != {constant}"
-                                    ))));
-                                }
+                    }
+                    VerifierKind::StringSet(constant_set) => {
+                        if !constant_set.contains(claim_string) {
+                            return Err(claim_mismatch(claim_key));
+                        }
+                    }
+                    #[cfg(feature = "matching")]
+                    VerifierKind::StringPattern(pattern) => {
+                        if !pattern.is_match(claim_string) {
+                            return Err(claim_mismatch(claim_key));
+                        }
+                    }
+                    #[cfg(feature = "matching")]
+                    VerifierKind::StringPatternSet(pattern_set) => {
+                        let mut found_match = false;
+                        for p in pattern_set {
+                            if p.is_match(claim_string) {
+                                found_match = true;
+                                break;
                             }
-                            VerifierKind::StringSet(constant_set) => {
-                                if !constant_set.contains(claim_string) {
-                                    return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                                        "Claim {claim_key}: {claim_string} not in set"
-                                    ))));
+                        }
+                        if !found_match {
+                            return Err(claim_mismatch(claim_key));
+                        }
+                    }
+                    VerifierKind::StringOrArrayContainsOneOf(constant_set) => {
+                        if !constant_set.contains(claim_string) {
+                            return Err(claim_mismatch(claim_key));
+                        }
+                    }
+                    VerifierKind::StringOrArrayContains(contains) => {
+                        if claim_string != contains {
+                            return Err(claim_mismatch(claim_key));
+                        }
+                    }
+                    VerifierKind::StringOrArrayContainsAll(constant_set) => {
+                        if constant_set.iter().any(|value| value != claim_string) {
+                            return Err(claim_mismatch(claim_key));
+                        }
+                    }
+                    _ => {
+                        return Err(Error::MalformedToken(ErrorDetails::new(format!(
+                            "Claim {claim_key}: has unexpected type (String)"
+                        ))));
+                    }
+                }
+            } else if let Value::Array(claim_array) = claim_value {
+                match claim_verifier {
+                    VerifierKind::StringOrArrayContains(contains) => {
+                        let mut found = false;
+                        for subclaim in claim_array {
+                            match subclaim {
+                                Value::String(subclaim_string) => {
+                                    if subclaim_string == contains {
+                                        found = true;
+                                        // XXX: don't break from loop early since we want to
+                                        // check _all_ array elements are strings
+                                    }
                                 }
-                            }
-                            #[cfg(feature = "matching")]
-                            VerifierKind::StringPattern(pattern) => {
-                                if !pattern.is_match(claim_string) {
+                                _ => {
                                     return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                                        "Claim {claim_key}: {claim_string} doesn't match regex {pattern}"
+                                        "Claim {claim_key}: array elements are not all strings"
                                     ))));
                                 }
                             }
-                            #[cfg(feature = "matching")]
-                            VerifierKind::StringPatternSet(pattern_set) => {
-                                let mut found_match = false;
-                                for p in pattern_set {
-                                    if p.is_match(claim_string) {
-                                        found_match = true;
-                                        break;
+                        }
+                        if !found {
+                            return Err(claim_mismatch(claim_key));
+                        }
+                    }
+                    VerifierKind::StringOrArrayContainsOneOf(constant_set) => {
+                        let mut found = false;
+                        for subclaim in claim_array {
+                            match subclaim {
+                                Value::String(subclaim_string) => {
+                                    if constant_set.contains(subclaim_string) {
+                                        found = true;
                                     }
                                 }
-                                if !found_match {
-                                    return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                                        "Claim {claim_key}: {claim_string} doesn't match regex set"
-                                    ))));
-                                }
-                            }
-                            VerifierKind::StringOrArrayContains(contains) => {
-                                if claim_string != contains {
+                                _ => {
                                     return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                                        "Claim {claim_key}: {claim_string} != {contains}"
+                                        "Claim {claim_key}: array elements are not all strings"
                                     ))));
                                 }
                             }
-                            _ => {
-                                return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                                    "Claim {claim_key}: has unexpected type (String)"
-                                ))));
-                            }
                         }
-                    } else if let Value::Array(claim_array) = claim_value {
-                        match claim_verifier {
-                            VerifierKind::StringOrArrayContains(contains) => {
-                                let mut found = false;
-                                for subclaim in claim_array {
-                                    match subclaim {
-                                        Value::String(subclaim_string) => {
-                                            if subclaim_string == contains {
-                                                found = true;
-                                                // XXX: don't break from loop early since we want to
-                                                // check _all_ array elements are strings
-                                            }
-                                        }
-                                        _ => {
-                                            return Err(Error::MalformedToken(ErrorDetails::new(
-                                                format!(
-                                                    "Claim {claim_key}: array elements are not all strings"
-                                                ),
-                                            )));
-                                        }
+                        if !found {
+                            return Err(claim_mismatch(claim_key));
+                        }
+                    }
+                    VerifierKind::StringOrArrayContainsAll(constant_set) => {
+                        let mut found = BTreeSet::new();
+                        for subclaim in claim_array {
+                            match subclaim {
+                                Value::String(subclaim_string) => {
+                                    if constant_set.contains(subclaim_string) {
+                                        found.insert(subclaim_string);
                                     }
                                 }
-                                if !found {
+                                _ => {
                                     return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                                        "Claim {claim_key}: array did not contain '{contains}'"
+                                        "Claim {claim_key}: array elements are not all strings"
                                     ))));
                                 }
                             }
-                            _ => {
-                                return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                                    "Claim {claim_key}: has unexpected type (Array)"
-                                ))));
-                            }
                         }
-                    } else if let Value::Number(_claim_number) = claim_value {
-                        // TODO: support verifying numeric claims
-                        return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                            "Claim {claim_key}: has unexpected type (Number)"
-                        ))));
-                    } else {
+                        if found.len() != constant_set.len() {
+                            return Err(claim_mismatch(claim_key));
+                        }
+                    }
+                    _ => {
                         return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                            "Claim {claim_key}: has unexpected type"
+                            "Claim {claim_key}: has unexpected type (Array)"
                         ))));
                     }
                 }
-                _ => {
-                    // If we have a verifier for particular claim then that claim is required
-                    return Err(Error::MalformedToken(ErrorDetails::new(format!(
-                        "Claim {claim_key}: missing"
-                    ))));
-                }
+            } else if let Value::Number(_claim_number) = claim_value {
+                // TODO: support verifying numeric claims
+                return Err(Error::MalformedToken(ErrorDetails::new(format!(
+                    "Claim {claim_key}: has unexpected type (Number)"
+                ))));
+            } else {
+                return Err(Error::MalformedToken(ErrorDetails::new(format!(
+                    "Claim {claim_key}: has unexpected type"
+                ))));
             }
         }
-        Ok(())
+        _ => {
+            // If we have a verifier for particular claim then that claim is required
+            return Err(claim_missing(claim_key));
+        }
     }
+    Ok(())
+}
 
-    /// Verify a token's signature and its claims, given a specific unix epoch timestamp
-    pub fn verify_for_time(
-        &self,
-        token: impl AsRef<str>,
-        algorithm: &Algorithm,
-        time_now: u64,
-    ) -> Result<TokenData, Error> {
-        let TokenSlices {
-            message,
-            signature,
-            header,
-            claims,
-        } = split_token(token.as_ref())?;
+/// Looks up a NumericDate claim (a unix epoch timestamp in seconds)
+///
+/// Some issuers encode timestamps as floats, so integral floats (such as
+/// `1575057015.0`) are also accepted, but fractional values are rejected
+pub(crate) fn numeric_date(
+    claims: &serde_json::map::Map<String, Value>,
+    claim: &str,
+) -> Result<Option<u64>, Error> {
+    match claims.get(claim) {
+        Some(Value::Number(number)) => {
+            if let Some(time) = number.as_u64() {
+                return Ok(Some(time));
+            }
+            // (`f64::fract()` isn't available without `std`)
+            match number.as_f64() {
+                Some(time)
+                    if time >= 0.0 && time < u64::MAX as f64 && time as u64 as f64 == time =>
+                {
+                    Ok(Some(time as u64))
+                }
+                _ => Err(Error::MalformedToken(ErrorDetails::new(format!(
+                    "Failed to parse '{claim}' as an integer"
+                )))),
+            }
+        }
+        Some(_) => Err(Error::MalformedToken(ErrorDetails::new(format!(
+            "Given '{claim}' not a number"
+        )))),
+        None => Ok(None),
+    }
+}
 
-        let header = decode_json_token_slice(header)?;
-        verify_signature_only(&header, message, signature, algorithm)?;
-        let claims = decode_json_token_slice(claims)?;
-        self.verify_claims_only(&claims, time_now)?;
+/// Checks a detached token's 'b64' header parameter, returning whether the
+/// payload is base64 encoded for the signing input
+fn detached_payload_is_encoded(header: &serde_json::value::Value) -> Result<bool, Error> {
+    let b64_is_critical = match header.get("crit") {
+        Some(Value::Array(crit)) => crit.iter().any(|name| name == "b64"),
+        _ => false,
+    };
 
-        Ok(TokenData {
-            header,
-            claims,
-            _extensible: (),
-        })
+    match header.get("b64") {
+        None => Ok(true),
+        Some(Value::Bool(b64)) if b64_is_critical => Ok(*b64),
+        Some(Value::Bool(_)) => Err(Error::MalformedToken(ErrorDetails::new(
+            "The 'b64' header parameter must be listed in 'crit'",
+        ))),
+        Some(_) => Err(Error::MalformedToken(ErrorDetails::new(
+            "Non-boolean 'b64' header parameter",
+        ))),
     }
+}
 
-    /// Verify a token's signature and its claims
-    pub fn verify(
-        &self,
-        token: impl AsRef<str>,
-        algorithm: &Algorithm,
-    ) -> Result<serde_json::value::Value, Error> {
-        let timestamp = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-            Ok(n) => n.as_secs(),
-            Err(_) => {
-                return Err(Error::InvalidInput(ErrorDetails::new(
-                    "SystemTime before UNIX EPOCH!",
-                )))
+/// Compares media types case-insensitively, where an "application/" prefix is optional
+///
+/// RFC 7515 recommends omitting the "application/" prefix from a 'typ' when
+/// there's no other '/' so e.g. "at+jwt" and "application/at+jwt" are equal.
+fn media_type_eq(a: &str, b: &str) -> bool {
+    fn short_form(media_type: &str) -> &str {
+        const PREFIX: &str = "application/";
+        match media_type.get(..PREFIX.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(PREFIX) => {
+                let rest = &media_type[PREFIX.len()..];
+                if rest.contains('/') {
+                    media_type
+                } else {
+                    rest
+                }
             }
-        };
-
-        match self.verify_for_time(token.as_ref(), algorithm, timestamp) {
-            Ok(data) => Ok(data.claims),
-            Err(error) => Err(error),
+            _ => media_type,
         }
     }
+    short_form(a).eq_ignore_ascii_case(short_form(b))
+}
+
+fn is_hmac(id: AlgorithmID) -> bool {
+    matches!(
+        id,
+        AlgorithmID::HS256 | AlgorithmID::HS384 | AlgorithmID::HS512
+    )
+}
+
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// The compact serialization of a token is always ASCII
+fn token_bytes_to_str(token: &[u8]) -> Result<&str, Error> {
+    if !token.is_ascii() {
+        return Err(Error::MalformedToken(ErrorDetails::new(
+            "Token contains non-ASCII bytes",
+        )));
+    }
+    core::str::from_utf8(token)
+        .map_err(|e| Error::MalformedToken(ErrorDetails::map("utf8 decode failure", Box::new(e))))
+}
+
+fn required_jwks_kid(header: &serde_json::value::Value) -> Result<&str, Error> {
+    header_kid(header)?.ok_or_else(|| {
+        Error::MalformedToken(ErrorDetails::new(
+            "Missing 'kid' needed to select a key from JWKS",
+        ))
+    })
 }
 
 /// Configures the requirements for checking token claims with a builder-pattern API
-#[derive(Debug, Default)]
+///
+/// A builder can be cloned to configure several similar `Verifier`s from a
+/// common template.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct VerifierBuilder {
     leeway: u32,
+    exp_leeway: Option<u32>,
+    nbf_leeway: Option<u32>,
+    iat_leeway: Option<u32>,
+    max_token_age: Option<Duration>,
+    past_time_claims: BTreeMap<String, Option<Duration>>,
+    algorithms: Option<Vec<AlgorithmID>>,
     ignore_exp: bool,
     ignore_nbf: bool,
     ignore_iat: bool,
+    require_exp: bool,
+    missing_exp_is_expired: bool,
+    require_nbf: bool,
+    allowed_crit: BTreeSet<String>,
+    required_typ: Option<String>,
+    max_decompressed_size: usize,
+    max_token_bytes: usize,
+    strip_bearer_prefix: bool,
+    jwks_without_kid: bool,
+    x5c_key_binding: bool,
+    insecure_disable_signature: bool,
+    deny_insecure: bool,
+    check_claim_consistency: bool,
+    strict_json: bool,
+    accept_string_dates: bool,
+    uniform_timing: bool,
+    clock: Option<SharedClock>,
+    on_validation: Option<ValidationCallback>,
+    jti_seen: Option<JtiSeenCallback>,
+    issuer_keys: IssuerKeys,
+    hmac_secrets: HmacSecrets,
+    #[cfg(feature = "claims-schema")]
+    claims_schema: Option<Value>,
 
-    claim_verifiers: HashMap<String, VerifierKind>,
+    required_claims: Vec<String>,
+    claim_verifiers: BTreeMap<String, VerifierKind>,
+}
+
+impl Default for VerifierBuilder {
+    fn default() -> Self {
+        VerifierBuilder::new()
+    }
 }
 
 impl VerifierBuilder {
     pub fn new() -> VerifierBuilder {
         VerifierBuilder {
             leeway: 0,
+            exp_leeway: None,
+            nbf_leeway: None,
+            iat_leeway: None,
+            max_token_age: None,
+            past_time_claims: BTreeMap::new(),
+            algorithms: None,
             ignore_exp: false,
             ignore_nbf: false,
             ignore_iat: false,
-            claim_verifiers: HashMap::new(),
+            require_exp: false,
+            missing_exp_is_expired: false,
+            require_nbf: false,
+            allowed_crit: BTreeSet::new(),
+            required_typ: None,
+            max_decompressed_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+            max_token_bytes: DEFAULT_MAX_TOKEN_BYTES,
+            strip_bearer_prefix: false,
+            jwks_without_kid: false,
+            x5c_key_binding: false,
+            insecure_disable_signature: false,
+            deny_insecure: false,
+            strict_json: false,
+            check_claim_consistency: false,
+            accept_string_dates: false,
+            uniform_timing: false,
+            clock: None,
+            on_validation: None,
+            jti_seen: None,
+            issuer_keys: IssuerKeys::default(),
+            hmac_secrets: HmacSecrets::default(),
+            #[cfg(feature = "claims-schema")]
+            claims_schema: None,
+            required_claims: Vec::new(),
+            claim_verifiers: BTreeMap::new(),
         }
     }
 
     /// Convenience for string_equals("iss", "value")
+    ///
+    /// If no issuer is configured then the 'iss' claim isn't checked
     pub fn issuer(&mut self, issuer: impl Into<String>) -> &mut Self {
         self.string_equals("iss", issuer)
     }
 
+    /// Convenience for string_equals_one_of("iss", values), to accept tokens
+    /// from any one of the given issuers (such as for multi-tenant services)
+    pub fn issuer_one_of(&mut self, issuers: &[&str]) -> &mut Self {
+        self.string_equals_one_of("iss", issuers)
+    }
+
+    /// Associates the key for verifying tokens from the given issuer, for use with
+    /// `Verifier::verify_with_issuer_keys()`
+    ///
+    /// For multi-tenant services where each issuer has its own key, this ties
+    /// together checking the 'iss' claim and selecting the key to verify the
+    /// signature. Giving another key for the same issuer replaces it.
+    pub fn issuer_key(
+        &mut self,
+        issuer: impl Into<String>,
+        algorithm: impl Into<Arc<Algorithm>>,
+    ) -> &mut Self {
+        self.issuer_keys.0.insert(issuer.into(), algorithm.into());
+        self
+    }
+
+    /// HMAC secrets to try, in order, when verifying tokens with
+    /// `Verifier::verify_with_hmac_secrets()`
+    ///
+    /// For rotating secrets without a 'kid' (see `HmacKeyStore` otherwise), such
+    /// as `&[current, previous]`, so that tokens signed with the previous secret
+    /// are still accepted while they're in flight. The HMAC key state for each
+    /// secret is computed up-front (without copying the secrets) and giving
+    /// secrets again replaces any previous ones.
+    pub fn hmac_secrets(&mut self, secrets: &[impl AsRef<[u8]>]) -> &mut Self {
+        self.hmac_secrets.0 = secrets
+            .iter()
+            .flat_map(|secret| {
+                HMAC_ALGORITHMS.iter().map(move |id| {
+                    let algorithm = Algorithm::new_hmac_from_slice(*id, secret.as_ref())
+                        .expect("HMAC algorithms accept any secret");
+                    Arc::new(algorithm)
+                })
+            })
+            .collect();
+        self
+    }
+
     /// Convenience for string_or_array_contains("aud", "value")
+    ///
+    /// Like the other audience checks, tokens without any 'aud' claim are
+    /// rejected with `Error::InvalidAudience`
     pub fn audience(&mut self, aud: impl Into<String>) -> &mut Self {
         self.string_or_array_contains("aud", aud)
     }
 
+    /// Convenience for string_or_array_contains_one_of("aud", values), to accept
+    /// tokens intended for any one of the given audiences
+    pub fn audience_one_of(&mut self, values: &[&str]) -> &mut Self {
+        self.string_or_array_contains_one_of("aud", values)
+    }
+
+    /// Convenience for string_or_array_contains_all("aud", values), to only
+    /// accept tokens intended for every one of the given audiences
+    ///
+    /// Unlike audience_one_of(), which accepts a token whose 'aud' contains
+    /// ANY of the values, the token's 'aud' must contain ALL of these values
+    pub fn require_all_audiences(&mut self, values: &[&str]) -> &mut Self {
+        self.string_or_array_contains_all("aud", values)
+    }
+
     /// Convenience for string_equals("sub", "value")
     pub fn subject(&mut self, sub: impl Into<String>) -> &mut Self {
         self.string_equals("sub", sub)
@@ -436,6 +2028,19 @@ impl VerifierBuilder {
         self.string_equals("nonce", nonce)
     }
 
+    /// Require that each of the given claims is present, with any value
+    ///
+    /// The first missing claim is reported via `Error::MissingRequiredClaim`,
+    /// and the values of these claims can still be checked separately
+    pub fn require_claims(&mut self, claims: &[&str]) -> &mut Self {
+        for &claim in claims {
+            if !self.required_claims.iter().any(|c| c == claim) {
+                self.required_claims.push(claim.to_owned());
+            }
+        }
+        self
+    }
+
     /// Check that a claim has a specific string value
     pub fn string_equals(
         &mut self,
@@ -449,9 +2054,9 @@ impl VerifierBuilder {
 
     /// Check that a claim equals one of the given string values
     pub fn string_equals_one_of(&mut self, claim: impl Into<String>, values: &[&str]) -> &mut Self {
-        let hash_set: HashSet<String> = values.iter().cloned().map(|s| s.to_owned()).collect();
+        let set: BTreeSet<String> = values.iter().cloned().map(|s| s.to_owned()).collect();
         self.claim_verifiers
-            .insert(claim.into(), VerifierKind::StringSet(hash_set));
+            .insert(claim.into(), VerifierKind::StringSet(set));
         self
     }
 
@@ -480,9 +2085,9 @@ impl VerifierBuilder {
         claim: impl Into<String>,
         values: &[Regex],
     ) -> &mut Self {
-        let hash_set: HashSet<Pattern> = values.iter().cloned().map(Pattern).collect();
+        let set: BTreeSet<Pattern> = values.iter().cloned().map(Pattern).collect();
         self.claim_verifiers
-            .insert(claim.into(), VerifierKind::StringPatternSet(hash_set));
+            .insert(claim.into(), VerifierKind::StringPatternSet(set));
         self
     }
 
@@ -498,12 +2103,265 @@ impl VerifierBuilder {
         self
     }
 
+    /// Check that a claim is a string equal to one of the given values, or an
+    /// array containing at least one of the given values
+    pub fn string_or_array_contains_one_of(
+        &mut self,
+        claim: impl Into<String>,
+        values: &[&str],
+    ) -> &mut Self {
+        let set: BTreeSet<String> = values.iter().cloned().map(|s| s.to_owned()).collect();
+        self.claim_verifiers
+            .insert(claim.into(), VerifierKind::StringOrArrayContainsOneOf(set));
+        self
+    }
+
+    /// Check that a claim is an array containing all of the given values, or a
+    /// string equal to the given value, if there's only one
+    pub fn string_or_array_contains_all(
+        &mut self,
+        claim: impl Into<String>,
+        values: &[&str],
+    ) -> &mut Self {
+        let set: BTreeSet<String> = values.iter().cloned().map(|s| s.to_owned()).collect();
+        self.claim_verifiers
+            .insert(claim.into(), VerifierKind::StringOrArrayContainsAll(set));
+        self
+    }
+
     /// Sets a leeway (in seconds) should be allowed when checking exp, nbf and iat claims
+    ///
+    /// The leeway allows for clock skew in the same way for each claim: a token
+    /// whose 'exp' has passed is still accepted until `exp + leeway`, and a token
+    /// whose 'nbf' or 'iat' is in the future is accepted from `nbf - leeway` or
+    /// `iat - leeway`.
+    ///
+    /// This can be overridden for specific claims via exp_leeway(), nbf_leeway() and iat_leeway()
     pub fn leeway(&mut self, leeway: u32) -> &mut Self {
         self.leeway = leeway;
         self
     }
 
+    /// Sets a leeway (in seconds) for checking the 'exp' claim, instead of leeway()
+    pub fn exp_leeway(&mut self, leeway: u32) -> &mut Self {
+        self.exp_leeway = Some(leeway);
+        self
+    }
+
+    /// Sets a leeway (in seconds) for checking the 'nbf' claim, instead of leeway()
+    pub fn nbf_leeway(&mut self, leeway: u32) -> &mut Self {
+        self.nbf_leeway = Some(leeway);
+        self
+    }
+
+    /// Sets a leeway (in seconds) for checking the 'iat' claim, instead of leeway()
+    pub fn iat_leeway(&mut self, leeway: u32) -> &mut Self {
+        self.iat_leeway = Some(leeway);
+        self
+    }
+
+    /// Require an 'iat' claim and check the token was issued no longer ago than `max_age`
+    ///
+    /// This rejects old tokens with `Error::TokenTooOld` even if they haven't expired,
+    /// and any iat leeway allows tokens to be that much older. This check is still
+    /// made after ignore_iat().
+    pub fn max_token_age(&mut self, max_age: Duration) -> &mut Self {
+        self.max_token_age = Some(max_age);
+        self
+    }
+
+    /// Check that this claim, if present, is a time in the past (such as OIDC's 'auth_time')
+    ///
+    /// The claim must be a NumericDate that's no later than the current time, with
+    /// the same leeway as for 'iat', otherwise the token is rejected with
+    /// `Error::MalformedToken`.
+    pub fn past_time_claim(&mut self, claim: impl Into<String>) -> &mut Self {
+        self.past_time_claims.entry(claim.into()).or_insert(None);
+        self
+    }
+
+    /// Like `past_time_claim()` but also rejects tokens with `Error::TokenTooOld`
+    /// if the claim's time is more than `max_age` ago (plus any iat leeway)
+    pub fn max_claim_age(&mut self, claim: impl Into<String>, max_age: Duration) -> &mut Self {
+        self.past_time_claims.insert(claim.into(), Some(max_age));
+        self
+    }
+
+    /// Convenience for max_claim_age("auth_time", max_age), to check how long ago
+    /// the user authenticated, when the token has an OIDC 'auth_time' claim
+    ///
+    /// Use require_claims(&["auth_time"]) as well to reject tokens without one.
+    pub fn max_auth_age(&mut self, max_age: Duration) -> &mut Self {
+        self.max_claim_age("auth_time", max_age)
+    }
+
+    /// Only accept tokens signed with one of the given algorithms
+    ///
+    /// A token's header 'alg' must always match the `Algorithm` used to verify it,
+    /// but this additionally ensures that a token can't be verified with an
+    /// unexpected `Algorithm` (such as an HMAC secret or `NONE`) which might
+    /// otherwise be selected based on untrusted input.
+    pub fn algorithms(&mut self, algorithms: &[AlgorithmID]) -> &mut Self {
+        self.algorithms = Some(algorithms.to_vec());
+        self
+    }
+
+    /// Accept tokens that list this parameter in their 'crit' (critical) header
+    ///
+    /// Per RFC 7515, tokens with a 'crit' parameter that isn't understood are
+    /// rejected with `Error::UnsupportedCriticalParam`. This declares that the
+    /// application understands and will itself process the given extension.
+    pub fn allow_crit(&mut self, name: impl Into<String>) -> &mut Self {
+        self.allowed_crit.insert(name.into());
+        self
+    }
+
+    /// Require the 'typ' (type) header parameter to be the given media type, such as "at+jwt"
+    ///
+    /// Per RFC 7515 the type is compared case-insensitively and an "application/"
+    /// prefix is optional. Tokens with a missing or different 'typ' are rejected
+    /// with `Error::InvalidHeader`. The 'typ' isn't checked unless this is set.
+    pub fn require_typ(&mut self, typ: impl Into<String>) -> &mut Self {
+        self.required_typ = Some(typ.into());
+        self
+    }
+
+    /// Verify tokens without a 'kid' against every `JwkSet` key for the token's 'alg'
+    ///
+    /// By default a `kid` is required to select a key from a `JwkSet`. With this
+    /// enabled, a token without a `kid` is accepted if any key whose algorithm
+    /// matches the header's 'alg' verifies its signature, otherwise it's rejected
+    /// with `Error::InvalidSignature` (or `Error::AlgorithmMismatch` if there are
+    /// no keys for the 'alg').
+    pub fn jwks_without_kid(&mut self) -> &mut Self {
+        self.jwks_without_kid = true;
+        self
+    }
+
+    /// Limit the size of `"zip": "DEF"` compressed claims once inflated (Defaults to 256KiB)
+    ///
+    /// This guards against decompression bombs, with larger claims being
+    /// rejected with `Error::MalformedToken`
+    pub fn max_decompressed_size(&mut self, max_size: usize) -> &mut Self {
+        self.max_decompressed_size = max_size;
+        self
+    }
+
+    /// Accept tokens with a "Bearer " prefix, as found in an HTTP Authorization header
+    ///
+    /// The prefix is matched case-insensitively. Otherwise (or without this
+    /// option) a token with a prefix is rejected with `Error::MalformedToken`.
+    /// Surrounding whitespace is always trimmed.
+    pub fn strip_bearer_prefix(&mut self) -> &mut Self {
+        self.strip_bearer_prefix = true;
+        self
+    }
+
+    /// Limit the size of tokens, in bytes (Defaults to 1MiB)
+    ///
+    /// Larger tokens are rejected with `Error::TokenTooLarge` before any base64
+    /// or JSON decoding. Since the decoded header and claims are smaller than
+    /// the token itself (and compressed claims are limited separately by
+    /// `max_decompressed_size()`) this also limits the decoded size.
+    pub fn max_token_bytes(&mut self, max_size: usize) -> &mut Self {
+        self.max_token_bytes = max_size;
+        self
+    }
+
+    /// INSECURE: Disable signature verification, while still validating claims
+    ///
+    /// This is only intended for tokens that are already trusted, such as when
+    /// the sender is authenticated by mutual TLS within an internal network.
+    /// Tokens must then be verified with `Verifier::verify_insecure()` and any
+    /// attempt to verify a token using an `Algorithm` (a key) will fail with
+    /// `Error::InvalidInput`, so that it's never silently ignored.
+    pub fn insecure_disable_signature(&mut self) -> &mut Self {
+        self.insecure_disable_signature = true;
+        self
+    }
+
+    /// Make `build()` fail if the configuration would accept unsigned or
+    /// never-expiring tokens
+    ///
+    /// This catches insecure configurations, such as from combining options
+    /// intended for testing, with `build()` returning `Error::InvalidInput`
+    /// that lists every problem found. It rejects disabling signature
+    /// verification via `insecure_disable_signature()` and ignoring the 'exp'
+    /// claim, and also requires tokens without an 'exp' to be rejected, via
+    /// `require_exp()` or `treat_missing_exp_as_expired()`, unless their age is
+    /// limited via `max_token_age()`.
+    pub fn deny_insecure(&mut self) -> &mut Self {
+        self.deny_insecure = true;
+        self
+    }
+
+    /// Reject tokens whose header or claims JSON has duplicate member names
+    ///
+    /// Parsers disagree on which value wins for a duplicated name (serde_json
+    /// takes the last) which makes headers like `{"alg":"HS256","alg":"none"}`
+    /// ambiguous. Such tokens are rejected with `Error::MalformedToken`.
+    pub fn strict_json(&mut self) -> &mut Self {
+        self.strict_json = true;
+        self
+    }
+
+    /// Reject tokens whose 'exp' time is before their 'nbf' or 'iat' time
+    ///
+    /// Such tokens could never be valid, and are rejected with `Error::MalformedClaims`
+    pub fn check_claim_consistency(&mut self) -> &mut Self {
+        self.check_claim_consistency = true;
+        self
+    }
+
+    /// Validate claims against a JSON Schema, such as to declare which claims are
+    /// required and their types in one place
+    ///
+    /// Tokens whose claims don't match are rejected with `Error::SchemaValidation`,
+    /// listing every failure with the JSON Pointer of the failing value. This is in
+    /// addition to any other claim checks.
+    ///
+    /// The schema is validated by the `jsonschema` crate (as draft 2020-12, unless
+    /// it declares its `$schema`), with `format` keywords checked as assertions.
+    /// Remote `$ref`s aren't resolved. `build()` returns `Error::InvalidInput` if the
+    /// schema is invalid.
+    #[cfg(feature = "claims-schema")]
+    pub fn claims_schema(&mut self, schema: Value) -> &mut Self {
+        self.claims_schema = Some(schema);
+        self
+    }
+
+    /// Accept time claims ('exp', 'nbf', 'iat' and any others with a maximum age)
+    /// given as RFC 3339 strings, such as `"2019-11-29T19:50:15Z"`
+    ///
+    /// This is only for interoperability with non-compliant issuers, since
+    /// times must otherwise be a NumericDate (seconds since the unix epoch) and
+    /// string dates are rejected with `Error::MalformedToken`. Any fractional
+    /// seconds are truncated.
+    pub fn accept_string_dates(&mut self) -> &mut Self {
+        self.accept_string_dates = true;
+        self
+    }
+
+    /// Always runs every check before returning the first failure, so that the
+    /// time taken to reject a token doesn't reveal which check failed
+    ///
+    /// Normally verification stops at the first failure, so (for example) a
+    /// token with a forged signature is rejected sooner than a validly signed
+    /// token with the wrong audience. For authentication endpoints exposed to
+    /// attackers, this mode checks the claims even when the signature is invalid,
+    /// at the cost of decoding and checking the claims of every rejected token.
+    /// The same error is returned as without this mode, though any
+    /// `on_validation()` callback is also told the outcome of each claim check.
+    ///
+    /// This doesn't make verification constant-time overall: the header checks
+    /// (the 'alg', 'crit' and 'typ', which are chosen by whoever made the token)
+    /// still fail early, any `jti_seen()` callback is only called for otherwise
+    /// valid tokens, and each check's own cost depends on the claims. Off by default.
+    pub fn uniform_timing(&mut self) -> &mut Self {
+        self.uniform_timing = true;
+        self
+    }
+
     /// Don't check the 'exp' expiry claim
     pub fn ignore_exp(&mut self) -> &mut Self {
         self.ignore_exp = true;
@@ -522,7 +2380,116 @@ impl VerifierBuilder {
         self
     }
 
+    /// Check that a claim has a specific JSON value
+    ///
+    /// Unlike string_equals() the value can be of any JSON type, such as a
+    /// number or boolean, and the claim must have the same type to match
+    pub fn claim_equals(&mut self, claim: impl Into<String>, value: impl Into<Value>) -> &mut Self {
+        self.claim_verifiers
+            .insert(claim.into(), VerifierKind::ValueConstant(value.into()));
+        self
+    }
+
+    /// Require an 'exp' expiry claim, otherwise `Error::MissingRequiredClaim` is returned
+    ///
+    /// This can't be combined with ignore_exp()
+    pub fn require_exp(&mut self) -> &mut Self {
+        self.require_exp = true;
+        self
+    }
+
+    /// Treat a token without an 'exp' claim as already expired, instead of never expiring
+    ///
+    /// This returns `Error::TokenExpiredAt(0)` for a missing 'exp', as opposed to
+    /// `require_exp()` which reports `Error::MissingRequiredClaim`.
+    ///
+    /// This can't be combined with ignore_exp()
+    pub fn treat_missing_exp_as_expired(&mut self) -> &mut Self {
+        self.missing_exp_is_expired = true;
+        self
+    }
+
+    /// Require tokens to have an 'x5c' header, whose first certificate has the verifying key
+    ///
+    /// This binds a token to a certificate, such as for mutual-TLS, by checking
+    /// that its signature was made with the key of the certificate, and that any
+    /// 'x5t#S256' thumbprint is for the same certificate. The certificate itself
+    /// and the rest of its chain are NOT validated, which is left to the
+    /// application (such as by comparing with the TLS client certificate).
+    ///
+    /// Returns `Error::InvalidHeader` if the 'x5c' is missing, malformed or for a
+    /// different key
+    #[cfg(feature = "std")]
+    pub fn require_x5c_key_binding(&mut self) -> &mut Self {
+        self.x5c_key_binding = true;
+        self
+    }
+
+    /// Require an 'nbf' not before claim, otherwise `Error::MissingRequiredClaim` is returned
+    ///
+    /// This can't be combined with ignore_nbf()
+    pub fn require_nbf(&mut self) -> &mut Self {
+        self.require_nbf = true;
+        self
+    }
+
+    /// Check a claim `Value` with a predicate, returning `true` if ok, else `false`
+    pub fn claim_matches(
+        &mut self,
+        claim: impl Into<String>,
+        predicate: impl Send + Sync + Fn(&serde_json::value::Value) -> bool + 'static,
+    ) -> &mut Self {
+        let closure_verifier = VerifierClosure {
+            func: Arc::new(predicate),
+        };
+        self.claim_verifiers
+            .insert(claim.into(), VerifierKind::Closure(closure_verifier));
+        self
+    }
+
+    /// Sets the `Clock` used to get the current time when verifying tokens
+    /// (Defaults to `SystemClock`, with the `std` feature)
+    ///
+    /// This doesn't affect the `_for_time` APIs that are given an explicit time
+    pub fn clock(&mut self, clock: impl Clock + 'static) -> &mut Self {
+        self.clock = Some(SharedClock(Arc::new(clock)));
+        self
+    }
+
+    /// Check for replayed tokens, via a callback that's given each token's 'jti' (JWT ID)
+    ///
+    /// If the callback returns `true`, because the 'jti' has already been seen,
+    /// then verification fails with `Error::TokenReplayed`. No state is kept by
+    /// the `Verifier` itself, so the callback is responsible for recording each
+    /// 'jti' it's given (such as until the token would expire). The callback is
+    /// only called after all other checks have passed and a 'jti' claim is then
+    /// required, otherwise `Error::MissingRequiredClaim` is returned.
+    pub fn jti_seen(&mut self, func: impl Send + Sync + Fn(&str) -> bool + 'static) -> &mut Self {
+        self.jti_seen = Some(JtiSeenCallback {
+            func: Arc::new(func),
+        });
+        self
+    }
+
+    /// Observe the outcome of each validation step, such as to count failures by reason
+    ///
+    /// The callback is called with the outcome of verifying the signature and
+    /// checking the 'exp', 'nbf', 'aud' and 'iss' claims, as each step is made
+    /// (so steps after a failure aren't reported, and steps that aren't
+    /// configured, or are ignored, aren't reported). It can't affect the result.
+    pub fn on_validation(
+        &mut self,
+        func: impl Send + Sync + Fn(ValidationStep, Result<(), &Error>) + 'static,
+    ) -> &mut Self {
+        self.on_validation = Some(ValidationCallback {
+            func: Arc::new(func),
+        });
+        self
+    }
+
     /// Check a claim `Value` manually, returning `true` if ok, else `false`
+    ///
+    /// This is equivalent to claim_matches()
     pub fn claim_callback(
         &mut self,
         claim: impl Into<String>,
@@ -537,12 +2504,97 @@ impl VerifierBuilder {
     }
 
     /// Build the final Verifier
+    ///
+    /// Returns `Error::InvalidInput` if a claim is both required and ignored, or
+    /// if no `clock()` was given without the `std` feature
     pub fn build(&self) -> Result<Verifier, Error> {
+        if self.require_exp && self.ignore_exp {
+            return Err(Error::InvalidInput(ErrorDetails::new(
+                "Can't both require and ignore the 'exp' claim",
+            )));
+        }
+        if self.missing_exp_is_expired && self.ignore_exp {
+            return Err(Error::InvalidInput(ErrorDetails::new(
+                "Can't both ignore the 'exp' claim and treat it as expired when missing",
+            )));
+        }
+        if self.require_nbf && self.ignore_nbf {
+            return Err(Error::InvalidInput(ErrorDetails::new(
+                "Can't both require and ignore the 'nbf' claim",
+            )));
+        }
+
+        if self.deny_insecure {
+            let mut problems = Vec::new();
+            if self.insecure_disable_signature {
+                problems.push("signature verification is disabled");
+            }
+            if self.ignore_exp {
+                problems.push("the 'exp' claim is ignored");
+            } else if !self.require_exp
+                && !self.missing_exp_is_expired
+                && self.max_token_age.is_none()
+            {
+                problems.push("tokens without an 'exp' claim never expire");
+            }
+            if !problems.is_empty() {
+                return Err(Error::InvalidInput(ErrorDetails::new(format!(
+                    "Insecure configuration: {}",
+                    problems.join(", ")
+                ))));
+            }
+        }
+
+        #[cfg(feature = "std")]
+        let clock = self.clock.clone().unwrap_or_else(SharedClock::system);
+        #[cfg(not(feature = "std"))]
+        let clock = self.clock.clone().ok_or_else(|| {
+            Error::InvalidInput(ErrorDetails::new(
+                "A clock must be given without the std feature",
+            ))
+        })?;
+
         Ok(Verifier {
-            leeway: self.leeway,
+            exp_leeway: self.exp_leeway.unwrap_or(self.leeway),
+            nbf_leeway: self.nbf_leeway.unwrap_or(self.leeway),
+            iat_leeway: self.iat_leeway.unwrap_or(self.leeway),
+            max_token_age: self.max_token_age.map(|age| age.as_secs()),
+            past_time_claims: self
+                .past_time_claims
+                .iter()
+                .map(|(claim, max_age)| (claim.clone(), max_age.map(|age| age.as_secs())))
+                .collect(),
+            algorithms: self.algorithms.clone(),
             ignore_exp: self.ignore_exp,
             ignore_nbf: self.ignore_nbf,
             ignore_iat: self.ignore_iat,
+            require_exp: self.require_exp,
+            missing_exp_is_expired: self.missing_exp_is_expired,
+            require_nbf: self.require_nbf,
+            allowed_crit: self.allowed_crit.clone(),
+            required_typ: self.required_typ.clone(),
+            max_decompressed_size: self.max_decompressed_size,
+            max_token_bytes: self.max_token_bytes,
+            strip_bearer_prefix: self.strip_bearer_prefix,
+            jwks_without_kid: self.jwks_without_kid,
+            x5c_key_binding: self.x5c_key_binding,
+            insecure_disable_signature: self.insecure_disable_signature,
+            strict_json: self.strict_json,
+            check_claim_consistency: self.check_claim_consistency,
+            accept_string_dates: self.accept_string_dates,
+            uniform_timing: self.uniform_timing,
+            clock,
+            on_validation: self.on_validation.clone(),
+            jti_seen: self.jti_seen.clone(),
+            issuer_keys: self.issuer_keys.clone(),
+            hmac_secrets: self.hmac_secrets.clone(),
+            #[cfg(feature = "claims-schema")]
+            claims_schema: self
+                .claims_schema
+                .as_ref()
+                .map(ClaimsSchema::compile)
+                .transpose()?,
+            required_claims: self.required_claims.clone(),
             claim_verifiers: self.claim_verifiers.clone(),
         })
     }
@@ -0,0 +1,61 @@
+//! Just enough X.509 parsing to find the public key of a certificate
+//!
+//! This doesn't validate certificates (their signatures, validity or chain of
+//! trust), it only supports checking that a token was signed with the key of
+//! the certificate given in its 'x5c' header.
+
+use simple_asn1::{oid, ASN1Block};
+
+use crate::crypto::rsa;
+
+/// A certificate's subject public key, in the forms stored by an `Algorithm`
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum PublicKey {
+    /// An uncompressed elliptic curve point
+    Ec(Vec<u8>),
+    /// A raw 32 byte key
+    Ed25519(Vec<u8>),
+    /// The (modulus, exponent), as big-endian bytes without leading zeros
+    Rsa(Vec<u8>, Vec<u8>),
+}
+
+/// Finds the subject public key of a DER encoded certificate
+///
+/// Returns `None` if the certificate can't be parsed or has an unsupported type of key
+pub(crate) fn certificate_public_key(der: &[u8]) -> Option<PublicKey> {
+    let asn1 = simple_asn1::from_der(der).ok()?;
+    let tbs_certificate = match asn1.first()? {
+        ASN1Block::Sequence(_, entries) => match entries.first()? {
+            ASN1Block::Sequence(_, tbs_certificate) => tbs_certificate,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    // The SubjectPublicKeyInfo is the only sequence of an AlgorithmIdentifier and a bit string
+    tbs_certificate.iter().find_map(|entry| match entry {
+        ASN1Block::Sequence(_, spki) => match spki.as_slice() {
+            [ASN1Block::Sequence(_, algorithm), ASN1Block::BitString(_, _, key)] => {
+                match algorithm.first()? {
+                    ASN1Block::ObjectIdentifier(_, id) => public_key(id, key),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+fn public_key(id: &simple_asn1::OID, key: &[u8]) -> Option<PublicKey> {
+    if *id == oid!(1, 2, 840, 10_045, 2, 1) {
+        Some(PublicKey::Ec(key.to_vec()))
+    } else if *id == oid!(1, 3, 101, 112) {
+        Some(PublicKey::Ed25519(key.to_vec()))
+    } else if *id == oid!(1, 2, 840, 113_549, 1, 1, 1) {
+        let (n, e) = rsa::der_public_components(key).ok()?;
+        Some(PublicKey::Rsa(n, e))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use serde_json::json;
+use serde_json::value::Value;
+
+use jsonwebtokens as jwt;
+use jwt::{Algorithm, AlgorithmID, ClaimsBuilder, Clock, Verifier};
+
+mod common;
+use common::get_time;
+
+const REFERENCE_TIME: u64 = 1575057015u64;
+
+struct FixedClock(u64);
+
+impl Clock for FixedClock {
+    fn now(&self) -> u64 {
+        self.0
+    }
+}
+
+#[test]
+fn standard_claims() {
+    let claims = ClaimsBuilder::new()
+        .clock(FixedClock(REFERENCE_TIME))
+        .issuer("ACME")
+        .subject("user-123")
+        .audience("app")
+        .issued_now()
+        .not_before_in(Duration::from_secs(10))
+        .expires_in(Duration::from_secs(3600))
+        .jwt_id("1234")
+        .claim("admin", true)
+        .build();
+
+    assert_eq!(
+        claims,
+        json!({
+            "iss": "ACME",
+            "sub": "user-123",
+            "aud": "app",
+            "iat": REFERENCE_TIME,
+            "nbf": REFERENCE_TIME + 10,
+            "exp": REFERENCE_TIME + 3600,
+            "jti": "1234",
+            "admin": true,
+        })
+    );
+    assert!(claims.get("exp").unwrap().is_u64());
+}
+
+#[test]
+fn saturating_durations() {
+    let claims = ClaimsBuilder::new()
+        .clock(FixedClock(REFERENCE_TIME))
+        .not_before_in(Duration::MAX)
+        .expires_in(Duration::MAX)
+        .build();
+
+    assert_eq!(claims, json!({ "nbf": u64::MAX, "exp": u64::MAX }));
+
+    let claims = ClaimsBuilder::new()
+        .clock(FixedClock(REFERENCE_TIME))
+        .expires_in(Duration::MAX)
+        .build();
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let token = jwt::encode(&json!({ "alg": "HS256" }), &claims, &alg).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+    verifier
+        .verify_for_time(&token, &alg, REFERENCE_TIME)
+        .unwrap();
+}
+
+#[test]
+fn explicit_times() {
+    let claims = ClaimsBuilder::new()
+        .audiences(&["app0", "app1"])
+        .not_before(REFERENCE_TIME)
+        .expires_at(REFERENCE_TIME + 60)
+        .build();
+
+    assert_eq!(
+        claims,
+        json!({
+            "aud": ["app0", "app1"],
+            "nbf": REFERENCE_TIME,
+            "exp": REFERENCE_TIME + 60,
+        })
+    );
+}
+
+#[test]
+fn round_trip_claims() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": alg.name() });
+    let claims = ClaimsBuilder::new()
+        .issuer("ACME")
+        .audience("app")
+        .issued_now()
+        .expires_in(Duration::from_secs(60))
+        .build();
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .issuer("ACME")
+        .audience("app")
+        .build()
+        .unwrap();
+    let verified: Value = verifier.verify(&token, &alg).unwrap();
+    assert_eq!(verified, claims);
+
+    let exp = verified.get("exp").unwrap().as_u64().unwrap();
+    assert!(exp > get_time() && exp <= get_time() + 60);
+}
+
+#[test]
+fn encode_with_iat() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": alg.name() });
+
+    let before = get_time();
+    let token = jwt::encode_with_iat(&header, &json!({ "sub": "user-123" }), &alg).unwrap();
+    let claims = jwt::decode_unverified(&token).unwrap().claims;
+    let iat = claims.get("iat").unwrap().as_u64().unwrap();
+    assert!(iat >= before && iat <= get_time());
+    assert_eq!(claims.get("sub").unwrap(), "user-123");
+
+    // An existing iat isn't overwritten
+    let token = jwt::encode_with_iat(&header, &json!({ "iat": REFERENCE_TIME }), &alg).unwrap();
+    let claims = jwt::decode_unverified(&token).unwrap().claims;
+    assert_eq!(claims, json!({ "iat": REFERENCE_TIME }));
+}
+
+#[test]
+#[should_panic(expected = "InvalidInput")]
+fn encode_with_iat_non_object_claims() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": alg.name() });
+    let _token = jwt::encode_with_iat(&header, &json!(["not", "an", "object"]), &alg).unwrap();
+}
@@ -0,0 +1,72 @@
+use serde_json::json;
+
+use jsonwebtokens as jwt;
+use jwt::{error::Error, Algorithm, AlgorithmID, Verifier};
+
+mod common;
+use common::get_time;
+
+#[test]
+fn reject_unknown_crit() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "crit": ["http://example.com/ext"], "http://example.com/ext": true });
+    let token = jwt::encode(&header, &json!({}), &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify_for_time(&token, &alg, get_time()) {
+        Err(Error::UnsupportedCriticalParam(name)) => assert_eq!(name, "http://example.com/ext"),
+        other => panic!("Expected UnsupportedCriticalParam, got {:?}", other.err()),
+    }
+    match verifier.verify_with_keys(&token, &[&alg]) {
+        Err(Error::UnsupportedCriticalParam(_)) => {}
+        other => panic!("Expected UnsupportedCriticalParam, got {other:?}"),
+    }
+}
+
+#[test]
+fn allow_crit() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "crit": ["exp", "ext"], "exp": 0, "ext": true });
+    let token = jwt::encode(&header, &json!({}), &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .allow_crit("exp")
+        .allow_crit("ext")
+        .build()
+        .unwrap();
+    let _claims = verifier.verify(&token, &alg).unwrap();
+
+    // Every critical parameter must be allowed
+    let verifier = Verifier::create().allow_crit("exp").build().unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::UnsupportedCriticalParam(name)) => assert_eq!(name, "ext"),
+        other => panic!("Expected UnsupportedCriticalParam, got {other:?}"),
+    }
+}
+
+#[test]
+fn reject_b64_crit() {
+    // An unencoded payload is only supported via verify_detached()
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "b64": false, "crit": ["b64"] });
+    let token = jwt::encode(&header, &json!({}), &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::UnsupportedCriticalParam(name)) => assert_eq!(name, "b64"),
+        other => panic!("Expected UnsupportedCriticalParam, got {other:?}"),
+    }
+}
+
+#[test]
+fn malformed_crit() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let verifier = Verifier::create().build().unwrap();
+    for crit in [json!([]), json!("exp"), json!([1])] {
+        let token = jwt::encode(&json!({ "crit": crit }), &json!({}), &alg).unwrap();
+        match verifier.verify(&token, &alg) {
+            Err(Error::MalformedToken(_)) => {}
+            other => panic!("Expected MalformedToken, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use serde_json::json;
+
+use jsonwebtokens as jwt;
+use jwt::{error::Error, raw, Algorithm, AlgorithmID, Verifier};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+// The example from RFC 7797, section 4.2
+const RFC7797_KEY: &str =
+    "AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9CAow";
+const RFC7797_TOKEN: &str = "eyJhbGciOiJIUzI1NiIsImI2NCI6ZmFsc2UsImNyaXQiOlsiYjY0Il19..A5dxf2s96_n5FLueVuW1Z_vh161FwXZC4YLPff6dmDY";
+
+fn rfc7797_alg() -> Algorithm {
+    Algorithm::new_hmac_b64(AlgorithmID::HS256, RFC7797_KEY).unwrap()
+}
+
+/// Creates a detached token with an arbitrary header, which is signed over the
+/// raw payload
+fn sign_detached(header: serde_json::Value, payload: &[u8], alg: &Algorithm) -> String {
+    let header = URL_SAFE_NO_PAD.encode(header.to_string());
+    let mut message = format!("{header}.").into_bytes();
+    message.extend_from_slice(payload);
+    let signature = URL_SAFE_NO_PAD.encode(alg.sign_bytes(&message).unwrap());
+    format!("{header}..{signature}")
+}
+
+#[test]
+fn encode_rfc7797_example() {
+    let header = json!({ "alg": "HS256" });
+    let token = jwt::encode_detached(&header, b"$.02", &rfc7797_alg()).unwrap();
+    assert_eq!(token, RFC7797_TOKEN);
+
+    let header = raw::decode_header_only(&token).unwrap();
+    assert_eq!(
+        header,
+        json!({ "alg": "HS256", "b64": false, "crit": ["b64"] })
+    );
+}
+
+#[test]
+fn verify_rfc7797_example() {
+    let verifier = Verifier::create().build().unwrap();
+    let header = verifier
+        .verify_detached(RFC7797_TOKEN, b"$.02", &rfc7797_alg())
+        .unwrap();
+    assert_eq!(header["b64"], false);
+}
+
+#[test]
+fn round_trip_non_utf8_payload() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let payload = [0xff, 0x00, b'.', 0xfe];
+    let token = jwt::encode_detached(&json!({}), &payload, &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    verifier.verify_detached(&token, &payload, &alg).unwrap();
+}
+
+#[test]
+fn verify_wrong_payload() {
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify_detached(RFC7797_TOKEN, b"$.03", &rfc7797_alg()) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+}
+
+#[test]
+fn verify_encoded_detached_payload() {
+    // Without 'b64' the detached payload is base64 encoded in the signing input
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let token = jwt::encode(&json!({}), &json!({ "foo": "bar" }), &alg).unwrap();
+    let raw::TokenSlices {
+        header, signature, ..
+    } = raw::split_token(&token).unwrap();
+    let detached = format!("{header}..{signature}");
+
+    let verifier = Verifier::create().build().unwrap();
+    verifier
+        .verify_detached(&detached, br#"{"foo":"bar"}"#, &alg)
+        .unwrap();
+}
+
+#[test]
+fn verify_attached_payload() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let token = jwt::encode(&json!({}), &json!({ "foo": "bar" }), &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify_detached(&token, br#"{"foo":"bar"}"#, &alg) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {other:?}"),
+    }
+}
+
+#[test]
+fn verify_unknown_crit() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256", "b64": false, "crit": ["b64", "exp"], "exp": 0 });
+    let token = sign_detached(header, b"payload", &alg);
+
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify_detached(&token, b"payload", &alg) {
+        Err(Error::UnsupportedCriticalParam(name)) => assert_eq!(name, "exp"),
+        other => panic!("Expected UnsupportedCriticalParam, got {other:?}"),
+    }
+
+    let verifier = Verifier::create().allow_crit("exp").build().unwrap();
+    verifier.verify_detached(&token, b"payload", &alg).unwrap();
+}
+
+#[test]
+fn verify_b64_not_critical() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let token = sign_detached(json!({ "alg": "HS256", "b64": false }), b"payload", &alg);
+
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify_detached(&token, b"payload", &alg) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {other:?}"),
+    }
+}
+
+#[test]
+fn verify_detached_checks_algorithms() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let token = jwt::encode_detached(&json!({}), b"payload", &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .algorithms(&[AlgorithmID::RS256])
+        .build()
+        .unwrap();
+    match verifier.verify_detached(&token, b"payload", &alg) {
+        Err(Error::AlgorithmMismatch()) => {}
+        other => panic!("Expected AlgorithmMismatch, got {other:?}"),
+    }
+}
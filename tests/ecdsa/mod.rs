@@ -2,7 +2,9 @@ use serde_json::json;
 use serde_json::value::Value;
 
 use jsonwebtokens as jwt;
-use jwt::{Algorithm, AlgorithmID, Verifier};
+use jwt::{error::Error, Algorithm, AlgorithmID, Verifier};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 
 use crate::common::get_time;
 
@@ -23,6 +25,59 @@ const EC_ALGORITHMS: &[KeyPair] = &[
         privkey: include_bytes!("private_ecdsa_key_jwtio_p384_pkcs8.pem"),
         pubkey: include_bytes!("public_ecdsa_key_jwtio_p384_pkcs8.pem"),
     },
+    #[cfg(feature = "es512")]
+    KeyPair {
+        id: AlgorithmID::ES512,
+        privkey: include_bytes!("private_ecdsa_key_p521_pkcs8.pem"),
+        pubkey: include_bytes!("public_ecdsa_key_p521_pkcs8.pem"),
+    },
+    #[cfg(feature = "es256k")]
+    KeyPair {
+        id: AlgorithmID::ES256K,
+        privkey: include_bytes!("private_ecdsa_key_secp256k1_pkcs8.pem"),
+        pubkey: include_bytes!("public_ecdsa_key_secp256k1_pkcs8.pem"),
+    },
+];
+
+#[cfg(any(feature = "es512", feature = "es256k"))]
+fn key_pair(id: AlgorithmID) -> &'static KeyPair<'static> {
+    EC_ALGORITHMS
+        .iter()
+        .find(|keypair| keypair.id == id)
+        .unwrap()
+}
+
+/// The curves that are only implemented by the RustCrypto backend, with a feature
+struct FeatureCurve<'a> {
+    id: AlgorithmID,
+    enabled: bool,
+    crv: &'a str,
+    privkey: &'a [u8],
+    pubkey: &'a [u8],
+    // The public key's coordinates
+    x: &'a str,
+    y: &'a str,
+}
+
+const FEATURE_CURVES: &[FeatureCurve] = &[
+    FeatureCurve {
+        id: AlgorithmID::ES512,
+        enabled: cfg!(feature = "es512"),
+        crv: "P-521",
+        privkey: include_bytes!("private_ecdsa_key_p521_pkcs8.pem"),
+        pubkey: include_bytes!("public_ecdsa_key_p521_pkcs8.pem"),
+        x: "APblaZZMz8AgJvm6mN1S1DOvNNmySCi9MRh-qIZkTAwKEUiKuc5qwtI6ijLpM0HwkGsYLLLGzmFvBVV7PCJIoPK4",
+        y: "ADOKC7t_pyWiAAcjqcVf7LflhmFeVyops3EzsSe5VlkUpfk9BOKWbPFSOEFVDwqfySFLLNcGeaKzLo5Ln0Jkn1Kj",
+    },
+    FeatureCurve {
+        id: AlgorithmID::ES256K,
+        enabled: cfg!(feature = "es256k"),
+        crv: "secp256k1",
+        privkey: include_bytes!("private_ecdsa_key_secp256k1_pkcs8.pem"),
+        pubkey: include_bytes!("public_ecdsa_key_secp256k1_pkcs8.pem"),
+        x: "6t95pTeX0B3KXZV95WhNIlzXE-EXIVVnBj7dXwF_3ts",
+        y: "Zzl_d7Mhfwv5BYVi1yR-m_NZ9quXDSeey__p_eY6iIM",
+    },
 ];
 
 #[test]
@@ -80,3 +135,243 @@ fn round_trip_claims() {
         assert_eq!(my_claims, claims);
     }
 }
+
+// RFC 7518 requires ECDSA signatures to be the fixed-length concatenation of r and s,
+// not an ASN.1 DER encoding
+#[test]
+fn signature_is_fixed_length_r_s() {
+    for keypair in EC_ALGORITHMS {
+        let len = match keypair.id {
+            AlgorithmID::ES384 => 96,
+            AlgorithmID::ES512 => 132,
+            _ => 64,
+        };
+        let alg = Algorithm::new_ecdsa_pem_signer(keypair.id, keypair.privkey).unwrap();
+        let signature = alg.sign("hello world").unwrap();
+        let signature = URL_SAFE_NO_PAD.decode(signature).unwrap();
+        assert_eq!(signature.len(), len);
+    }
+}
+
+#[test]
+fn wrong_curve_verification_key() {
+    let privkey_pem = include_bytes!("private_ecdsa_key_jwtio_p256_pkcs8.pem");
+    let pubkey_pem = include_bytes!("public_ecdsa_key_jwtio_p384_pkcs8.pem");
+
+    let alg = Algorithm::new_ecdsa_pem_signer(AlgorithmID::ES256, privkey_pem).unwrap();
+    let header = json!({"alg": alg.name()});
+    let claims = json!({"exp": get_time() + 10000});
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let alg = Algorithm::new_ecdsa_pem_verifier(AlgorithmID::ES256, pubkey_pem).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify(token, &alg) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+}
+
+#[test]
+#[cfg(feature = "es512")]
+fn es512_signature_length() {
+    let keypair = key_pair(AlgorithmID::ES512);
+    let signer = Algorithm::new_ecdsa_pem_signer(keypair.id, keypair.privkey).unwrap();
+    let alg = Algorithm::new_ecdsa_pem_verifier(keypair.id, keypair.pubkey).unwrap();
+    let signature = signer.sign_bytes(b"hello world").unwrap();
+    assert_eq!(signature.len(), 132);
+    alg.verify_bytes(b"hello world", &signature).unwrap();
+
+    // Each of r and s must be exactly 66 bytes, even with leading zeros
+    let truncated = &signature[..131];
+    let mut padded = signature.clone();
+    padded.push(0);
+    let mut unpadded = signature[1..66].to_vec();
+    unpadded.extend_from_slice(&signature[66..]);
+    for signature in [truncated, &padded, &unpadded] {
+        match alg.verify_bytes(b"hello world", signature) {
+            Err(Error::InvalidSignature()) => {}
+            other => panic!("Expected InvalidSignature, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "es512")]
+fn es512_wrong_curve() {
+    let p384_privkey = include_bytes!("private_ecdsa_key_jwtio_p384_pkcs8.pem");
+    let p384_pubkey = include_bytes!("public_ecdsa_key_jwtio_p384_pkcs8.pem");
+    let p521_privkey = include_bytes!("private_ecdsa_key_p521_pkcs8.pem");
+
+    for (id, privkey) in [
+        (AlgorithmID::ES512, &p384_privkey[..]),
+        (AlgorithmID::ES384, &p521_privkey[..]),
+    ] {
+        match Algorithm::new_ecdsa_pem_signer(id, privkey) {
+            Err(Error::InvalidInput(_)) => {}
+            other => panic!("Expected InvalidInput, got {:?}", other.err()),
+        }
+    }
+
+    let signer = Algorithm::new_ecdsa_pem_signer(AlgorithmID::ES512, p521_privkey).unwrap();
+    let token = jwt::encode(&json!({"alg": "ES512"}), &json!({"sub": "user"}), &signer).unwrap();
+    let alg = Algorithm::new_ecdsa_pem_verifier(AlgorithmID::ES512, p384_pubkey).unwrap();
+    let verifier = Verifier::create().ignore_exp().build().unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+
+    // P-384 sized coordinates
+    let p384_coordinate = URL_SAFE_NO_PAD.encode([1u8; 48]);
+    match Algorithm::new_ecdsa_x_y_b64_verifier(
+        AlgorithmID::ES512,
+        &p384_coordinate,
+        &p384_coordinate,
+    ) {
+        Err(Error::InvalidInput(_)) => {}
+        other => panic!("Expected InvalidInput, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn signing_and_verify_only_keys() {
+    for keypair in EC_ALGORITHMS {
+        let signer = Algorithm::new_ecdsa_pem_signer(keypair.id, keypair.privkey).unwrap();
+        let verifier_alg = Algorithm::new_ecdsa_pem_verifier(keypair.id, keypair.pubkey).unwrap();
+        assert!(signer.can_sign());
+        assert!(!verifier_alg.can_sign());
+
+        let claims = json!({ "sub": "b@b.com", "exp": get_time() + 10000 });
+        let header = json!({ "alg": signer.name() });
+        match jwt::encode(&header, &claims, &verifier_alg) {
+            Err(Error::CannotSign()) => {}
+            other => panic!("Expected CannotSign, got {other:?}"),
+        }
+
+        // A signing key can also verify
+        let token = jwt::encode(&header, &claims, &signer).unwrap();
+        let verifier = Verifier::create().build().unwrap();
+        assert_eq!(verifier.verify(&token, &signer).unwrap(), claims);
+        assert_eq!(verifier.verify(&token, &verifier_alg).unwrap(), claims);
+    }
+}
+
+#[test]
+#[cfg(feature = "es256k")]
+fn es256k_rejects_p256_keys() {
+    let p256_privkey = include_bytes!("private_ecdsa_key_jwtio_p256_pkcs8.pem");
+    let p256_pubkey = include_bytes!("public_ecdsa_key_jwtio_p256_pkcs8.pem");
+    let secp256k1 = key_pair(AlgorithmID::ES256K);
+
+    for (id, privkey) in [
+        (AlgorithmID::ES256K, &p256_privkey[..]),
+        (AlgorithmID::ES256, secp256k1.privkey),
+    ] {
+        match Algorithm::new_ecdsa_pem_signer(id, privkey) {
+            Err(Error::InvalidInput(_)) => {}
+            other => panic!("Expected InvalidInput, got {:?}", other.err()),
+        }
+    }
+
+    // An ES256 signature has the same size, but doesn't verify with a P-256 public key
+    // (or its coordinates) presented as a secp256k1 key
+    let p256_signer = Algorithm::new_ecdsa_pem_signer(AlgorithmID::ES256, p256_privkey).unwrap();
+    let signature = p256_signer.sign_bytes(b"hello world").unwrap();
+    let jwk = json!({
+        "kty": "EC",
+        "crv": "secp256k1",
+        "x": "EVs_o5-uQbTjL3chynL4wXgUg2R9q9UU8I5mEovUf84",
+        "y": "kGe5DgSIycKp8w9aJmoHhB1sB3QTugfnRWm5nU_TzsY",
+    });
+    for alg in [
+        Algorithm::new_ecdsa_pem_verifier(AlgorithmID::ES256K, p256_pubkey).unwrap(),
+        Algorithm::from_jwk(&jwk).unwrap(),
+    ] {
+        assert_eq!(alg.id(), AlgorithmID::ES256K);
+        match alg.verify_bytes(b"hello world", &signature) {
+            Err(Error::InvalidSignature()) => {}
+            other => panic!("Expected InvalidSignature, got {other:?}"),
+        }
+    }
+}
+
+// Unlike Bitcoin, RFC 8812 doesn't require the "low S" form of a signature
+#[test]
+#[cfg(feature = "es256k")]
+fn es256k_high_s_signature() {
+    // The order of the secp256k1 curve
+    const N: [u8; 32] = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xfe, 0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36,
+        0x41, 0x41,
+    ];
+    let keypair = key_pair(AlgorithmID::ES256K);
+    let signer = Algorithm::new_ecdsa_pem_signer(keypair.id, keypair.privkey).unwrap();
+    let alg = Algorithm::new_ecdsa_pem_verifier(keypair.id, keypair.pubkey).unwrap();
+    let mut signature = signer.sign_bytes(b"hello world").unwrap();
+
+    // Replace s with n - s
+    let mut borrow = 0;
+    for i in (0..32).rev() {
+        let (s, rest) = (signature[32 + i], N[i]);
+        let diff = i16::from(rest) - i16::from(s) - borrow;
+        borrow = i16::from(diff < 0);
+        signature[32 + i] = diff.rem_euclid(256) as u8;
+    }
+    alg.verify_bytes(b"hello world", &signature).unwrap();
+}
+
+#[test]
+fn feature_curve_jwks() {
+    for curve in FEATURE_CURVES.iter().filter(|curve| curve.enabled) {
+        let signer = Algorithm::new_ecdsa_pem_signer(curve.id, curve.privkey).unwrap();
+        let header = json!({ "alg": curve.id.to_string() });
+        let token = jwt::encode(&header, &json!({"sub": "user"}), &signer).unwrap();
+
+        let jwk =
+            json!({ "kty": "EC", "crv": curve.crv, "alg": curve.id, "x": curve.x, "y": curve.y });
+        let alg = Algorithm::from_jwk(&jwk).unwrap();
+        assert_eq!(alg.id(), curve.id);
+        assert_eq!(
+            alg.jwk_thumbprint().unwrap(),
+            signer.jwk_thumbprint().unwrap()
+        );
+        let verifier = Verifier::create().ignore_exp().build().unwrap();
+        assert_eq!(
+            verifier.verify(&token, &alg).unwrap(),
+            json!({"sub": "user"})
+        );
+    }
+}
+
+#[test]
+fn feature_curves_unsupported() {
+    for curve in FEATURE_CURVES.iter().filter(|curve| !curve.enabled) {
+        let name = curve.id.to_string();
+        let jwk = json!({ "kty": "EC", "crv": curve.crv, "x": curve.x, "y": curve.y });
+        for result in [
+            Algorithm::new_ecdsa_pem_signer(curve.id, curve.privkey),
+            Algorithm::new_ecdsa_pem_verifier(curve.id, curve.pubkey),
+            Algorithm::new_ecdsa_x_y_b64_verifier(curve.id, curve.x, curve.y),
+            Algorithm::from_jwk(&jwk),
+        ] {
+            match result {
+                Err(Error::UnsupportedAlgorithm(unsupported)) => assert_eq!(unsupported, name),
+                other => panic!("Expected UnsupportedAlgorithm, got {:?}", other.err()),
+            }
+        }
+
+        // And a token can't be verified with a supported curve's key instead, including
+        // a P-256 key for ES256K, which uses the same size signatures
+        let header = URL_SAFE_NO_PAD.encode(json!({ "alg": name }).to_string());
+        let claims = URL_SAFE_NO_PAD.encode(json!({ "sub": "user" }).to_string());
+        let token = format!("{header}.{claims}.{}", URL_SAFE_NO_PAD.encode([0u8; 64]));
+        let pubkey = include_bytes!("public_ecdsa_key_jwtio_p256_pkcs8.pem");
+        let alg = Algorithm::new_ecdsa_pem_verifier(AlgorithmID::ES256, pubkey).unwrap();
+        let verifier = Verifier::create().build().unwrap();
+        match verifier.verify(&token, &alg) {
+            Err(Error::AlgorithmMismatch()) => {}
+            other => panic!("Expected AlgorithmMismatch, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use serde_json::json;
+use serde_json::value::Value;
+
+use jsonwebtokens as jwt;
+use jwt::{error::Error, Algorithm, AlgorithmID, Verifier};
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+
+use crate::common::get_time;
+
+// Generated with `openssl genpkey -algorithm ed25519` (raw seeds and public keys, base64 encoded)
+const SEED: &str = "rAsSIx8YqbQeIhA5BMD8oKqwbY/84zsJg6urTbBQ8mk=";
+const PUBKEY: &str = "P/m2yaaAtmu7bLWEeDlWC/2mY5HYu9m8s71rNnl7BUU=";
+const OTHER_PUBKEY: &str = "WaAL/Kf5n/DbDfznkUuB5Epg3GUlnSh7bDSPa4mZ2I8=";
+
+fn new_signer() -> Algorithm {
+    Algorithm::new_ed25519_signer(&STANDARD.decode(SEED).unwrap()).unwrap()
+}
+
+fn new_verifier(pubkey: &str) -> Algorithm {
+    Algorithm::new_ed25519_verifier(&STANDARD.decode(pubkey).unwrap()).unwrap()
+}
+
+#[test]
+fn jwt_name() {
+    let alg = new_signer();
+    assert_eq!(alg.id(), AlgorithmID::EdDSA);
+    assert_eq!(alg.name(), "EdDSA");
+}
+
+#[test]
+fn round_trip_sign_verification() {
+    let signature = new_signer().sign("hello world").unwrap();
+    assert_eq!(URL_SAFE_NO_PAD.decode(&signature).unwrap().len(), 64);
+
+    let alg = new_verifier(PUBKEY);
+    alg.verify(None, "hello world", signature).unwrap();
+}
+
+#[test]
+fn round_trip_raw_bytes() {
+    // Ed25519 signatures are deterministic so the raw and base64 APIs must agree
+    let signer = new_signer();
+    let signature = signer.sign_bytes(b"hello world").unwrap();
+    assert_eq!(
+        URL_SAFE_NO_PAD.encode(&signature),
+        signer.sign("hello world").unwrap()
+    );
+
+    let alg = new_verifier(PUBKEY);
+    alg.verify_bytes(b"hello world", &signature).unwrap();
+    match new_verifier(OTHER_PUBKEY).verify_bytes(b"hello world", &signature) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+}
+
+#[test]
+#[should_panic(expected = "InvalidInput")]
+fn wrong_public_key_length() {
+    let _alg = Algorithm::new_ed25519_verifier(&[0u8; 16]).unwrap();
+}
+
+#[test]
+fn round_trip_claims() {
+    let my_claims = json!({
+        "sub": "b@b.com",
+        "company": "ACME",
+        "exp": get_time() + 10000,
+    });
+
+    let alg = new_signer();
+    let header = json!({"alg": alg.name()});
+    let token = jwt::encode(&header, &my_claims, &alg).unwrap();
+
+    let alg = new_verifier(PUBKEY);
+    let verifier = Verifier::create().build().unwrap();
+    let claims: Value = verifier.verify(token, &alg).unwrap();
+
+    assert_eq!(my_claims, claims);
+}
+
+#[test]
+fn wrong_public_key() {
+    let alg = new_signer();
+    let header = json!({"alg": alg.name()});
+    let claims = json!({"exp": get_time() + 10000});
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let alg = new_verifier(OTHER_PUBKEY);
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify(token, &alg) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+}
+
+#[test]
+fn tampered_claims() {
+    let alg = new_signer();
+    let header = json!({"alg": alg.name()});
+    let claims = json!({"sub": "alice", "exp": get_time() + 10000});
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let tampered_claims = json!({"sub": "mallory", "exp": get_time() + 10000});
+    let tampered_claims = URL_SAFE_NO_PAD.encode(tampered_claims.to_string());
+    let parts: Vec<&str> = token.split('.').collect();
+    let token = [parts[0], &tampered_claims, parts[2]].join(".");
+
+    let alg = new_verifier(PUBKEY);
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify_for_time(token, &alg, get_time()) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn signing_and_verify_only_keys() {
+    let signer = new_signer();
+    let verifier_alg = new_verifier(PUBKEY);
+    assert!(signer.can_sign());
+    assert!(!verifier_alg.can_sign());
+
+    let claims = json!({ "sub": "b@b.com", "exp": get_time() + 10000 });
+    let header = json!({ "alg": "EdDSA" });
+    match jwt::encode(&header, &claims, &verifier_alg) {
+        Err(Error::CannotSign()) => {}
+        other => panic!("Expected CannotSign, got {other:?}"),
+    }
+
+    // A signing key can also verify
+    let token = jwt::encode(&header, &claims, &signer).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+    assert_eq!(verifier.verify(&token, &signer).unwrap(), claims);
+    let other_key = new_verifier(OTHER_PUBKEY);
+    match verifier.verify(&token, &other_key) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+}
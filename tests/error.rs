@@ -0,0 +1,160 @@
+use std::error::Error as StdError;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use jsonwebtokens as jwt;
+use jwt::error::Error;
+use jwt::{Algorithm, AlgorithmID, Verifier};
+
+mod common;
+use common::get_time;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+fn source<E: StdError + 'static>(err: &Error) -> &E {
+    err.source()
+        .unwrap_or_else(|| panic!("Expected a source for {err:?}"))
+        .downcast_ref::<E>()
+        .unwrap_or_else(|| panic!("Unexpected source type for {err:?}"))
+}
+
+#[test]
+fn json_source() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = URL_SAFE_NO_PAD.encode(json!({ "alg": "HS256" }).to_string());
+    let claims = URL_SAFE_NO_PAD.encode("{ not json");
+    let message = format!("{header}.{claims}");
+    let token = format!("{message}.{}", alg.sign(&message).unwrap());
+
+    let verifier = Verifier::create().build().unwrap();
+    let err = verifier.verify(token, &alg).unwrap_err();
+    assert!(matches!(err, Error::MalformedToken(_)));
+    let _: &serde_json::Error = source(&err);
+}
+
+#[test]
+fn base64_source() {
+    let err = jwt::decode_unverified("e30.!!!.").err().unwrap();
+    let _: &base64::DecodeError = source(&err);
+}
+
+#[test]
+fn pem_source() {
+    let err = Algorithm::new_rsa_pem_verifier(AlgorithmID::RS256, b"not a pem").unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn backend_source() {
+    // A valid PEM and DER encoding, but not a valid key
+    let pem = include_bytes!("ecdsa/private_ecdsa_key_jwtio_p256_pkcs8.pem");
+    let err = Algorithm::new_ecdsa_pem_signer(AlgorithmID::ES384, pem).unwrap_err();
+    #[cfg(not(feature = "rust-crypto"))]
+    let _: &ring::error::KeyRejected = source(&err);
+    #[cfg(feature = "rust-crypto")]
+    let _: &p384::pkcs8::Error = source(&err);
+}
+
+#[derive(Debug, Deserialize)]
+struct MyClaims {
+    #[allow(dead_code)]
+    admin: bool,
+}
+
+#[test]
+fn malformed_claims_source() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let claims = json!({ "exp": get_time() + 10000 });
+    let token = jwt::encode(&json!({}), &claims, &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    let err = verifier.verify_typed::<MyClaims>(token, &alg).unwrap_err();
+    let _: &serde_json::Error = source(&err);
+}
+
+#[test]
+fn no_source() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let token = jwt::encode(&json!({}), &json!({ "exp": 1000 }), &alg).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+    let err = verifier.verify(token, &alg).unwrap_err();
+    assert!(matches!(err, Error::TokenExpiredAt(1000)));
+    assert!(err.source().is_none());
+}
+
+#[test]
+fn error_categories() {
+    use jwt::error::ErrorCategory;
+
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create().issuer("some-issuer").build().unwrap();
+    let category = |claims: serde_json::Value, alg: &Algorithm| {
+        let token = jwt::encode(&header, &claims, alg).unwrap();
+        verifier.verify(token, alg).unwrap_err().category()
+    };
+
+    let now = get_time();
+    let iss = "some-issuer";
+    assert_eq!(
+        category(json!({ "iss": iss, "exp": now - 100 }), &alg),
+        ErrorCategory::Expired
+    );
+    assert_eq!(
+        category(json!({ "iss": iss, "nbf": now + 100 }), &alg),
+        ErrorCategory::NotYetValid
+    );
+    assert_eq!(
+        category(json!({ "iss": iss, "iat": now + 100 }), &alg),
+        ErrorCategory::NotYetValid
+    );
+    assert_eq!(
+        category(json!({ "iss": "other" }), &alg),
+        ErrorCategory::PolicyFailed
+    );
+    assert_eq!(
+        category(json!({ "iss": 123 }), &alg),
+        ErrorCategory::Malformed
+    );
+
+    let token = jwt::encode(&header, &json!({ "iss": iss }), &alg).unwrap();
+    let other_alg = Algorithm::new_hmac(AlgorithmID::HS256, "other secret").unwrap();
+    let err = verifier.verify(&token, &other_alg).unwrap_err();
+    assert_eq!(err.category(), ErrorCategory::BadSignature);
+    let err = verifier.verify("not a token", &alg).unwrap_err();
+    assert_eq!(err.category(), ErrorCategory::Malformed);
+}
+
+#[test]
+fn from_dependency_errors() {
+    fn claims_from_json(json: &str) -> Result<serde_json::Value, Error> {
+        let claims: serde_json::Value = serde_json::from_str(json)?;
+        Ok(claims)
+    }
+    fn decode_secret(secret_b64: &str) -> Result<Vec<u8>, Error> {
+        Ok(URL_SAFE_NO_PAD.decode(secret_b64)?)
+    }
+
+    assert_eq!(
+        claims_from_json(r#"{"sub":"user"}"#).unwrap(),
+        json!({ "sub": "user" })
+    );
+    match claims_from_json("{") {
+        Err(err @ Error::InvalidInput(_)) => {
+            assert_eq!(err.to_string(), "Invalid Input: json parse failure");
+            assert!(source::<serde_json::Error>(&err).is_eof());
+        }
+        other => panic!("Expected InvalidInput, got {other:?}"),
+    }
+
+    assert_eq!(decode_secret("c2VjcmV0").unwrap(), b"secret");
+    match decode_secret("c2VjcmV0!") {
+        Err(err @ Error::InvalidInput(_)) => {
+            assert_eq!(err.to_string(), "Invalid Input: base64 decode failure");
+            source::<base64::DecodeError>(&err);
+        }
+        other => panic!("Expected InvalidInput, got {other:?}"),
+    }
+}
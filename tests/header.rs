@@ -0,0 +1,228 @@
+use serde_json::json;
+
+use jsonwebtokens as jwt;
+use jwt::{error::Error, Algorithm, AlgorithmID, Header, Verifier};
+
+mod common;
+use common::get_time;
+
+#[test]
+fn round_trip_kid() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = Header::for_algorithm(&alg).with_kid("key0");
+    assert_eq!(header.alg(), Some("HS256"));
+    let claims = json!({ "exp": get_time() + 10000 });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let unverified = jwt::decode_unverified(&token).unwrap();
+    assert_eq!(unverified.header, json!({ "alg": "HS256", "kid": "key0" }));
+    let header = Header::try_from(unverified.header).unwrap();
+    assert_eq!(header.kid(), Some("key0"));
+    assert_eq!(header.alg(), Some("HS256"));
+}
+
+#[test]
+fn for_algorithm_with_kid() {
+    let mut alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    alg.set_kid("key1");
+    let header = Header::for_algorithm(&alg);
+    assert_eq!(header.kid(), Some("key1"));
+
+    // The Algorithm's kid must match the token's kid to be verified
+    let claims = json!({ "exp": get_time() + 10000 });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+    let data = verifier.verify_for_time(&token, &alg, get_time()).unwrap();
+    assert_eq!(Header::try_from(data.header).unwrap().kid(), Some("key1"));
+}
+
+#[test]
+fn missing_or_non_string_kid() {
+    assert_eq!(Header::new().kid(), None);
+    let header = Header::try_from(json!({ "alg": "HS256", "kid": 1234 })).unwrap();
+    assert_eq!(header.kid(), None);
+    assert_eq!(header.get("kid"), Some(&json!(1234)));
+}
+
+#[test]
+#[should_panic(expected = "MalformedToken")]
+fn non_object_header() {
+    let _header = Header::try_from(json!(["alg", "HS256"])).unwrap();
+}
+
+#[test]
+fn verified_token_data_accessors() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = Header::for_algorithm(&alg).with_kid("key0");
+    let token = jwt::encode(&header, &json!({ "sub": "user" }), &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    let data = verifier.verify_for_time(&token, &alg, get_time()).unwrap();
+    assert_eq!(data.claims(), &json!({ "sub": "user" }));
+    let header = Header::try_from(data.header().clone()).unwrap();
+    assert_eq!(header.kid(), Some("key0"));
+    assert_eq!(header.alg(), Some("HS256"));
+}
+
+#[test]
+fn algorithm_id() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS384, "secret").unwrap();
+    let token = jwt::encode(&Header::for_algorithm(&alg), &json!({}), &alg).unwrap();
+    let unverified = jwt::decode_unverified(&token).unwrap();
+    let header = Header::try_from(unverified.header).unwrap();
+    assert_eq!(header.algorithm_id().unwrap(), AlgorithmID::HS384);
+
+    match Header::new().algorithm_id() {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {other:?}"),
+    }
+    match Header::new().with_param("alg", 256).algorithm_id() {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {other:?}"),
+    }
+    match Header::new().with_param("alg", "XS256").algorithm_id() {
+        Err(Error::UnsupportedAlgorithm(name)) => assert_eq!(name, "XS256"),
+        other => panic!("Expected UnsupportedAlgorithm, got {other:?}"),
+    }
+}
+
+#[test]
+fn require_typ() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let encode_with_typ = |typ: Option<&str>| {
+        let header = Header::for_algorithm(&alg);
+        let header = match typ {
+            Some(typ) => header.with_typ(typ),
+            None => header,
+        };
+        jwt::encode(&header, &json!({ "sub": "user" }), &alg).unwrap()
+    };
+
+    let token = encode_with_typ(Some("at+jwt"));
+    let unverified = jwt::decode_unverified(&token).unwrap();
+    assert_eq!(
+        Header::try_from(unverified.header).unwrap().typ(),
+        Some("at+jwt")
+    );
+
+    // 'typ' isn't checked by default
+    let verifier = Verifier::create().build().unwrap();
+    verifier.verify_for_time(&token, &alg, get_time()).unwrap();
+    verifier
+        .verify_for_time(encode_with_typ(None), &alg, get_time())
+        .unwrap();
+
+    let verifier = Verifier::create().require_typ("at+jwt").build().unwrap();
+    for typ in [
+        "at+jwt",
+        "AT+JWT",
+        "application/at+jwt",
+        "Application/At+JWT",
+    ] {
+        verifier
+            .verify_for_time(encode_with_typ(Some(typ)), &alg, get_time())
+            .unwrap();
+    }
+
+    for typ in [
+        Some("JWT"),
+        Some("application/jwt"),
+        Some("text/at+jwt"),
+        None,
+    ] {
+        match verifier.verify(encode_with_typ(typ), &alg) {
+            Err(Error::InvalidHeader(name)) => assert_eq!(name, "typ"),
+            other => panic!("Expected InvalidHeader for {typ:?}, got {other:?}"),
+        }
+    }
+
+    let token = jwt::encode(
+        &Header::for_algorithm(&alg).with_param("typ", 1),
+        &json!({}),
+        &alg,
+    )
+    .unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::InvalidHeader(name)) => assert_eq!(name, "typ"),
+        other => panic!("Expected InvalidHeader, got {other:?}"),
+    }
+}
+
+#[test]
+fn x5c_key_binding() {
+    let ec_certificate = include_bytes!("ecdsa/certificate_ecdsa_p256.der");
+    let rsa_certificate = include_bytes!("rsa/certificate_rsa_pkcs1.der");
+    let ec_signer = Algorithm::new_ecdsa_pem_signer(
+        AlgorithmID::ES256,
+        include_bytes!("ecdsa/private_ecdsa_key_jwtio_p256_pkcs8.pem"),
+    )
+    .unwrap();
+    let ec_verifier = Algorithm::new_ecdsa_pem_verifier(
+        AlgorithmID::ES256,
+        include_bytes!("ecdsa/public_ecdsa_key_jwtio_p256_pkcs8.pem"),
+    )
+    .unwrap();
+    let claims = json!({ "sub": "user" });
+
+    let header = Header::for_algorithm(&ec_signer)
+        .with_x5c(&[&ec_certificate[..], &rsa_certificate[..]])
+        .with_x5t_s256(ec_certificate);
+    let token = jwt::encode(&header, &claims, &ec_signer).unwrap();
+    let unverified = Header::try_from(jwt::decode_unverified(&token).unwrap().header).unwrap();
+    assert_eq!(
+        unverified.x5c().unwrap(),
+        Some(vec![ec_certificate.to_vec(), rsa_certificate.to_vec()])
+    );
+    assert_eq!(unverified.x5t_s256(), header.x5t_s256());
+
+    let verifier = Verifier::create()
+        .require_x5c_key_binding()
+        .build()
+        .unwrap();
+    for alg in [&ec_signer, &ec_verifier] {
+        verifier.verify_for_time(&token, alg, get_time()).unwrap();
+    }
+
+    let rsa_signer = Algorithm::new_rsa_pem_signer(
+        AlgorithmID::RS256,
+        include_bytes!("rsa/private_rsa_key_pkcs1.pem"),
+    )
+    .unwrap();
+    let rsa_header = Header::for_algorithm(&rsa_signer).with_x5c(&[rsa_certificate]);
+    let rsa_token = jwt::encode(&rsa_header, &claims, &rsa_signer).unwrap();
+    verifier
+        .verify_for_time(&rsa_token, &rsa_signer, get_time())
+        .unwrap();
+
+    // The certificate must be for the verifying key, and match any thumbprint
+    let expect_invalid = |header: Header, param: &str| {
+        let token = jwt::encode(&header, &claims, &ec_signer).unwrap();
+        match verifier.verify_for_time(&token, &ec_verifier, get_time()) {
+            Err(Error::InvalidHeader(name)) => assert_eq!(name, param),
+            other => panic!("Expected InvalidHeader, got {:?}", other.err()),
+        }
+    };
+    expect_invalid(Header::for_algorithm(&ec_signer), "x5c");
+    expect_invalid(
+        Header::for_algorithm(&ec_signer).with_x5c(&[rsa_certificate]),
+        "x5c",
+    );
+    expect_invalid(
+        Header::for_algorithm(&ec_signer).with_param("x5c", json!(["%%%"])),
+        "x5c",
+    );
+    expect_invalid(header.clone().with_x5t_s256(rsa_certificate), "x5t#S256");
+
+    // 'x5c' isn't checked by default
+    let token = jwt::encode(
+        &Header::for_algorithm(&ec_signer).with_x5c(&[rsa_certificate]),
+        &claims,
+        &ec_signer,
+    )
+    .unwrap();
+    Verifier::create()
+        .build()
+        .unwrap()
+        .verify_for_time(&token, &ec_verifier, get_time())
+        .unwrap();
+}
@@ -0,0 +1,352 @@
+use serde_json::json;
+use serde_json::value::Value;
+
+use jsonwebtokens as jwt;
+use jwt::{error::Error, Algorithm, AlgorithmID, JwkSet, TokenData, Verifier};
+
+mod common;
+use common::get_time;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+fn round_trip(signer: &Algorithm, jwk: &Value) {
+    let my_claims = json!({
+        "sub": "b@b.com",
+        "company": "ACME",
+        "exp": get_time() + 10000,
+    });
+    let header = json!({"alg": signer.name(), "kid": "kid1234"});
+    let token = jwt::encode(&header, &my_claims, signer).unwrap();
+
+    let alg = Algorithm::from_jwk(jwk).unwrap();
+    assert_eq!(alg.id(), signer.id());
+    assert_eq!(alg.kid(), Some("kid1234"));
+
+    let verifier = Verifier::create().build().unwrap();
+    let claims: Value = verifier.verify(token, &alg).unwrap();
+    assert_eq!(my_claims, claims);
+}
+
+#[test]
+fn oct_jwk() {
+    let signer = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let jwk = json!({"kty": "oct", "alg": "HS256", "kid": "kid1234", "k": "c2VjcmV0"});
+    round_trip(&signer, &jwk);
+}
+
+#[test]
+fn rsa_jwk() {
+    let privkey = include_bytes!("rsa/private_rsa_key_pkcs1.pem");
+    let signer = Algorithm::new_rsa_pem_signer(AlgorithmID::RS256, privkey).unwrap();
+    let jwk = json!({
+        "kty": "RSA",
+        "alg": "RS256",
+        "use": "sig",
+        "kid": "kid1234",
+        "n": "yRE6rHuNR0QbHO3H3Kt2pOKGVhQqGZXInOduQNxXzuKlvQTLUTv4l4sggh5_CYYi_cvI-SXVT9kPWSKXxJXBXd_4LkvcPuUakBoAkfh-eiFVMh2VrUyWyj3MFl0HTVF9KwRXLAcwkREiS3npThHRyIxuy0ZMeZfxVL5arMhw1SRELB8HoGfG_AtH89BIE9jDBHZ9dLelK9a184zAf8LwoPLxvJb3Il5nncqPcSfKDDodMFBIMc4lQzDKL5gvmiXLXB1AGLm8KBjfE8s3L5xqi-yUod-j8MtvIj812dkS4QMiRVN_by2h3ZY8LYVGrqZXZTcgn2ujn8uKjXLZVD5TdQ",
+        "e": "AQAB"
+    });
+    round_trip(&signer, &jwk);
+}
+
+#[test]
+fn ec_jwk() {
+    let privkey = include_bytes!("ecdsa/private_ecdsa_key_jwtio_p256_pkcs8.pem");
+    let signer = Algorithm::new_ecdsa_pem_signer(AlgorithmID::ES256, privkey).unwrap();
+    let jwk = json!({
+        "kty": "EC",
+        "crv": "P-256",
+        "kid": "kid1234",
+        "x": "EVs_o5-uQbTjL3chynL4wXgUg2R9q9UU8I5mEovUf84",
+        "y": "kGe5DgSIycKp8w9aJmoHhB1sB3QTugfnRWm5nU_TzsY"
+    });
+    round_trip(&signer, &jwk);
+
+    let privkey = include_bytes!("ecdsa/private_ecdsa_key_jwtio_p384_pkcs8.pem");
+    let signer = Algorithm::new_ecdsa_pem_signer(AlgorithmID::ES384, privkey).unwrap();
+    let jwk = json!({
+        "kty": "EC",
+        "crv": "P-384",
+        "alg": "ES384",
+        "kid": "kid1234",
+        "x": "C1uWSXj2czCDwMTLWV5BFmwxdM6PX9p-Pk9Yf9rIf374m5XP1U8q79dBhLSIuaoj",
+        "y": "svOT39UUcPJROSD1FqYLued0rXiooIii1D3jaW6pmGVJFhodzC31cy5sfOYotrzF"
+    });
+    round_trip(&signer, &jwk);
+}
+
+#[test]
+fn okp_jwk() {
+    let seed = URL_SAFE_NO_PAD
+        .decode("rAsSIx8YqbQeIhA5BMD8oKqwbY_84zsJg6urTbBQ8mk")
+        .unwrap();
+    let signer = Algorithm::new_ed25519_signer(&seed).unwrap();
+    let jwk = json!({
+        "kty": "OKP",
+        "crv": "Ed25519",
+        "kid": "kid1234",
+        "x": "P_m2yaaAtmu7bLWEeDlWC_2mY5HYu9m8s71rNnl7BUU"
+    });
+    round_trip(&signer, &jwk);
+}
+
+#[test]
+#[should_panic(expected = "missing required 'e' member")]
+fn rsa_jwk_missing_exponent() {
+    let jwk = json!({"kty": "RSA", "alg": "RS256", "n": "AQAB"});
+    Algorithm::from_jwk(&jwk).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "missing required 'alg' member")]
+fn oct_jwk_missing_alg() {
+    let jwk = json!({"kty": "oct", "k": "c2VjcmV0"});
+    Algorithm::from_jwk(&jwk).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "inconsistent with its curve")]
+fn ec_jwk_inconsistent_alg() {
+    let jwk = json!({
+        "kty": "EC",
+        "crv": "P-256",
+        "alg": "ES384",
+        "x": "EVs_o5-uQbTjL3chynL4wXgUg2R9q9UU8I5mEovUf84",
+        "y": "kGe5DgSIycKp8w9aJmoHhB1sB3QTugfnRWm5nU_TzsY"
+    });
+    Algorithm::from_jwk(&jwk).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Unsupported JWK kty 'foo'")]
+fn unsupported_kty() {
+    let jwk = json!({"kty": "foo"});
+    Algorithm::from_jwk(&jwk).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "isn't for signatures")]
+fn encryption_jwk() {
+    let jwk = json!({"kty": "oct", "alg": "HS256", "use": "enc", "k": "c2VjcmV0"});
+    Algorithm::from_jwk(&jwk).unwrap();
+}
+
+const JWKS: &str = r#"{
+    "keys": [
+        { "kty": "oct", "alg": "HS256", "kid": "key0", "k": "c2VjcmV0MA" },
+        { "kty": "oct", "alg": "HS256", "kid": "key1", "k": "c2VjcmV0MQ" },
+        { "kty": "oct", "alg": "A128KW", "use": "enc", "kid": "key2", "k": "c2VjcmV0Mg" }
+    ]
+}"#;
+
+#[test]
+fn jwks_select_by_kid() {
+    let jwks = JwkSet::from_json(JWKS).unwrap();
+    assert_eq!(jwks.keys().count(), 2);
+    assert!(jwks.find("key1").is_some());
+    assert!(jwks.find("key2").is_none());
+
+    let my_claims = json!({"sub": "b@b.com", "exp": get_time() + 10000});
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret1").unwrap();
+    let header = json!({"alg": alg.name(), "kid": "key1"});
+    let token = jwt::encode(&header, &my_claims, &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    let claims = verifier.verify_with_jwks(&token, &jwks).unwrap();
+    assert_eq!(my_claims, claims);
+
+    let TokenData { header, .. } = verifier
+        .verify_for_time_with_jwks(&token, &jwks, get_time())
+        .unwrap();
+    assert_eq!(header.get("kid").unwrap(), "key1");
+}
+
+#[test]
+fn jwks_skips_unusable_keys() {
+    let jwks = json!({
+        "keys": [
+            { "kty": "foo", "kid": "key0" },
+            { "kty": "EC", "crv": "P-999", "kid": "key1", "x": "AA", "y": "AA" },
+            { "kty": "RSA", "alg": "RSA-OAEP", "use": "enc", "kid": "key2", "n": "AQAB", "e": "AQAB" },
+            { "kty": "oct", "kid": "key3", "k": "c2VjcmV0Mw" },
+            { "kty": "oct", "alg": "HS256", "kid": "key4", "k": "c2VjcmV0NA" },
+        ]
+    });
+    let jwks = JwkSet::from_value(&jwks).unwrap();
+    let kids: Vec<_> = jwks.keys().map(|alg| alg.kid().unwrap()).collect();
+    assert_eq!(kids, ["key4"]);
+
+    let claims = json!({"sub": "b@b.com", "exp": get_time() + 10000});
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret4").unwrap();
+    let token = jwt::encode(&json!({"alg": alg.name(), "kid": "key4"}), &claims, &alg).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+    assert_eq!(verifier.verify_with_jwks(&token, &jwks).unwrap(), claims);
+}
+
+#[test]
+fn jwks_without_usable_keys() {
+    for jwks in [
+        json!({ "keys": "key0" }),
+        json!({ "keys": [] }),
+        json!({ "keys": [{ "kty": "foo" }] }),
+        json!({ "keys": [{ "kty": "oct", "alg": "HS256", "k": "c2VjcmV0" }, "key1"] }),
+    ] {
+        match JwkSet::from_value(&jwks) {
+            Err(Error::InvalidInput(_)) => {}
+            other => panic!("Expected InvalidInput, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn jwks_unknown_kid() {
+    let jwks = JwkSet::from_json(JWKS).unwrap();
+
+    let claims = json!({"sub": "b@b.com", "exp": get_time() + 10000});
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret1").unwrap();
+    let header = json!({"alg": alg.name(), "kid": "key3"});
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify_with_jwks(&token, &jwks) {
+        Err(Error::NoMatchingKey(kid)) => assert_eq!(kid, "key3"),
+        other => panic!("Expected NoMatchingKey, got {other:?}"),
+    }
+}
+
+#[test]
+#[should_panic(expected = "MalformedToken")]
+fn jwks_missing_kid() {
+    let jwks = JwkSet::from_json(JWKS).unwrap();
+
+    let claims = json!({"sub": "b@b.com", "exp": get_time() + 10000});
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret1").unwrap();
+    let header = json!({"alg": alg.name()});
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    verifier.verify_with_jwks(&token, &jwks).unwrap();
+}
+
+#[test]
+fn jwks_without_kid() {
+    let jwks = JwkSet::from_json(JWKS).unwrap();
+    let verifier = Verifier::create().jwks_without_kid().build().unwrap();
+    let claims = json!({"sub": "b@b.com", "exp": get_time() + 10000});
+
+    // Both HS256 keys are tried
+    for secret in ["secret0", "secret1"] {
+        let alg = Algorithm::new_hmac(AlgorithmID::HS256, secret).unwrap();
+        let token = jwt::encode(&json!({"alg": "HS256"}), &claims, &alg).unwrap();
+        assert_eq!(verifier.verify_with_jwks(&token, &jwks).unwrap(), claims);
+    }
+
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret2").unwrap();
+    let token = jwt::encode(&json!({"alg": "HS256"}), &claims, &alg).unwrap();
+    match verifier.verify_with_jwks(&token, &jwks) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+
+    let alg = Algorithm::new_hmac(AlgorithmID::HS384, "secret0").unwrap();
+    let token = jwt::encode(&json!({"alg": "HS384"}), &claims, &alg).unwrap();
+    match verifier.verify_with_jwks(&token, &jwks) {
+        Err(Error::AlgorithmMismatch()) => {}
+        other => panic!("Expected AlgorithmMismatch, got {other:?}"),
+    }
+
+    // A 'kid' still selects a single key
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret0").unwrap();
+    let token = jwt::encode(&json!({"alg": "HS256", "kid": "key1"}), &claims, &alg).unwrap();
+    match verifier.verify_with_jwks(&token, &jwks) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+}
+
+#[test]
+fn jwk_thumbprint() {
+    // The example from RFC 7638, section 3.1
+    let alg = Algorithm::new_rsa_n_e_b64_verifier(
+        AlgorithmID::RS256,
+        "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+        "AQAB",
+    )
+    .unwrap();
+    assert_eq!(
+        alg.jwk_thumbprint().unwrap(),
+        "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+    );
+
+    // The thumbprint doesn't depend on how the key was given
+    let rsa = [
+        Algorithm::new_rsa_pem_signer(
+            AlgorithmID::RS256,
+            include_bytes!("rsa/private_rsa_key_pkcs1.pem"),
+        )
+        .unwrap(),
+        Algorithm::new_rsa_pem_verifier(
+            AlgorithmID::RS256,
+            include_bytes!("rsa/public_rsa_key_pkcs1.pem"),
+        )
+        .unwrap(),
+        Algorithm::new_rsa_n_e_b64_verifier(
+            AlgorithmID::RS256,
+            "yRE6rHuNR0QbHO3H3Kt2pOKGVhQqGZXInOduQNxXzuKlvQTLUTv4l4sggh5_CYYi_cvI-SXVT9kPWSKXxJXBXd_4LkvcPuUakBoAkfh-eiFVMh2VrUyWyj3MFl0HTVF9KwRXLAcwkREiS3npThHRyIxuy0ZMeZfxVL5arMhw1SRELB8HoGfG_AtH89BIE9jDBHZ9dLelK9a184zAf8LwoPLxvJb3Il5nncqPcSfKDDodMFBIMc4lQzDKL5gvmiXLXB1AGLm8KBjfE8s3L5xqi-yUod-j8MtvIj812dkS4QMiRVN_by2h3ZY8LYVGrqZXZTcgn2ujn8uKjXLZVD5TdQ",
+            "AQAB",
+        )
+        .unwrap(),
+    ];
+    let ec = [
+        Algorithm::new_ecdsa_pem_signer(
+            AlgorithmID::ES256,
+            include_bytes!("ecdsa/private_ecdsa_key_jwtio_p256_pkcs8.pem"),
+        )
+        .unwrap(),
+        Algorithm::new_ecdsa_pem_verifier(
+            AlgorithmID::ES256,
+            include_bytes!("ecdsa/public_ecdsa_key_jwtio_p256_pkcs8.pem"),
+        )
+        .unwrap(),
+        Algorithm::new_ecdsa_x_y_b64_verifier(
+            AlgorithmID::ES256,
+            "EVs_o5-uQbTjL3chynL4wXgUg2R9q9UU8I5mEovUf84",
+            "kGe5DgSIycKp8w9aJmoHhB1sB3QTugfnRWm5nU_TzsY",
+        )
+        .unwrap(),
+    ];
+    let oct = [
+        Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap(),
+        Algorithm::new_hmac_b64(AlgorithmID::HS512, "c2VjcmV0").unwrap(),
+    ];
+    let seed = URL_SAFE_NO_PAD
+        .decode("rAsSIx8YqbQeIhA5BMD8oKqwbY_84zsJg6urTbBQ8mk")
+        .unwrap();
+    let public_key = URL_SAFE_NO_PAD
+        .decode("P_m2yaaAtmu7bLWEeDlWC_2mY5HYu9m8s71rNnl7BUU")
+        .unwrap();
+    let okp = [
+        Algorithm::new_ed25519_signer(&seed).unwrap(),
+        Algorithm::new_ed25519_verifier(&public_key).unwrap(),
+    ];
+    for keys in [&rsa[..], &ec[..], &oct[..], &okp[..]] {
+        let thumbprint = keys[0].jwk_thumbprint().unwrap();
+        for alg in keys {
+            assert_eq!(alg.jwk_thumbprint().unwrap(), thumbprint);
+        }
+    }
+    assert_ne!(
+        rsa[0].jwk_thumbprint().unwrap(),
+        ec[0].jwk_thumbprint().unwrap()
+    );
+
+    for alg in [
+        Algorithm::new_unsecured().unwrap(),
+        Algorithm::new_hmac_from_slice(AlgorithmID::HS256, b"secret").unwrap(),
+    ] {
+        match alg.jwk_thumbprint() {
+            Err(Error::InvalidInput(_)) => {}
+            other => panic!("Expected InvalidInput, got {other:?}"),
+        }
+    }
+}
@@ -1,3 +1,4 @@
 mod common;
 mod ecdsa;
+mod eddsa;
 mod rsa;
@@ -0,0 +1,228 @@
+#![cfg(feature = "remote-jwks")]
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+use serde_json::json;
+
+use jsonwebtokens as jwt;
+use jwt::{error::Error, Algorithm, AlgorithmID, Verifier};
+use jwt::{JwksFetchFuture, JwksFetcher, JwksResponse, RemoteJwks};
+
+mod common;
+use common::get_time;
+
+const JWKS_URL: &str = "https://example.com/.well-known/jwks.json";
+
+const JWKS_KEY0: &str = r#"{ "keys": [
+    { "kty": "oct", "alg": "HS256", "kid": "key0", "k": "c2VjcmV0MA" }
+]}"#;
+const JWKS_KEY0_KEY1: &str = r#"{ "keys": [
+    { "kty": "oct", "alg": "HS256", "kid": "key0", "k": "c2VjcmV0MA" },
+    { "kty": "oct", "alg": "HS256", "kid": "key1", "k": "c2VjcmV0MQ" }
+]}"#;
+
+#[derive(Default)]
+struct MockServer {
+    // None simulates a server error
+    body: Mutex<Option<&'static str>>,
+    cache_control: Mutex<Option<&'static str>>,
+    delay: Mutex<Duration>,
+    fetches: AtomicUsize,
+}
+
+impl MockServer {
+    fn new(body: &'static str) -> Arc<Self> {
+        let server = MockServer::default();
+        *server.body.lock().unwrap() = Some(body);
+        Arc::new(server)
+    }
+
+    fn fetches(&self) -> usize {
+        self.fetches.load(Ordering::SeqCst)
+    }
+}
+
+struct MockFetcher(Arc<MockServer>);
+
+impl JwksFetcher for MockFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> JwksFetchFuture<'a> {
+        Box::pin(async move {
+            assert_eq!(url, JWKS_URL);
+            let server = &self.0;
+            server.fetches.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(*server.delay.lock().unwrap());
+            let cache_control = server.cache_control.lock().unwrap().map(|s| s.to_owned());
+            let body = *server.body.lock().unwrap();
+            match body {
+                Some(body) => Ok(JwksResponse {
+                    body: body.to_owned(),
+                    cache_control,
+                }),
+                None => Err("503 Service Unavailable".into()),
+            }
+        })
+    }
+}
+
+fn new_remote_jwks(server: &Arc<MockServer>) -> RemoteJwks<MockFetcher> {
+    RemoteJwks::new(JWKS_URL, MockFetcher(server.clone()))
+}
+
+#[test]
+fn fetch_once_and_cache() {
+    let server = MockServer::new(JWKS_KEY0);
+    let jwks = new_remote_jwks(&server);
+
+    let alg = tokio_test::block_on(jwks.get_key("key0")).unwrap();
+    assert_eq!(alg.kid(), Some("key0"));
+    let _alg = tokio_test::block_on(jwks.get_key("key0")).unwrap();
+    assert_eq!(server.fetches(), 1);
+}
+
+#[test]
+fn refetch_after_max_age() {
+    let server = MockServer::new(JWKS_KEY0);
+    *server.cache_control.lock().unwrap() = Some("public, max-age=0");
+    let jwks = new_remote_jwks(&server);
+
+    let _alg = tokio_test::block_on(jwks.get_key("key0")).unwrap();
+    let _alg = tokio_test::block_on(jwks.get_key("key0")).unwrap();
+    assert_eq!(server.fetches(), 2);
+}
+
+#[test]
+fn refetch_for_unknown_kid() {
+    let server = MockServer::new(JWKS_KEY0);
+    let mut jwks = new_remote_jwks(&server);
+    jwks.set_min_refresh_interval(Duration::ZERO);
+
+    let _alg = tokio_test::block_on(jwks.get_key("key0")).unwrap();
+
+    // Rotate in a new key
+    *server.body.lock().unwrap() = Some(JWKS_KEY0_KEY1);
+    let alg = tokio_test::block_on(jwks.get_key("key1")).unwrap();
+    assert_eq!(alg.kid(), Some("key1"));
+    assert_eq!(server.fetches(), 2);
+}
+
+#[test]
+fn rate_limit_refetch_for_unknown_kid() {
+    let server = MockServer::new(JWKS_KEY0);
+    let jwks = new_remote_jwks(&server);
+
+    let _alg = tokio_test::block_on(jwks.get_key("key0")).unwrap();
+    for _ in 0..3 {
+        match tokio_test::block_on(jwks.get_key("bogus")) {
+            Err(Error::NoMatchingKey(kid)) => assert_eq!(kid, "bogus"),
+            other => panic!("Expected NoMatchingKey, got {other:?}"),
+        }
+    }
+    assert_eq!(server.fetches(), 1);
+}
+
+#[test]
+fn coalesce_concurrent_fetches() {
+    let server = MockServer::new(JWKS_KEY0);
+    *server.delay.lock().unwrap() = Duration::from_millis(100);
+    let jwks = new_remote_jwks(&server);
+
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                let _alg = tokio_test::block_on(jwks.get_key("key0")).unwrap();
+            });
+        }
+    });
+    assert_eq!(server.fetches(), 1);
+}
+
+#[test]
+fn coalesce_concurrent_fetches_without_caching() {
+    // Tasks waiting on another's fetch still use its key set, even though
+    // it's already expired by the time they get to look at it
+    let server = MockServer::new(JWKS_KEY0);
+    *server.cache_control.lock().unwrap() = Some("max-age=0");
+    *server.delay.lock().unwrap() = Duration::from_millis(100);
+    let jwks = new_remote_jwks(&server);
+
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                let alg = tokio_test::block_on(jwks.get_key("key0")).unwrap();
+                assert_eq!(alg.kid(), Some("key0"));
+            });
+        }
+    });
+    assert_eq!(server.fetches(), 1);
+}
+
+#[test]
+fn fetch_failure() {
+    let server = MockServer::new(JWKS_KEY0);
+    *server.body.lock().unwrap() = None;
+    let jwks = new_remote_jwks(&server);
+
+    match tokio_test::block_on(jwks.get_key("key0")) {
+        Err(Error::KeyFetchFailed(_)) => {}
+        other => panic!("Expected KeyFetchFailed, got {other:?}"),
+    }
+}
+
+#[test]
+fn verify_with_remote_jwks() {
+    let server = MockServer::new(JWKS_KEY0_KEY1);
+    let jwks = new_remote_jwks(&server);
+
+    let my_claims = json!({"sub": "b@b.com", "exp": get_time() + 10000});
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret1").unwrap();
+    let header = json!({"alg": alg.name(), "kid": "key1"});
+    let token = jwt::encode(&header, &my_claims, &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    let claims = tokio_test::block_on(verifier.verify_with_remote_jwks(&token, &jwks)).unwrap();
+    assert_eq!(my_claims, claims);
+}
+
+/// A minimal executor, to check nothing depends on running within a tokio runtime
+fn block_on_without_tokio<T>(future: impl Future<Output = T>) -> T {
+    struct ThreadWaker(Thread);
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[test]
+fn verify_with_remote_jwks_without_tokio_runtime() {
+    let server = MockServer::new(JWKS_KEY0_KEY1);
+    let jwks = new_remote_jwks(&server);
+
+    let my_claims = json!({"sub": "b@b.com", "exp": get_time() + 10000});
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret1").unwrap();
+    let header = json!({"alg": alg.name(), "kid": "key1"});
+    let token = jwt::encode(&header, &my_claims, &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    let claims = block_on_without_tokio(verifier.verify_with_remote_jwks(&token, &jwks)).unwrap();
+    assert_eq!(my_claims, claims);
+
+    // A cached key is found without a fetch
+    block_on_without_tokio(jwks.get_key("key0")).unwrap();
+    assert_eq!(server.fetches(), 1);
+}
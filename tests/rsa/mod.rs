@@ -2,7 +2,7 @@ use serde_json::json;
 use serde_json::value::Value;
 
 use jsonwebtokens as jwt;
-use jwt::{Algorithm, AlgorithmID, TokenData, Verifier};
+use jwt::{error::Error, Algorithm, AlgorithmID, TokenData, Verifier};
 
 use crate::common::get_time;
 
@@ -26,6 +26,76 @@ fn decode_token_wrong_algorithm() {
     let _claims: Value = verifier.verify(token, &alg).unwrap();
 }
 
+#[test]
+fn algorithm_allowlist() {
+    let privkey_pem = include_bytes!("private_rsa_key_pkcs1.pem");
+    let pubkey_pem = include_bytes!("public_rsa_key_pkcs1.pem");
+
+    let alg = Algorithm::new_rsa_pem_signer(AlgorithmID::RS256, privkey_pem).unwrap();
+    let header = json!({ "alg": alg.name() });
+    let claims = json!({ "exp": get_time() + 10000 });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let alg = Algorithm::new_rsa_pem_verifier(AlgorithmID::RS256, pubkey_pem).unwrap();
+    let verifier = Verifier::create()
+        .algorithms(&[AlgorithmID::RS256, AlgorithmID::ES256])
+        .build()
+        .unwrap();
+    let _claims: Value = verifier.verify(&token, &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .algorithms(&[AlgorithmID::PS256])
+        .build()
+        .unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::AlgorithmMismatch()) => {}
+        other => panic!("Expected AlgorithmMismatch, got {other:?}"),
+    }
+}
+
+#[test]
+fn algorithm_allowlist_rejects_hmac_confusion() {
+    let pubkey_pem = include_bytes!("public_rsa_key_pkcs1.pem");
+
+    // A classic attack is to sign a token with HMAC, using a public key as the
+    // secret, in case the verifier picks an HMAC Algorithm based on the header
+    let attacker_alg = Algorithm::new_hmac(AlgorithmID::HS256, &pubkey_pem[..]).unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "sub": "admin", "exp": get_time() + 10000 });
+    let token = jwt::encode(&header, &claims, &attacker_alg).unwrap();
+
+    let verifier = Verifier::create()
+        .algorithms(&[AlgorithmID::RS256])
+        .build()
+        .unwrap();
+
+    let alg = Algorithm::new_rsa_pem_verifier(AlgorithmID::RS256, pubkey_pem).unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::AlgorithmMismatch()) => {}
+        other => panic!("Expected AlgorithmMismatch, got {other:?}"),
+    }
+    match verifier.verify(&token, &attacker_alg) {
+        Err(Error::AlgorithmMismatch()) => {}
+        other => panic!("Expected AlgorithmMismatch, got {other:?}"),
+    }
+
+    let alg = Algorithm::new_unsecured().unwrap();
+    let token = jwt::encode(&json!({ "alg": "none" }), &claims, &alg).unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::AlgorithmMismatch()) => {}
+        other => panic!("Expected AlgorithmMismatch, got {other:?}"),
+    }
+}
+
+#[test]
+fn algorithm_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let pubkey_pem = include_bytes!("public_rsa_key_pkcs1.pem");
+    let alg = Algorithm::new_rsa_pem_verifier(AlgorithmID::RS256, pubkey_pem).unwrap();
+    assert_send_sync(&alg);
+}
+
 #[test]
 fn round_trip_sign_verification_pem_pkcs1() {
     let privkey_pem = include_bytes!("private_rsa_key_pkcs1.pem");
@@ -65,6 +135,49 @@ fn round_trip_sign_verification_pem_pkcs8() {
     }
 }
 
+#[test]
+fn der_keys_match_pem_keys() {
+    // Converted from private_rsa_key_pkcs1.pem with `openssl rsa` and `openssl pkcs8`
+    let privkey_pem = include_bytes!("private_rsa_key_pkcs1.pem");
+    let privkey_ders: [&[u8]; 2] = [
+        include_bytes!("private_rsa_key_pkcs1.der"),
+        include_bytes!("private_rsa_key_pkcs8.der"),
+    ];
+    let pubkey_ders: [&[u8]; 2] = [
+        include_bytes!("public_rsa_key_pkcs1.der"),
+        include_bytes!("public_rsa_key_pkcs8.der"),
+    ];
+
+    // RSASSA-PKCS1-v1_5 signatures are deterministic
+    let pem_signature = Algorithm::new_rsa_pem_signer(AlgorithmID::RS256, privkey_pem)
+        .unwrap()
+        .sign("hello world")
+        .unwrap();
+    for privkey_der in privkey_ders {
+        let alg = Algorithm::new_rsa_der_signer(AlgorithmID::RS256, privkey_der).unwrap();
+        assert_eq!(alg.sign("hello world").unwrap(), pem_signature);
+    }
+    for pubkey_der in pubkey_ders {
+        let alg = Algorithm::new_rsa_der_verifier(AlgorithmID::RS256, pubkey_der).unwrap();
+        alg.verify(None, "hello world", &pem_signature).unwrap();
+    }
+}
+
+#[test]
+fn invalid_der_keys() {
+    let pubkey_pem = include_bytes!("public_rsa_key_pkcs1.pem");
+    for key in [&b"not a key"[..], &pubkey_pem[..]] {
+        match Algorithm::new_rsa_der_verifier(AlgorithmID::RS256, key) {
+            Err(Error::InvalidInput(_)) => {}
+            other => panic!("Expected InvalidInput, got {:?}", other.err()),
+        }
+        match Algorithm::new_rsa_der_signer(AlgorithmID::RS256, key) {
+            Err(Error::InvalidInput(_)) => {}
+            other => panic!("Expected InvalidInput, got {:?}", other.err()),
+        }
+    }
+}
+
 #[test]
 fn round_trip_claims() {
     let privkey_pem = include_bytes!("private_rsa_key_pkcs8.pem");
@@ -138,8 +251,7 @@ fn dont_allow_sign_with_verify_algorithm() {
 }
 
 #[test]
-#[should_panic(expected = "InvalidInput")]
-fn dont_allow_verify_with_sign_algorithm() {
+fn allow_verify_with_sign_algorithm() {
     let my_claims = json!({
         "sub": "b@b.com",
         "company": "ACME",
@@ -153,7 +265,8 @@ fn dont_allow_verify_with_sign_algorithm() {
     let token = jwt::encode(&header, &my_claims, &sign_alg).unwrap();
 
     let verifier = Verifier::create().build().unwrap();
-    let _claims: Value = verifier.verify(token, &sign_alg).unwrap();
+    let claims: Value = verifier.verify(token, &sign_alg).unwrap();
+    assert_eq!(claims, my_claims);
 }
 
 #[test]
@@ -201,3 +314,96 @@ fn roundtrip_with_jwtio_example_jey() {
         assert_eq!(my_claims, claims);
     }
 }
+
+#[test]
+#[should_panic(expected = "must be at least 2048 bits")]
+fn reject_small_signing_key() {
+    let privkey_pem = include_bytes!("private_rsa_key_1024_pkcs1.pem");
+    let _alg = Algorithm::new_rsa_pem_signer(AlgorithmID::RS256, privkey_pem).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "must be at least 2048 bits")]
+fn reject_small_verification_key() {
+    let pubkey_pem = include_bytes!("public_rsa_key_1024_pkcs1.pem");
+    let _alg = Algorithm::new_rsa_pem_verifier(AlgorithmID::RS256, pubkey_pem).unwrap();
+}
+
+// RSASSA-PKCS1-v1_5 and RSASSA-PSS signatures must not be interchangeable
+#[test]
+fn pkcs1_and_pss_signatures_not_interchangeable() {
+    let privkey_pem = include_bytes!("private_rsa_key_pkcs1.pem");
+    let pubkey_pem = include_bytes!("public_rsa_key_pkcs1.pem");
+
+    for (sign_id, verify_id) in [
+        (AlgorithmID::RS256, AlgorithmID::PS256),
+        (AlgorithmID::PS256, AlgorithmID::RS256),
+    ] {
+        let alg = Algorithm::new_rsa_pem_signer(sign_id, privkey_pem).unwrap();
+        let signature = alg.sign("hello world").unwrap();
+
+        let alg = Algorithm::new_rsa_pem_verifier(verify_id, pubkey_pem).unwrap();
+        match alg.verify(None, "hello world", signature) {
+            Err(Error::InvalidSignature()) => {}
+            other => panic!("Expected InvalidSignature, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn hmac_header_rejected_for_rsa_key_without_allowlist() {
+    let pubkey_pem = include_bytes!("public_rsa_key_pkcs1.pem");
+
+    // Sign with HMAC using the public key bytes as the secret
+    let attacker_alg = Algorithm::new_hmac(AlgorithmID::HS256, &pubkey_pem[..]).unwrap();
+    let claims = json!({ "sub": "admin", "exp": get_time() + 10000 });
+    for name in ["HS256", "HS384", "HS512"] {
+        let header = json!({ "alg": name });
+        let id = name.parse().unwrap();
+        let attacker_alg = Algorithm::new_hmac(id, &pubkey_pem[..]).unwrap();
+        let token = jwt::encode(&header, &claims, &attacker_alg).unwrap();
+
+        // No algorithm allowlist is configured
+        let verifier = Verifier::create().build().unwrap();
+        let alg = Algorithm::new_rsa_pem_verifier(AlgorithmID::RS256, pubkey_pem).unwrap();
+        match verifier.verify(&token, &alg) {
+            Err(Error::AlgorithmMismatch()) => {}
+            other => panic!("Expected AlgorithmMismatch, got {other:?}"),
+        }
+        match verifier.verify_with_keys(&token, &[&alg]) {
+            Err(Error::InvalidSignature()) => {}
+            other => panic!("Expected InvalidSignature, got {other:?}"),
+        }
+    }
+
+    // The HMAC algorithm itself is still usable for HMAC tokens
+    let token = jwt::encode(&json!({ "alg": "HS256" }), &claims, &attacker_alg).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+    verifier.verify(&token, &attacker_alg).unwrap();
+}
+
+#[test]
+fn signing_and_verify_only_keys() {
+    let privkey_pem = include_bytes!("private_rsa_key_pkcs1.pem");
+    let pubkey_pem = include_bytes!("public_rsa_key_pkcs1.pem");
+
+    for &id in RSA_ALGORITHMS {
+        let signer = Algorithm::new_rsa_pem_signer(id, privkey_pem).unwrap();
+        let verifier_alg = Algorithm::new_rsa_pem_verifier(id, pubkey_pem).unwrap();
+        assert!(signer.can_sign());
+        assert!(!verifier_alg.can_sign());
+
+        let claims = json!({ "sub": "b@b.com", "exp": get_time() + 10000 });
+        let header = json!({ "alg": signer.name() });
+        match jwt::encode(&header, &claims, &verifier_alg) {
+            Err(Error::CannotSign()) => {}
+            other => panic!("Expected CannotSign, got {other:?}"),
+        }
+
+        // A signing key can also verify
+        let token = jwt::encode(&header, &claims, &signer).unwrap();
+        let verifier = Verifier::create().build().unwrap();
+        assert_eq!(verifier.verify(&token, &signer).unwrap(), claims);
+        assert_eq!(verifier.verify(&token, &verifier_alg).unwrap(), claims);
+    }
+}
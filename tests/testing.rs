@@ -0,0 +1,53 @@
+#![cfg(feature = "testing")]
+
+use serde_json::json;
+
+use jsonwebtokens as jwt;
+use jwt::{error::Error, testing, Algorithm, AlgorithmID, Verifier};
+
+#[test]
+fn corrupted_tokens() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": alg.name() });
+    let claims = json!({ "sub": "b@b.com" });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+    verifier.verify(&token, &alg).unwrap();
+
+    let tampered = testing::tampered_payload(&token).unwrap();
+    assert_eq!(
+        jwt::decode_unverified(&tampered).unwrap().claims["tampered"],
+        true
+    );
+    match verifier.verify(&tampered, &alg) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+
+    let wrong_alg = testing::wrong_alg(&token, AlgorithmID::HS384).unwrap();
+    match verifier.verify(&wrong_alg, &alg) {
+        Err(Error::AlgorithmMismatch()) => {}
+        other => panic!("Expected AlgorithmMismatch, got {other:?}"),
+    }
+
+    let bad_signature = testing::bad_signature(&token).unwrap();
+    match verifier.verify(&bad_signature, &alg) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+
+    // An unsecured token gets a non-empty signature
+    let none = Algorithm::new_unsecured().unwrap();
+    let token = jwt::encode(&json!({ "alg": "none" }), &claims, &none).unwrap();
+    let bad_signature = testing::bad_signature(&token).unwrap();
+    let slices = jwt::raw::split_token(&bad_signature).unwrap();
+    match none.verify(None, slices.message, slices.signature) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+
+    match testing::tampered_payload("not a token") {
+        Err(Error::WrongSegmentCount(1)) => {}
+        other => panic!("Expected WrongSegmentCount, got {other:?}"),
+    }
+}
@@ -1,9 +1,12 @@
+use std::time::{Duration, UNIX_EPOCH};
+
 use serde_json::json;
 
 use jsonwebtokens as jwt;
-use jwt::{error::Error, Algorithm, AlgorithmID, Verifier};
+use jwt::{error::Error, Algorithm, AlgorithmID, Clock, Verifier};
 
 mod common;
+use common::get_time;
 
 const REFERENCE_TIME: u64 = 1575057015u64;
 
@@ -159,3 +162,668 @@ fn token_used_before_just_before_issue_with_leeway() {
         .verify_for_time(token_str, &alg, REFERENCE_TIME)
         .unwrap();
 }
+
+#[test]
+fn separate_exp_and_nbf_leeway() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "nbf": REFERENCE_TIME + 30, "exp": REFERENCE_TIME + 100 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    // The global leeway applies to nbf while exp has no leeway
+    let verifier = Verifier::create().leeway(60).exp_leeway(0).build().unwrap();
+    let _token_data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME)
+        .unwrap();
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME + 100) {
+        Err(Error::TokenExpiredAt(at)) => assert_eq!(at, REFERENCE_TIME + 100),
+        other => panic!("Expected TokenExpiredAt, got {:?}", other.err()),
+    }
+
+    // A specific nbf leeway overrides the global leeway
+    let verifier = Verifier::create()
+        .leeway(60)
+        .nbf_leeway(10)
+        .build()
+        .unwrap();
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn iat_leeway() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "iat": REFERENCE_TIME + 10 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create().iat_leeway(10).build().unwrap();
+    let _token_data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME)
+        .unwrap();
+
+    let verifier = Verifier::create().leeway(10).iat_leeway(5).build().unwrap();
+    assert!(verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME)
+        .is_err());
+}
+
+#[test]
+fn max_token_age() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "iat": REFERENCE_TIME, "exp": REFERENCE_TIME + 10000 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .max_token_age(Duration::from_secs(300))
+        .build()
+        .unwrap();
+    let _token_data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME + 300)
+        .unwrap();
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME + 301) {
+        Err(Error::TokenTooOld(iat)) => assert_eq!(iat, REFERENCE_TIME),
+        other => panic!("Expected TokenTooOld, got {:?}", other.err()),
+    }
+
+    // The iat leeway allows a slightly older token
+    let verifier = Verifier::create()
+        .max_token_age(Duration::from_secs(300))
+        .iat_leeway(5)
+        .build()
+        .unwrap();
+    let _token_data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME + 305)
+        .unwrap();
+}
+
+#[test]
+fn unbounded_max_ages() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "iat": REFERENCE_TIME, "auth_time": REFERENCE_TIME, "exp": REFERENCE_TIME + 10000 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    // Adding the iat leeway to a maximum age mustn't overflow
+    let verifier = Verifier::create()
+        .max_token_age(Duration::MAX)
+        .max_auth_age(Duration::MAX)
+        .iat_leeway(5)
+        .build()
+        .unwrap();
+    let _token_data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME + 300)
+        .unwrap();
+}
+
+#[test]
+fn max_token_age_missing_iat() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "exp": REFERENCE_TIME + 10000 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .max_token_age(Duration::from_secs(300))
+        .build()
+        .unwrap();
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME) {
+        Err(Error::MissingRequiredClaim(claim)) => assert_eq!(claim, "iat"),
+        other => panic!("Expected MissingRequiredClaim, got {:?}", other.err()),
+    }
+}
+
+struct FixedClock(u64);
+
+impl Clock for FixedClock {
+    fn now(&self) -> u64 {
+        self.0
+    }
+}
+
+#[test]
+fn fixed_clock() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "exp": REFERENCE_TIME });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .clock(FixedClock(REFERENCE_TIME - 1))
+        .build()
+        .unwrap();
+    let _claims = verifier.verify(&token_str, &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .clock(FixedClock(REFERENCE_TIME))
+        .build()
+        .unwrap();
+    match verifier.verify(&token_str, &alg) {
+        Err(Error::TokenExpiredAt(at)) => assert_eq!(at, REFERENCE_TIME),
+        other => panic!("Expected TokenExpiredAt, got {other:?}"),
+    }
+}
+
+#[test]
+fn clock_at_epoch_with_leeway() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "exp": REFERENCE_TIME });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .leeway(60)
+        .clock(FixedClock(0))
+        .build()
+        .unwrap();
+    let _claims = verifier.verify(&token_str, &alg).unwrap();
+}
+
+#[test]
+fn require_exp_and_nbf() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let token_str = jwt::encode(&header, &json!({}), &alg).unwrap();
+
+    let verifier = Verifier::create().require_exp().build().unwrap();
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME) {
+        Err(Error::MissingRequiredClaim(claim)) => assert_eq!(claim, "exp"),
+        other => panic!("Expected MissingRequiredClaim, got {:?}", other.err()),
+    }
+    let verifier = Verifier::create().require_nbf().build().unwrap();
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME) {
+        Err(Error::MissingRequiredClaim(claim)) => assert_eq!(claim, "nbf"),
+        other => panic!("Expected MissingRequiredClaim, got {:?}", other.err()),
+    }
+
+    let claims = json!({ "nbf": REFERENCE_TIME, "exp": REFERENCE_TIME + 100 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create()
+        .require_exp()
+        .require_nbf()
+        .build()
+        .unwrap();
+    let _token_data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME)
+        .unwrap();
+}
+
+#[test]
+fn treat_missing_exp_as_expired() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let token_str = jwt::encode(&header, &json!({ "iat": REFERENCE_TIME }), &alg).unwrap();
+
+    // By default a token without an 'exp' never expires
+    let _token_data = Verifier::create()
+        .build()
+        .unwrap()
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME)
+        .unwrap();
+
+    let verifier = Verifier::create()
+        .treat_missing_exp_as_expired()
+        .build()
+        .unwrap();
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME) {
+        Err(Error::TokenExpiredAt(at)) => assert_eq!(at, 0),
+        other => panic!("Expected TokenExpiredAt, got {:?}", other.err()),
+    }
+
+    let claims = json!({ "iat": REFERENCE_TIME, "exp": REFERENCE_TIME + 100 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let _token_data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME)
+        .unwrap();
+}
+
+#[test]
+fn require_and_ignore_conflict() {
+    match Verifier::create().require_exp().ignore_exp().build() {
+        Err(Error::InvalidInput(_)) => {}
+        other => panic!("Expected InvalidInput, got {:?}", other.err()),
+    }
+    match Verifier::create()
+        .treat_missing_exp_as_expired()
+        .ignore_exp()
+        .build()
+    {
+        Err(Error::InvalidInput(_)) => {}
+        other => panic!("Expected InvalidInput, got {:?}", other.err()),
+    }
+    match Verifier::create().ignore_nbf().require_nbf().build() {
+        Err(Error::InvalidInput(_)) => {}
+        other => panic!("Expected InvalidInput, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn numeric_date_forms() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create().build().unwrap();
+
+    // Integers and integral floats are both accepted
+    for exp in [json!(REFERENCE_TIME), json!(REFERENCE_TIME as f64)] {
+        let token_str = jwt::encode(&header, &json!({ "exp": exp }), &alg).unwrap();
+        let _token_data = verifier
+            .verify_for_time(&token_str, &alg, REFERENCE_TIME - 1)
+            .unwrap();
+        match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME) {
+            Err(Error::TokenExpiredAt(at)) => assert_eq!(at, REFERENCE_TIME),
+            other => panic!("Expected TokenExpiredAt, got {:?}", other.err()),
+        }
+    }
+
+    // Fractional, negative and non-numeric values are rejected
+    for claim in ["exp", "nbf", "iat"] {
+        for value in [
+            json!(REFERENCE_TIME as f64 - 0.5),
+            json!(-1),
+            json!("1575057015"),
+        ] {
+            let claims = json!({ claim: value });
+            let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+            match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME) {
+                Err(Error::MalformedToken(_)) => {}
+                other => panic!(
+                    "Expected MalformedToken for {claims}, got {:?}",
+                    other.err()
+                ),
+            }
+        }
+    }
+}
+
+#[test]
+fn accept_string_dates() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create().accept_string_dates().build().unwrap();
+
+    // 2019-11-29T19:50:15Z is REFERENCE_TIME
+    let claims = json!({
+        "iat": "2019-11-29T19:40:15Z",
+        "nbf": "2019-11-29T20:45:15.5+01:00",
+        "exp": "2019-11-29T19:50:15Z",
+    });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME - 1)
+        .unwrap();
+    assert_eq!(verifier.expires_in(&data, REFERENCE_TIME - 1), Some(1));
+    // Only the verifier knows that string dates are accepted
+    assert_eq!(data.expires_in(REFERENCE_TIME - 1), None);
+    let data = jwt::decode_unverified(&token_str).unwrap();
+    assert_eq!(data.expires_in(REFERENCE_TIME - 1), None);
+    let numeric_only = Verifier::create().build().unwrap();
+    assert_eq!(numeric_only.expires_in(&data, REFERENCE_TIME - 1), None);
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME) {
+        Err(Error::TokenExpiredAt(at)) => assert_eq!(at, REFERENCE_TIME),
+        other => panic!("Expected TokenExpiredAt, got {:?}", other.err()),
+    }
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME - 301) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!(
+            "Expected MalformedToken (before nbf), got {:?}",
+            other.err()
+        ),
+    }
+
+    // Numeric dates are still accepted, while invalid strings and string dates
+    // without the option are rejected
+    let token_str = jwt::encode(&header, &json!({ "exp": REFERENCE_TIME }), &alg).unwrap();
+    let _token_data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME - 1)
+        .unwrap();
+    let token_str = jwt::encode(&header, &json!({ "exp": "2019-11-29" }), &alg).unwrap();
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME - 1) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {:?}", other.err()),
+    }
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    match Verifier::create()
+        .build()
+        .unwrap()
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME - 1)
+    {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn verify_for_system_time() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "exp": REFERENCE_TIME });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    let before = UNIX_EPOCH + Duration::from_secs(REFERENCE_TIME - 1);
+    let _token_data = verifier
+        .verify_for_system_time(&token_str, &alg, before)
+        .unwrap();
+    let at = UNIX_EPOCH + Duration::from_secs(REFERENCE_TIME);
+    match verifier.verify_for_system_time(&token_str, &alg, at) {
+        Err(Error::TokenExpiredAt(exp)) => assert_eq!(exp, REFERENCE_TIME),
+        other => panic!("Expected TokenExpiredAt, got {:?}", other.err()),
+    }
+
+    let before_epoch = UNIX_EPOCH - Duration::from_secs(1);
+    match verifier.verify_for_system_time(&token_str, &alg, before_epoch) {
+        Err(Error::InvalidInput(_)) => {}
+        other => panic!("Expected InvalidInput, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn check_claim_consistency() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let permissive = Verifier::create()
+        .ignore_exp()
+        .ignore_nbf()
+        .ignore_iat()
+        .build()
+        .unwrap();
+    let verifier = Verifier::create()
+        .ignore_exp()
+        .ignore_nbf()
+        .ignore_iat()
+        .check_claim_consistency()
+        .build()
+        .unwrap();
+
+    let inconsistent = [
+        json!({ "exp": REFERENCE_TIME, "nbf": REFERENCE_TIME + 1 }),
+        json!({ "exp": REFERENCE_TIME, "iat": REFERENCE_TIME + 1 }),
+        json!({ "exp": REFERENCE_TIME, "nbf": REFERENCE_TIME - 1, "iat": REFERENCE_TIME + 1 }),
+    ];
+    for claims in inconsistent {
+        let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+        let _token_data = permissive
+            .verify_for_time(&token_str, &alg, REFERENCE_TIME)
+            .unwrap();
+        match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME) {
+            Err(Error::MalformedClaims(_)) => {}
+            other => panic!(
+                "Expected MalformedClaims for {claims}, got {:?}",
+                other.err()
+            ),
+        }
+    }
+
+    let consistent = [
+        json!({ "exp": REFERENCE_TIME, "nbf": REFERENCE_TIME, "iat": REFERENCE_TIME }),
+        json!({ "exp": REFERENCE_TIME }),
+        json!({ "nbf": REFERENCE_TIME, "iat": REFERENCE_TIME + 1 }),
+    ];
+    for claims in consistent {
+        let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+        let _token_data = verifier
+            .verify_for_time(&token_str, &alg, REFERENCE_TIME)
+            .unwrap();
+    }
+}
+
+#[test]
+fn verify_with_system_time() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+
+    let claims = json!({ "exp": get_time() + 100 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+    let verified = verifier.verify(&token_str, &alg).unwrap();
+    assert_eq!(verified, claims);
+
+    // Leeway and ignore options are also applied
+    let claims = json!({ "exp": get_time() - 10 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    match verifier.verify(&token_str, &alg) {
+        Err(Error::TokenExpiredAt(_)) => {}
+        other => panic!("Expected TokenExpiredAt, got {other:?}"),
+    }
+    let verifier = Verifier::create().leeway(60).build().unwrap();
+    let _claims = verifier.verify(&token_str, &alg).unwrap();
+    let verifier = Verifier::create().ignore_exp().build().unwrap();
+    let _claims = verifier.verify(&token_str, &alg).unwrap();
+}
+
+#[test]
+fn leeway_boundaries() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create().leeway(5).build().unwrap();
+
+    // 'exp' is extended into the future: accepted until exp + leeway
+    let token_str = jwt::encode(&header, &json!({ "exp": REFERENCE_TIME }), &alg).unwrap();
+    verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME + 4)
+        .unwrap();
+    match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME + 5) {
+        Err(Error::TokenExpiredAt(at)) => assert_eq!(at, REFERENCE_TIME),
+        other => panic!("Expected TokenExpiredAt, got {:?}", other.err()),
+    }
+
+    // 'nbf' and 'iat' are relaxed into the past: accepted from nbf/iat - leeway
+    for claim in ["nbf", "iat"] {
+        let claims = json!({ claim: REFERENCE_TIME });
+        let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+        verifier
+            .verify_for_time(&token_str, &alg, REFERENCE_TIME - 5)
+            .unwrap();
+        match verifier.verify_for_time(&token_str, &alg, REFERENCE_TIME - 6) {
+            Err(Error::MalformedToken(_)) => {}
+            other => panic!("Expected MalformedToken for {claim}, got {:?}", other.err()),
+        }
+    }
+}
+
+#[test]
+fn expires_in() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create().build().unwrap();
+
+    let token_str = jwt::encode(&header, &json!({ "exp": REFERENCE_TIME }), &alg).unwrap();
+    let data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME - 60)
+        .unwrap();
+    assert_eq!(data.expires_in(REFERENCE_TIME - 60), Some(60));
+    assert_eq!(data.expires_in(REFERENCE_TIME + 10), Some(-10));
+    assert_eq!(verifier.expires_in(&data, REFERENCE_TIME - 60), Some(60));
+
+    // The same for claims that were verified some other way
+    let data = jwt::TokenData {
+        header: json!({ "alg": "HS256" }),
+        claims: json!({ "exp": REFERENCE_TIME }),
+        _extensible: (),
+    };
+    assert_eq!(data.expires_in(REFERENCE_TIME - 60), Some(60));
+
+    let token_str = jwt::encode(&header, &json!({ "sub": "user" }), &alg).unwrap();
+    let data = verifier
+        .verify_for_time(&token_str, &alg, REFERENCE_TIME)
+        .unwrap();
+    assert_eq!(data.expires_in(REFERENCE_TIME), None);
+}
+
+#[test]
+fn validate_time() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({
+        "nbf": REFERENCE_TIME - 100,
+        "iat": REFERENCE_TIME - 100,
+        "exp": REFERENCE_TIME + 100,
+    });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create()
+        .leeway(5)
+        .max_token_age(Duration::from_secs(300))
+        .build()
+        .unwrap();
+    let data = verifier
+        .verify_for_time(&token, &alg, REFERENCE_TIME)
+        .unwrap();
+
+    verifier.validate_time(&data, REFERENCE_TIME).unwrap();
+    verifier.validate_time(&data, REFERENCE_TIME + 104).unwrap();
+    match verifier.validate_time(&data, REFERENCE_TIME + 105) {
+        Err(Error::TokenExpiredAt(exp)) => assert_eq!(exp, REFERENCE_TIME + 100),
+        other => panic!("Expected TokenExpiredAt, got {other:?}"),
+    }
+    match verifier.validate_time(&data, REFERENCE_TIME - 106) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {other:?}"),
+    }
+
+    let verifier = Verifier::create()
+        .max_token_age(Duration::from_secs(50))
+        .build()
+        .unwrap();
+    match verifier.validate_time(&data, REFERENCE_TIME) {
+        Err(Error::TokenTooOld(iat)) => assert_eq!(iat, REFERENCE_TIME - 100),
+        other => panic!("Expected TokenTooOld, got {other:?}"),
+    }
+}
+
+#[test]
+fn max_auth_age() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create()
+        .leeway(5)
+        .max_auth_age(Duration::from_secs(3600))
+        .build()
+        .unwrap();
+    let verify = |claims| {
+        let token = jwt::encode(&header, &claims, &alg).unwrap();
+        verifier.verify_for_time(&token, &alg, REFERENCE_TIME)
+    };
+
+    verify(json!({ "auth_time": REFERENCE_TIME - 3605 })).unwrap();
+    verify(json!({ "auth_time": REFERENCE_TIME + 5 })).unwrap();
+    // Without an 'auth_time' there's nothing to check
+    verify(json!({})).unwrap();
+
+    match verify(json!({ "auth_time": REFERENCE_TIME - 3606 })) {
+        Err(Error::TokenTooOld(auth_time)) => assert_eq!(auth_time, REFERENCE_TIME - 3606),
+        other => panic!("Expected TokenTooOld, got {:?}", other.err()),
+    }
+    match verify(json!({ "auth_time": REFERENCE_TIME + 6 })) {
+        Err(err @ Error::MalformedToken(_)) => {
+            assert_eq!(err.category(), jwt::error::ErrorCategory::NotYetValid)
+        }
+        other => panic!("Expected MalformedToken, got {:?}", other.err()),
+    }
+    match verify(json!({ "auth_time": "yesterday" })) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn past_time_claim() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create()
+        .past_time_claim("updated_at")
+        .build()
+        .unwrap();
+
+    let token = jwt::encode(&header, &json!({ "updated_at": 0 }), &alg).unwrap();
+    verifier
+        .verify_for_time(&token, &alg, REFERENCE_TIME)
+        .unwrap();
+
+    let token = jwt::encode(&header, &json!({ "updated_at": REFERENCE_TIME + 1 }), &alg).unwrap();
+    match verifier.verify_for_time(&token, &alg, REFERENCE_TIME) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn validity_window() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({
+        "iat": REFERENCE_TIME - 100,
+        "auth_time": REFERENCE_TIME - 100,
+        "nbf": REFERENCE_TIME - 50,
+        "exp": REFERENCE_TIME + 1000,
+    });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifiers = [
+        (
+            Verifier::create().build().unwrap(),
+            (Some(REFERENCE_TIME - 50), Some(REFERENCE_TIME + 999)),
+        ),
+        (
+            Verifier::create()
+                .nbf_leeway(10)
+                .exp_leeway(20)
+                .build()
+                .unwrap(),
+            (Some(REFERENCE_TIME - 60), Some(REFERENCE_TIME + 1019)),
+        ),
+        (
+            Verifier::create()
+                .max_token_age(Duration::from_secs(500))
+                .build()
+                .unwrap(),
+            (Some(REFERENCE_TIME - 50), Some(REFERENCE_TIME + 400)),
+        ),
+        (
+            // Adding the iat leeway to a maximum age mustn't overflow
+            Verifier::create()
+                .max_token_age(Duration::MAX)
+                .max_auth_age(Duration::MAX)
+                .iat_leeway(5)
+                .build()
+                .unwrap(),
+            (Some(REFERENCE_TIME - 50), Some(REFERENCE_TIME + 999)),
+        ),
+        (
+            Verifier::create()
+                .ignore_exp()
+                .ignore_nbf()
+                .build()
+                .unwrap(),
+            (Some(REFERENCE_TIME - 100), None),
+        ),
+    ];
+    for (verifier, window) in verifiers {
+        let (verified_window, data) = verifier
+            .verify_for_time_with_validity(&token_str, &alg, REFERENCE_TIME)
+            .unwrap();
+        assert_eq!(verified_window, window);
+        assert_eq!(data.claims, claims);
+
+        // The bounds are the first and last times the token is accepted
+        let (not_before, not_after) = window;
+        let not_before = not_before.unwrap();
+        assert!(verifier.validate_time(&data, not_before).is_ok());
+        assert!(verifier.validate_time(&data, not_before - 1).is_err());
+        if let Some(not_after) = not_after {
+            assert!(verifier.validate_time(&data, not_after).is_ok());
+            assert!(verifier.validate_time(&data, not_after + 1).is_err());
+        }
+    }
+
+    // Without time claims the window is unbounded
+    let token_str = jwt::encode(&header, &json!({ "sub": "user" }), &alg).unwrap();
+    let (window, _data) = Verifier::create()
+        .build()
+        .unwrap()
+        .verify_for_time_with_validity(&token_str, &alg, REFERENCE_TIME)
+        .unwrap();
+    assert_eq!(window, (None, None));
+}
@@ -1,3 +1,4 @@
+use serde::Deserialize;
 use serde_json::json;
 use serde_json::value::Value;
 
@@ -5,7 +6,7 @@ use serde_json::value::Value;
 use regex::Regex;
 
 use jsonwebtokens as jwt;
-use jwt::{Algorithm, AlgorithmID, Verifier};
+use jwt::{error::Error, Algorithm, AlgorithmID, JwkSet, Verifier};
 
 mod common;
 use common::get_time;
@@ -30,7 +31,7 @@ fn string_equals() {
 }
 
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "InvalidClaim")]
 fn string_equals_failure() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -50,7 +51,7 @@ fn string_equals_failure() {
 }
 
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "MissingRequiredClaim")]
 fn string_equals_missing() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -90,7 +91,7 @@ fn string_matches() {
 
 #[cfg(feature = "matching")]
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "InvalidClaim")]
 fn string_matches_failure() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -111,7 +112,7 @@ fn string_matches_failure() {
 
 #[cfg(feature = "matching")]
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "MissingRequiredClaim")]
 fn string_matches_missing() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -157,7 +158,7 @@ fn closure_verify_u64_claim() {
 }
 
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "InvalidClaim")]
 fn closure_fail_verify_u64_claim() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -215,7 +216,7 @@ fn non_string_iss() {
 }
 
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "MalformedClaims")]
 fn non_string_or_array_aud() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -241,7 +242,7 @@ fn string_or_array_aud() {
 }
 
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "MalformedClaims")]
 fn non_string_array_aud() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -273,7 +274,7 @@ fn iss_equal() {
 }
 
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "InvalidIssuer")]
 fn iss_not_equal() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -283,6 +284,44 @@ fn iss_not_equal() {
     let _claims: Value = verifier.verify(token_str, &alg).unwrap();
 }
 
+#[test]
+#[should_panic(expected = "InvalidIssuer")]
+fn iss_missing() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "aud": "ACME" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().issuer("ACME").build().unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
+#[test]
+fn iss_one_of() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "iss": "https://tenant2.example.com" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create()
+        .issuer_one_of(&["https://tenant1.example.com", "https://tenant2.example.com"])
+        .build()
+        .unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "InvalidIssuer")]
+fn iss_not_one_of() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "iss": "https://tenant3.example.com" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create()
+        .issuer_one_of(&["https://tenant1.example.com", "https://tenant2.example.com"])
+        .build()
+        .unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
 #[test]
 fn aud_equal() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
@@ -304,7 +343,40 @@ fn aud_equal_with_array() {
 }
 
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "InvalidAudience")]
+fn aud_array_not_containing() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "aud": ["ACME2", "ACME3"] });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().audience("ACME").build().unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "InvalidAudience")]
+fn aud_empty_array() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "aud": [] });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().audience("ACME").build().unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "MalformedClaims")]
+fn aud_wrong_type_with_audience() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "aud": { "name": "ACME" } });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().audience("ACME").build().unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "InvalidAudience")]
 fn aud_not_equal() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -314,6 +386,108 @@ fn aud_not_equal() {
     let _claims: Value = verifier.verify(token_str, &alg).unwrap();
 }
 
+#[test]
+#[should_panic(expected = "InvalidAudience")]
+fn aud_missing() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "iss": "ACME" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().audience("ACME").build().unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
+#[test]
+fn aud_missing_for_each_audience_check() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let token_str = jwt::encode(&header, &json!({ "sub": "user" }), &alg).unwrap();
+
+    let verifiers = [
+        Verifier::create().audience("ACME").build().unwrap(),
+        Verifier::create()
+            .audience_one_of(&["ACME", "ACME2"])
+            .build()
+            .unwrap(),
+        Verifier::create()
+            .require_all_audiences(&["ACME", "ACME2"])
+            .build()
+            .unwrap(),
+    ];
+    for verifier in verifiers {
+        match verifier.verify(&token_str, &alg) {
+            Err(Error::InvalidAudience()) => {}
+            other => panic!("Expected InvalidAudience, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn aud_one_of() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create()
+        .audience_one_of(&["ACME", "ACME2"])
+        .build()
+        .unwrap();
+
+    let claims = json!({ "aud": "ACME2" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+
+    let claims = json!({ "aud": ["ACMEv3", "ACME2"] });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
+#[test]
+fn aud_all_of() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create()
+        .require_all_audiences(&["ACME", "ACME2"])
+        .build()
+        .unwrap();
+
+    let claims = json!({ "aud": ["ACME2", "ACMEv3", "ACME"] });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+
+    for aud in [
+        json!(["ACME", "ACMEv3"]),
+        json!("ACME"),
+        json!(["ACME", "ACME"]),
+    ] {
+        let token_str = jwt::encode(&header, &json!({ "aud": aud }), &alg).unwrap();
+        match verifier.verify(token_str, &alg) {
+            Err(Error::InvalidAudience()) => {}
+            other => panic!("Expected InvalidAudience, got {other:?}"),
+        }
+    }
+
+    // A single required audience may also match a string 'aud'
+    let verifier = Verifier::create()
+        .require_all_audiences(&["ACME"])
+        .build()
+        .unwrap();
+    let token_str = jwt::encode(&header, &json!({ "aud": "ACME" }), &alg).unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "InvalidAudience")]
+fn aud_not_one_of() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "aud": ["ACMEv2", "ACMEv3"] });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create()
+        .audience_one_of(&["ACME", "ACME2"])
+        .build()
+        .unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
 #[test]
 fn sub_equal() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
@@ -325,7 +499,7 @@ fn sub_equal() {
 }
 
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "InvalidClaim")]
 fn sub_not_equal() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -349,7 +523,7 @@ fn equals_one_of() {
 }
 
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "InvalidClaim")]
 fn equals_one_of_failure() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -401,7 +575,7 @@ fn matches_one_of() {
 
 #[cfg(feature = "matching")]
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "InvalidClaim")]
 fn matches_one_of_failure() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -422,7 +596,7 @@ fn matches_one_of_failure() {
 
 #[cfg(feature = "matching")]
 #[test]
-#[should_panic(expected = "MalformedToken")]
+#[should_panic(expected = "MissingRequiredClaim")]
 fn matches_one_of_missing() {
     let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
     let header = json!({ "alg": "HS256" });
@@ -467,3 +641,800 @@ fn string_array_contains() {
         .unwrap();
     let _claims: Value = verifier.verify(token_str, &alg).unwrap();
 }
+
+#[test]
+fn claim_equals() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "sub": "user-123", "admin": true, "level": 3 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create()
+        .claim_equals("sub", "user-123")
+        .claim_equals("admin", true)
+        .claim_equals("level", 3)
+        .build()
+        .unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+}
+
+#[test]
+fn claim_equals_failure() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "level": "3" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().claim_equals("level", 3).build().unwrap();
+    match verifier.verify(token_str, &alg) {
+        Err(Error::InvalidClaim(claim)) => assert_eq!(claim, "level"),
+        other => panic!("Expected InvalidClaim, got {other:?}"),
+    }
+}
+
+#[test]
+fn claim_equals_missing() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "iss": "ACME" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create()
+        .claim_equals("sub", "user-123")
+        .build()
+        .unwrap();
+    match verifier.verify(token_str, &alg) {
+        Err(Error::MissingRequiredClaim(claim)) => assert_eq!(claim, "sub"),
+        other => panic!("Expected MissingRequiredClaim, got {other:?}"),
+    }
+}
+
+#[test]
+fn claim_matches() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "scope": "read write" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let has_scope = |scope: &'static str| {
+        move |v: &Value| v.as_str().is_some_and(|s| s.split(' ').any(|s| s == scope))
+    };
+
+    let verifier = Verifier::create()
+        .claim_matches("scope", has_scope("write"))
+        .build()
+        .unwrap();
+    let _claims: Value = verifier.verify(&token_str, &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .claim_matches("scope", has_scope("admin"))
+        .build()
+        .unwrap();
+    match verifier.verify(&token_str, &alg) {
+        Err(Error::InvalidClaim(claim)) => assert_eq!(claim, "scope"),
+        other => panic!("Expected InvalidClaim, got {other:?}"),
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct MyClaims {
+    sub: String,
+    exp: u64,
+    admin: bool,
+}
+
+#[test]
+fn verify_typed() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256", "kid": "kid1234" });
+    let exp = get_time() + 10000;
+    let claims = json!({ "sub": "user-123", "exp": exp, "admin": true });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+
+    let data = verifier
+        .verify_typed_for_time::<MyClaims>(&token_str, &alg, get_time())
+        .unwrap();
+    assert_eq!(data.header.get("kid").unwrap(), "kid1234");
+    assert_eq!(
+        data.claims,
+        MyClaims {
+            sub: "user-123".to_owned(),
+            exp,
+            admin: true,
+        }
+    );
+
+    let claims: MyClaims = verifier.verify_typed(&token_str, &alg).unwrap();
+    assert_eq!(claims.sub, "user-123");
+}
+
+#[test]
+fn verify_typed_malformed_claims() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "sub": "user-123", "exp": get_time() + 10000 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create().build().unwrap();
+
+    match verifier.verify_typed::<MyClaims>(&token_str, &alg) {
+        Err(err @ Error::MalformedClaims(_)) => {
+            assert!(err.to_string().contains("missing field `admin`"), "{err}");
+        }
+        other => panic!("Expected MalformedClaims, got {other:?}"),
+    }
+}
+
+#[test]
+fn insecure_disable_signature() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let other_alg = Algorithm::new_hmac(AlgorithmID::HS256, "other").unwrap();
+    let claims = json!({ "iss": "internal", "exp": get_time() + 10000 });
+    let token = jwt::encode(&json!({}), &claims, &other_alg).unwrap();
+
+    let verifier = Verifier::create()
+        .issuer("internal")
+        .insecure_disable_signature()
+        .build()
+        .unwrap();
+    let verified: Value = verifier.verify_insecure(&token).unwrap();
+    assert_eq!(verified, claims);
+
+    // Claims are still validated
+    let wrong_issuer = jwt::encode(&json!({}), &json!({ "iss": "external" }), &alg).unwrap();
+    match verifier.verify_insecure(&wrong_issuer) {
+        Err(Error::InvalidIssuer()) => {}
+        other => panic!("Expected InvalidIssuer, got {other:?}"),
+    }
+    let expired = jwt::encode(&json!({}), &json!({ "exp": get_time() - 10 }), &alg).unwrap();
+    let verifier = Verifier::create()
+        .insecure_disable_signature()
+        .build()
+        .unwrap();
+    match verifier.verify_insecure(&expired) {
+        Err(Error::TokenExpiredAt(_)) => {}
+        other => panic!("Expected TokenExpiredAt, got {other:?}"),
+    }
+
+    // A key can't be given to such a Verifier
+    match verifier.verify(&token, &alg) {
+        Err(Error::InvalidInput(_)) => {}
+        other => panic!("Expected InvalidInput, got {other:?}"),
+    }
+    match verifier.verify_with_keys(&token, &[&alg]) {
+        Err(Error::InvalidInput(_)) => {}
+        other => panic!("Expected InvalidInput, got {other:?}"),
+    }
+
+    // Nor a key set, whether or not the token has a 'kid' to select a key
+    let jwks =
+        json!({ "keys": [{ "kty": "oct", "alg": "HS256", "kid": "key0", "k": "c2VjcmV0" }] });
+    let jwks = JwkSet::from_value(&jwks).unwrap();
+    let verifier = Verifier::create()
+        .insecure_disable_signature()
+        .jwks_without_kid()
+        .build()
+        .unwrap();
+    for header in [
+        json!({}),
+        json!({ "alg": "HS256" }),
+        json!({ "kid": "key1" }),
+    ] {
+        let token = jwt::encode(&header, &claims, &alg).unwrap();
+        match verifier.verify_with_jwks(&token, &jwks) {
+            Err(Error::InvalidInput(_)) => {}
+            other => panic!("Expected InvalidInput, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn verify_insecure_requires_opt_in() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let token = jwt::encode(&json!({}), &json!({}), &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify_insecure(&token) {
+        Err(Error::InvalidInput(_)) => {}
+        other => panic!("Expected InvalidInput, got {other:?}"),
+    }
+}
+
+#[test]
+fn require_claims() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create()
+        .require_claims(&["sub", "scope", "tenant"])
+        .claim_equals("tenant", "acme")
+        .build()
+        .unwrap();
+
+    let claims = json!({ "sub": "user", "scope": null, "tenant": "acme" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let _claims: Value = verifier.verify(token_str, &alg).unwrap();
+
+    // The first missing claim is reported
+    let claims = json!({ "sub": "user" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    match verifier.verify(token_str, &alg) {
+        Err(Error::MissingRequiredClaim(claim)) => assert_eq!(claim, "scope"),
+        other => panic!("Expected MissingRequiredClaim, got {other:?}"),
+    }
+
+    // Values may still be checked separately
+    let claims = json!({ "sub": "user", "scope": "read", "tenant": "other" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    match verifier.verify(token_str, &alg) {
+        Err(Error::InvalidClaim(claim)) => assert_eq!(claim, "tenant"),
+        other => panic!("Expected InvalidClaim, got {other:?}"),
+    }
+}
+
+#[test]
+fn strict_json_duplicate_members() {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let sign_raw = |header: &str, claims: &str| {
+        let message = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header),
+            URL_SAFE_NO_PAD.encode(claims)
+        );
+        let signature = alg.sign(&message).unwrap();
+        format!("{message}.{signature}")
+    };
+    let lenient = Verifier::create().build().unwrap();
+    let strict = Verifier::create().strict_json().build().unwrap();
+
+    // The same name in different objects is fine
+    let token = sign_raw(r#"{"alg":"HS256"}"#, r#"{"sub":"a","obj":{"sub":"b"}}"#);
+    let _claims: Value = strict.verify(&token, &alg).unwrap();
+
+    for (header, claims) in [
+        (r#"{"alg":"HS256","alg":"HS256"}"#, r#"{"sub":"a"}"#),
+        (r#"{"alg":"HS256"}"#, r#"{"sub":"a","sub":"b"}"#),
+        (r#"{"alg":"HS256"}"#, r#"{"obj":[{"a":1,"a":2}]}"#),
+        // Names are compared after unescaping
+        (r#"{"alg":"HS256"}"#, r#"{"sub":"a","su\u0062":"b"}"#),
+    ] {
+        let token = sign_raw(header, claims);
+        let _claims: Value = lenient.verify(&token, &alg).unwrap();
+        match strict.verify(&token, &alg) {
+            Err(Error::MalformedToken(_)) => {}
+            other => panic!("Expected MalformedToken for {header} {claims}, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn max_token_bytes() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "data": "x".repeat(1000) });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .max_token_bytes(token_str.len())
+        .build()
+        .unwrap();
+    let _claims: Value = verifier.verify(&token_str, &alg).unwrap();
+
+    let verifier = Verifier::create()
+        .max_token_bytes(token_str.len() - 1)
+        .build()
+        .unwrap();
+    match verifier.verify(&token_str, &alg) {
+        Err(Error::TokenTooLarge(size)) => assert_eq!(size, token_str.len()),
+        other => panic!("Expected TokenTooLarge, got {other:?}"),
+    }
+    match verifier.verify_bytes(token_str.as_bytes(), &alg) {
+        Err(Error::TokenTooLarge(_)) => {}
+        other => panic!("Expected TokenTooLarge, got {other:?}"),
+    }
+
+    // The limit is checked before anything is decoded
+    let garbage = "!".repeat(2 * 1024 * 1024);
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify(&garbage, &alg) {
+        Err(Error::TokenTooLarge(_)) => {}
+        other => panic!("Expected TokenTooLarge, got {other:?}"),
+    }
+}
+
+#[test]
+fn default_builder() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let claims = json!({ "sub": "user" });
+    let token_str = jwt::encode(&json!({ "alg": "HS256" }), &claims, &alg).unwrap();
+
+    // A default builder has the same (non-zero) limits as Verifier::create()
+    let builder = jwt::VerifierBuilder::default();
+    assert_eq!(builder, Verifier::create());
+    let verifier = builder.build().unwrap();
+    let verified: Value = verifier.verify(&token_str, &alg).unwrap();
+    assert_eq!(verified, claims);
+}
+
+#[test]
+fn whitespace_and_bearer_prefix() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "sub": "user" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    let bearer_verifier = Verifier::create().strip_bearer_prefix().build().unwrap();
+    for verifier in [&verifier, &bearer_verifier] {
+        let verified: Value = verifier.verify(&token_str, &alg).unwrap();
+        assert_eq!(verified, claims);
+        let verified: Value = verifier.verify(format!(" {token_str}\n"), &alg).unwrap();
+        assert_eq!(verified, claims);
+    }
+
+    for token in [
+        format!("Bearer {token_str}"),
+        format!(" bearer  {token_str}\r\n"),
+    ] {
+        let verified: Value = bearer_verifier.verify(&token, &alg).unwrap();
+        assert_eq!(verified, claims);
+        match verifier.verify(&token, &alg) {
+            Err(Error::MalformedToken(_)) => {}
+            other => panic!("Expected MalformedToken, got {other:?}"),
+        }
+    }
+
+    // Only a prefix is stripped
+    match bearer_verifier.verify(format!("Bearer Bearer {token_str}"), &alg) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {other:?}"),
+    }
+}
+
+#[test]
+fn clone_and_compare_verifiers() {
+    let mut template = Verifier::create();
+    template.issuer("http://some-auth-service.com").leeway(5);
+    let base = template.build().unwrap();
+
+    let mut admin = template.clone();
+    admin.claim_equals("role", "admin");
+    let admin = admin.build().unwrap();
+
+    assert_eq!(base, template.build().unwrap());
+    assert_eq!(base.clone(), base);
+    assert_ne!(base, admin);
+
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "iss": "http://some-auth-service.com", "role": "user" });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let _claims: Value = base.verify(&token_str, &alg).unwrap();
+    match admin.verify(&token_str, &alg) {
+        Err(Error::InvalidClaim(claim)) => assert_eq!(claim, "role"),
+        other => panic!("Expected InvalidClaim, got {other:?}"),
+    }
+}
+
+#[test]
+fn on_validation_callback() {
+    use jwt::ValidationStep;
+    use std::sync::{Arc, Mutex};
+
+    let steps = Arc::new(Mutex::new(Vec::new()));
+    let observed = steps.clone();
+    let verifier = Verifier::create()
+        .issuer("http://some-auth-service.com")
+        .audience("application_id")
+        .on_validation(move |step, outcome| {
+            let outcome = outcome.map_err(|e| e.to_string());
+            observed.lock().unwrap().push((step, outcome));
+        })
+        .build()
+        .unwrap();
+
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({
+        "iss": "http://some-auth-service.com",
+        "aud": "application_id",
+        "exp": get_time() + 10000,
+    });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    let _claims: Value = verifier.verify(&token_str, &alg).unwrap();
+    {
+        let mut steps = steps.lock().unwrap();
+        // The order in which claim values are checked isn't defined
+        steps[3..].sort_by_key(|(step, _)| format!("{step:?}"));
+        assert_eq!(
+            *steps,
+            vec![
+                (ValidationStep::Signature, Ok(())),
+                (ValidationStep::NotBefore, Ok(())),
+                (ValidationStep::Expiry, Ok(())),
+                (ValidationStep::Audience, Ok(())),
+                (ValidationStep::Issuer, Ok(())),
+            ]
+        );
+        steps.clear();
+    }
+
+    // The callback observes a failure but the error is still returned
+    let claims = json!({ "iss": "http://some-auth-service.com", "exp": get_time() - 10000 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    match verifier.verify(&token_str, &alg) {
+        Err(Error::TokenExpiredAt(_)) => {}
+        other => panic!("Expected TokenExpiredAt, got {other:?}"),
+    }
+    {
+        let steps = steps.lock().unwrap();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0], (ValidationStep::Signature, Ok(())));
+        assert_eq!(steps[2].0, ValidationStep::Expiry);
+        assert!(steps[2].1.is_err());
+    }
+}
+
+#[test]
+fn jti_replay_check() {
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+
+    let seen = Arc::new(Mutex::new(HashSet::new()));
+    let recorder = seen.clone();
+    let verifier = Verifier::create()
+        .jti_seen(move |jti| !recorder.lock().unwrap().insert(jti.to_owned()))
+        .build()
+        .unwrap();
+
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let token_str = jwt::encode(&header, &json!({ "jti": "abc123" }), &alg).unwrap();
+
+    let data = verifier
+        .verify_for_time(&token_str, &alg, get_time())
+        .unwrap();
+    assert_eq!(data.jti(), Some("abc123"));
+    match verifier.verify(&token_str, &alg) {
+        Err(Error::TokenReplayed(jti)) => assert_eq!(jti, "abc123"),
+        other => panic!("Expected TokenReplayed, got {other:?}"),
+    }
+
+    let token_str = jwt::encode(&header, &json!({ "sub": "user" }), &alg).unwrap();
+    match verifier.verify(&token_str, &alg) {
+        Err(Error::MissingRequiredClaim(claim)) => assert_eq!(claim, "jti"),
+        other => panic!("Expected MissingRequiredClaim, got {other:?}"),
+    }
+
+    // Tokens that fail other checks aren't reported
+    let claims = json!({ "jti": "expired", "exp": get_time() - 10000 });
+    let token_str = jwt::encode(&header, &claims, &alg).unwrap();
+    assert!(verifier.verify(&token_str, &alg).is_err());
+    assert!(!seen.lock().unwrap().contains("expired"));
+}
+
+#[test]
+fn prepared_verifier() {
+    let mut alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    alg.set_kid("key1");
+    let verifier = Verifier::create()
+        .audience("application_id")
+        .build()
+        .unwrap();
+    let mut prepared = jwt::PreparedVerifier::new(verifier.clone(), alg);
+    let alg = prepared.algorithm();
+    assert_eq!(prepared.verifier(), &verifier);
+
+    let header = json!({ "alg": "HS256", "kid": "key1" });
+    let claims = json!({ "aud": "application_id", "exp": get_time() + 10000 });
+    let token_str = jwt::encode(&header, &claims, alg).unwrap();
+    let other_claims = json!({ "aud": "application_id", "sub": "other" });
+    let other_str = jwt::encode(&header, &other_claims, alg).unwrap();
+    let typ_str = jwt::encode(
+        &json!({ "alg": "HS256", "kid": "key1", "typ": "JWT" }),
+        &claims,
+        alg,
+    )
+    .unwrap();
+    let expected = verifier
+        .verify_for_time(&token_str, alg, get_time())
+        .unwrap();
+    let wrong_alg_str =
+        jwt::encode(&json!({ "alg": "HS256", "kid": "key2" }), &claims, alg).unwrap();
+    let tampered_str = format!(
+        "{}.{}",
+        other_str.rsplit_once('.').unwrap().0,
+        token_str.rsplit_once('.').unwrap().1
+    );
+
+    // Consecutive tokens with the same header, and then a different header
+    for token in [&token_str, &token_str] {
+        let data = prepared.verify_for_time(token, get_time()).unwrap();
+        assert_eq!(data.header, expected.header);
+        assert_eq!(data.claims, expected.claims);
+    }
+    assert_eq!(prepared.verify(&other_str).unwrap(), other_claims);
+    let data = prepared.verify_for_time(&typ_str, get_time()).unwrap();
+    assert_eq!(data.header.get("typ"), Some(&json!("JWT")));
+
+    // A header that fails its checks isn't remembered
+    for _ in 0..2 {
+        match prepared.verify_for_time(&wrong_alg_str, get_time()) {
+            Err(Error::MalformedToken(_)) => {}
+            other => panic!("Expected MalformedToken, got {:?}", other.err()),
+        }
+    }
+    match prepared.verify_for_time(&tampered_str, get_time()) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {:?}", other.err()),
+    }
+    match prepared.verify_for_time(&token_str, get_time() + 20000) {
+        Err(Error::TokenExpiredAt(_)) => {}
+        other => panic!("Expected TokenExpiredAt, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn issuer_keys() {
+    let alg_a = Algorithm::new_hmac(AlgorithmID::HS256, "secret-a").unwrap();
+    let alg_b = Algorithm::new_hmac(AlgorithmID::HS256, "secret-b").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let token_a = jwt::encode(&header, &json!({ "iss": "iss-a", "n": 1 }), &alg_a).unwrap();
+    let token_b = jwt::encode(&header, &json!({ "iss": "iss-b", "n": 2 }), &alg_b).unwrap();
+    let forged = jwt::encode(&header, &json!({ "iss": "iss-a" }), &alg_b).unwrap();
+    let unknown = jwt::encode(&header, &json!({ "iss": "iss-c" }), &alg_a).unwrap();
+    let no_iss = jwt::encode(&header, &json!({ "n": 3 }), &alg_a).unwrap();
+
+    let verifier = Verifier::create()
+        .issuer_key("iss-a", alg_a)
+        .issuer_key("iss-b", alg_b)
+        .build()
+        .unwrap();
+    assert_eq!(verifier.verify_with_issuer_keys(&token_a).unwrap()["n"], 1);
+    assert_eq!(verifier.verify_with_issuer_keys(&token_b).unwrap()["n"], 2);
+
+    // A token claiming to be from one issuer but signed with another's key
+    match verifier.verify_with_issuer_keys(&forged) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+    for token in [&unknown, &no_iss] {
+        match verifier.verify_with_issuer_keys(token) {
+            Err(Error::InvalidIssuer()) => {}
+            other => panic!("Expected InvalidIssuer, got {other:?}"),
+        }
+    }
+
+    // Verifiers only compare as equal if their keys are shared
+    assert_eq!(verifier, verifier.clone());
+}
+
+#[test]
+fn verify_collect_errors() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({
+        "aud": "other_application",
+        "iss": 42,
+        "exp": get_time() - 100,
+        "scope": "write",
+    });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create()
+        .audience("application_id")
+        .claim_equals("scope", "read")
+        .jti_seen(|_| panic!("Only checked for otherwise valid tokens"))
+        .build()
+        .unwrap();
+
+    // The first failure is still reported by itself via the other APIs
+    match verifier.verify_for_time(&token, &alg, get_time()) {
+        Err(Error::TokenExpiredAt(_)) => {}
+        other => panic!("Expected TokenExpiredAt, got {:?}", other.err()),
+    }
+    match verifier.verify_collect_errors(&token, &alg, get_time()) {
+        Err(errors) => assert!(
+            matches!(
+                errors.as_slice(),
+                [
+                    Error::TokenExpiredAt(_),
+                    Error::MalformedToken(_), // 'iss' isn't a string
+                    Error::InvalidAudience(),
+                    Error::InvalidClaim(claim),
+                ] if claim == "scope"
+            ),
+            "Unexpected errors {errors:?}"
+        ),
+        Ok(_) => panic!("Expected errors"),
+    }
+
+    // A bad signature is the only error reported, since the claims can't be trusted
+    let other_alg = Algorithm::new_hmac(AlgorithmID::HS256, "other secret").unwrap();
+    match verifier.verify_collect_errors(&token, &other_alg, get_time()) {
+        Err(errors) => assert!(matches!(errors.as_slice(), [Error::InvalidSignature()])),
+        Ok(_) => panic!("Expected errors"),
+    }
+
+    let claims = json!({ "aud": "application_id", "scope": "read", "jti": "id" });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+    let verifier = Verifier::create()
+        .audience("application_id")
+        .claim_equals("scope", "read")
+        .build()
+        .unwrap();
+    let data = verifier
+        .verify_collect_errors(&token, &alg, get_time())
+        .unwrap();
+    assert_eq!(data.claims, claims);
+}
+
+#[test]
+fn deny_insecure() {
+    Verifier::create()
+        .deny_insecure()
+        .require_exp()
+        .build()
+        .unwrap();
+    Verifier::create()
+        .deny_insecure()
+        .treat_missing_exp_as_expired()
+        .build()
+        .unwrap();
+    Verifier::create()
+        .deny_insecure()
+        .max_token_age(std::time::Duration::from_secs(300))
+        .build()
+        .unwrap();
+
+    // Insecure options are still allowed without deny_insecure()
+    Verifier::create()
+        .ignore_exp()
+        .insecure_disable_signature()
+        .build()
+        .unwrap();
+
+    // By default, tokens without an 'exp' would be accepted forever
+    match Verifier::create().deny_insecure().build() {
+        Err(Error::InvalidInput(_)) => {}
+        other => panic!("Expected InvalidInput, got {other:?}"),
+    }
+    match Verifier::create()
+        .deny_insecure()
+        .require_exp()
+        .insecure_disable_signature()
+        .build()
+    {
+        Err(err @ Error::InvalidInput(_)) => assert!(err.to_string().contains("signature")),
+        other => panic!("Expected InvalidInput, got {other:?}"),
+    }
+    match Verifier::create()
+        .deny_insecure()
+        .max_token_age(std::time::Duration::from_secs(300))
+        .ignore_exp()
+        .build()
+    {
+        Err(err @ Error::InvalidInput(_)) => assert!(err.to_string().contains("'exp'")),
+        other => panic!("Expected InvalidInput, got {other:?}"),
+    }
+}
+
+#[cfg(feature = "claims-schema")]
+#[test]
+fn claims_schema() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let verifier = Verifier::create()
+        .claims_schema(json!({
+            "type": "object",
+            "required": ["name", "roles"],
+            "properties": {
+                "name": { "type": "string" },
+                "roles": { "type": "array", "items": { "enum": ["admin", "user"] } },
+            },
+        }))
+        .build()
+        .unwrap();
+
+    let claims = json!({ "name": "user", "roles": ["user"] });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+    verifier.verify(&token, &alg).unwrap();
+
+    let claims = json!({ "name": 42, "roles": ["user", "root"] });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::SchemaValidation(failures)) => assert_eq!(
+            failures,
+            vec![
+                r#"/name: 42 is not of type "string""#,
+                r#"/roles/1: "root" is not one of "admin" or "user""#,
+            ]
+        ),
+        other => panic!("Expected SchemaValidation, got {:?}", other.err()),
+    }
+
+    // Failures for the claims object itself have no path, and the paths of
+    // other claims are escaped JSON Pointers
+    let verifier = Verifier::create()
+        .claims_schema(json!({
+            "required": ["name"],
+            "properties": { "a/b~c": { "format": "email" } },
+        }))
+        .build()
+        .unwrap();
+    let token = jwt::encode(&header, &json!({ "a/b~c": "user" }), &alg).unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::SchemaValidation(failures)) => assert_eq!(
+            failures,
+            vec![
+                r#""name" is a required property"#,
+                r#"/a~1b~0c: "user" is not a "email""#,
+            ]
+        ),
+        other => panic!("Expected SchemaValidation, got {:?}", other.err()),
+    }
+
+    // An invalid schema is rejected when building the verifier
+    match Verifier::create()
+        .claims_schema(json!({ "type": "no-such-type" }))
+        .build()
+    {
+        Err(Error::InvalidInput(_)) => {}
+        other => panic!("Expected InvalidInput, got {other:?}"),
+    }
+}
+
+#[test]
+fn uniform_timing() {
+    use jwt::{PreparedVerifier, ValidationStep};
+    use std::sync::{Arc, Mutex};
+
+    let steps = Arc::new(Mutex::new(Vec::new()));
+    let observed = steps.clone();
+    let mut builder = Verifier::create();
+    builder
+        .audience("application_id")
+        .jti_seen(|_| panic!("Only checked for otherwise valid tokens"))
+        .on_validation(move |step, _| observed.lock().unwrap().push(step));
+    let early = builder.build().unwrap();
+    let uniform = builder.uniform_timing().build().unwrap();
+
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let other_alg = Algorithm::new_hmac(AlgorithmID::HS256, "other secret").unwrap();
+    let header = json!({ "alg": "HS256" });
+    let claims = json!({ "aud": "other_application", "exp": get_time() - 100, "jti": "1" });
+    let token = jwt::encode(&header, &claims, &alg).unwrap();
+
+    // The same (first) error is returned either way, but the claims are still
+    // checked when the signature is invalid
+    for verifier in [&early, &uniform] {
+        steps.lock().unwrap().clear();
+        match verifier.verify(&token, &other_alg) {
+            Err(Error::InvalidSignature()) => {}
+            other => panic!("Expected InvalidSignature, got {other:?}"),
+        }
+        let observed = steps.lock().unwrap().clone();
+        if *verifier == uniform {
+            assert_eq!(
+                observed,
+                vec![
+                    ValidationStep::Signature,
+                    ValidationStep::NotBefore,
+                    ValidationStep::Expiry,
+                    ValidationStep::Audience,
+                ]
+            );
+        } else {
+            assert_eq!(observed, vec![ValidationStep::Signature]);
+        }
+
+        match verifier.verify(&token, &alg) {
+            Err(Error::TokenExpiredAt(_)) => {}
+            other => panic!("Expected TokenExpiredAt, got {other:?}"),
+        }
+    }
+
+    let mut prepared = PreparedVerifier::new(uniform, other_alg);
+    steps.lock().unwrap().clear();
+    match prepared.verify(&token) {
+        Err(Error::InvalidSignature()) => {}
+        other => panic!("Expected InvalidSignature, got {other:?}"),
+    }
+    assert_eq!(steps.lock().unwrap().len(), 4);
+}
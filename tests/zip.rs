@@ -0,0 +1,105 @@
+use serde_json::json;
+use serde_json::value::Value;
+
+use jsonwebtokens as jwt;
+use jwt::{error::Error, raw, Algorithm, AlgorithmID, Verifier};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+mod common;
+use common::get_time;
+
+// Compressed with Python's zlib (raw DEFLATE) and signed with the secret "secret"
+const PYTHON_TOKEN: &str = "eyJhbGciOiJIUzI1NiIsInppcCI6IkRFRiJ9.DcrBCoAgEIThV4k5l1LdfJXooLmQkCiuS0H07u1t_uF7kZjhcPZe2VmrJdQMPT7Xi8xRMkawBCXC1KZ5WfXwEuE2eJ0Bu4KjVFLSyMfhbqkTvh8.w8ewSsi7DT4wH-nBM0VWKIPp4JWpHp0bye8IH47_Lf8";
+
+#[test]
+fn verify_python_compressed_claims() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let verifier = Verifier::create().audience("a").build().unwrap();
+    let claims: Value = verifier.verify(PYTHON_TOKEN, &alg).unwrap();
+    assert_eq!(
+        claims,
+        json!({
+            "iss": "https://issuer.example.com",
+            "sub": "user-123",
+            "aud": ["a", "b"],
+            "scope": "read write",
+        })
+    );
+
+    let unverified = jwt::decode_unverified(PYTHON_TOKEN).unwrap();
+    assert_eq!(unverified.claims, claims);
+}
+
+#[test]
+fn round_trip_compressed_claims() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let claims = json!({
+        "sub": "b@b.com",
+        "exp": get_time() + 10000,
+        "roles": vec!["reader"; 100],
+    });
+    let token = jwt::encode_compressed(&json!({}), &claims, &alg).unwrap();
+    let uncompressed = jwt::encode(&json!({}), &claims, &alg).unwrap();
+    assert!(token.len() < uncompressed.len() / 4);
+    assert_eq!(raw::decode_header_only(&token).unwrap()["zip"], "DEF");
+
+    let verifier = Verifier::create().build().unwrap();
+    let verified: Value = verifier.verify(&token, &alg).unwrap();
+    assert_eq!(verified, claims);
+
+    // A default builder also has a (non-zero) decompressed size limit
+    let verifier = jwt::VerifierBuilder::default().build().unwrap();
+    let verified: Value = verifier.verify(&token, &alg).unwrap();
+    assert_eq!(verified, claims);
+}
+
+#[test]
+fn max_decompressed_size() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let claims = json!({ "padding": "a".repeat(1_000_000) });
+    let token = jwt::encode_compressed(&json!({}), &claims, &alg).unwrap();
+    assert!(token.len() < 10_000);
+
+    // The default limit rejects this decompression bomb
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {other:?}"),
+    }
+
+    let verifier = Verifier::create()
+        .max_decompressed_size(2_000_000)
+        .build()
+        .unwrap();
+    let verified: Value = verifier.verify(&token, &alg).unwrap();
+    assert_eq!(verified, claims);
+}
+
+#[test]
+fn unsupported_zip() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let token = jwt::encode(&json!({ "zip": "GZIP" }), &json!({}), &alg).unwrap();
+
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {other:?}"),
+    }
+}
+
+#[test]
+fn corrupt_compressed_claims() {
+    let alg = Algorithm::new_hmac(AlgorithmID::HS256, "secret").unwrap();
+    let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","zip":"DEF"}"#);
+    // A reserved (invalid) DEFLATE block type
+    let claims = URL_SAFE_NO_PAD.encode([0x07, 0x00]);
+    let message = format!("{header}.{claims}");
+    let token = format!("{message}.{}", alg.sign(&message).unwrap());
+
+    let verifier = Verifier::create().build().unwrap();
+    match verifier.verify(&token, &alg) {
+        Err(Error::MalformedToken(_)) => {}
+        other => panic!("Expected MalformedToken, got {other:?}"),
+    }
+}